@@ -0,0 +1,243 @@
+//! Micro-benchmark of split against interleaved complex storage for a
+//! binary contraction.
+//!
+//! strided-opteinsum stores complex128 operands interleaved, as
+//! `StridedArray<Complex64>`, and has no split (separate real and imaginary
+//! arrays) layout, so `create_operands` can't offer one. What a split layout
+//! would buy can still be measured one step at a time: the same product on
+//! split operands is a few real contractions. Per step shape this binary
+//! times
+//!
+//! - `interleaved`: `einsum2_into` on `Complex64` operands;
+//! - `split 4M`: four real `einsum2_into` calls,
+//!   `Cr = Ar Br - Ai Bi` and `Ci = Ar Bi + Ai Br`, accumulating with `beta`;
+//! - `split 3M`: three real calls and the element-wise sums around them,
+//!   `T1 = Ar Br`, `T2 = Ai Bi`, `Ci = (Ar + Ai)(Br + Bi) - T1 - T2`,
+//!   `Cr = T1 - T2` (the scheme of `zgemm3m`).
+//!
+//! Every variant writes into preallocated outputs; the split results are
+//! checked against the interleaved one after timing. Operands are filled
+//! with deterministic nonzero values.
+//!
+//! Build & run:
+//!   cargo run --release --bin complex_layout
+
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use num_complex::Complex64;
+use strided_view::StridedArray;
+
+/// Median of `nruns` timings of `run` after `warmup` discarded ones.
+fn median_ms(warmup: usize, nruns: usize, mut run: impl FnMut() -> Duration) -> f64 {
+    for _ in 0..warmup {
+        run();
+    }
+    let mut times: Vec<f64> = (0..nruns).map(|_| run().as_secs_f64() * 1e3).collect();
+    times.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    times[times.len() / 2]
+}
+
+struct StepShape {
+    name: &'static str,
+    ia: &'static str,
+    ib: &'static str,
+    ic: &'static str,
+    dims: &'static [(char, usize)],
+}
+
+const SHAPES: &[StepShape] = &[
+    StepShape {
+        name: "matmul 64",
+        ia: "ij",
+        ib: "jk",
+        ic: "ik",
+        dims: &[('i', 64), ('j', 64), ('k', 64)],
+    },
+    StepShape {
+        name: "matmul 256",
+        ia: "ij",
+        ib: "jk",
+        ic: "ik",
+        dims: &[('i', 256), ('j', 256), ('k', 256)],
+    },
+    StepShape {
+        name: "matmul 512",
+        ia: "ij",
+        ib: "jk",
+        ic: "ik",
+        dims: &[('i', 512), ('j', 512), ('k', 512)],
+    },
+    StepShape {
+        name: "batched matmul b32 64",
+        ia: "bij",
+        ib: "bjk",
+        ic: "bik",
+        dims: &[('b', 32), ('i', 64), ('j', 64), ('k', 64)],
+    },
+    // The late step of tensornetwork_permutation_light_415 (all dims 2), as in
+    // the tn_light_415_late_step case study.
+    StepShape {
+        name: "TN light 415 step 408",
+        ia: "caxydefghizjb",
+        ib: "hklicxmnopdqfrstyjuzgvwe",
+        ic: "abklwmnopqrstuvxyz",
+        dims: &[],
+    },
+];
+
+/// A real array of `dims` with deterministic values; `phase` tells operands apart.
+fn filled(dims: &[usize], phase: f64) -> StridedArray<f64> {
+    let mut arr = StridedArray::<f64>::col_major(dims);
+    for (k, x) in arr.data_mut().iter_mut().enumerate() {
+        *x = (k as f64 * 0.618 + phase).sin();
+    }
+    arr
+}
+
+/// The interleaved array with real part `re` and imaginary part `im`.
+fn interleave(re: &StridedArray<f64>, im: &StridedArray<f64>) -> StridedArray<Complex64> {
+    let mut arr = StridedArray::<Complex64>::col_major(re.dims());
+    for ((z, &r), &i) in arr.data_mut().iter_mut().zip(re.data()).zip(im.data()) {
+        *z = Complex64::new(r, i);
+    }
+    arr
+}
+
+/// `out = x + sign * y`, element-wise over column-major arrays.
+fn add(out: &mut StridedArray<f64>, x: &StridedArray<f64>, y: &StridedArray<f64>, sign: f64) {
+    for ((o, &a), &b) in out.data_mut().iter_mut().zip(x.data()).zip(y.data()) {
+        *o = a + sign * b;
+    }
+}
+
+fn main() {
+    let warmup = 3;
+    let nruns = 15;
+    println!("complex128 storage: interleaved vs split (median of {nruns}, {warmup} warmup)");
+    println!("{}", "=".repeat(94));
+    println!(
+        "{:<24} {:>10} {:>16} {:>14} {:>8} {:>14} {:>8}",
+        "step", "C elems", "interleaved (ms)", "split 4M (ms)", "4M/int", "split 3M (ms)", "3M/int"
+    );
+    for shape in SHAPES {
+        let dims: HashMap<char, usize> = shape.dims.iter().copied().collect();
+        let labels = |s: &str| s.chars().collect::<Vec<char>>();
+        let (ia, ib, ic) = (labels(shape.ia), labels(shape.ib), labels(shape.ic));
+        let extent = |ids: &[char]| {
+            ids.iter()
+                .map(|id| dims.get(id).copied().unwrap_or(2))
+                .collect::<Vec<usize>>()
+        };
+        let (a_dims, b_dims, c_dims) = (extent(&ia), extent(&ib), extent(&ic));
+        let (ar, ai) = (filled(&a_dims, 0.0), filled(&a_dims, 1.0));
+        let (br, bi) = (filled(&b_dims, 2.0), filled(&b_dims, 3.0));
+        let (a, b) = (interleave(&ar, &ai), interleave(&br, &bi));
+        let real = |c: &mut StridedArray<f64>,
+                    x: &StridedArray<f64>,
+                    y: &StridedArray<f64>,
+                    alpha: f64,
+                    beta: f64| {
+            strided_einsum2::einsum2_into(
+                c.view_mut(),
+                &x.view(),
+                &y.view(),
+                &ic,
+                &ia,
+                &ib,
+                alpha,
+                beta,
+            )
+            .unwrap();
+        };
+
+        let mut c = StridedArray::<Complex64>::col_major(&c_dims);
+        let interleaved_run = || {
+            let t = Instant::now();
+            strided_einsum2::einsum2_into(
+                c.view_mut(),
+                &a.view(),
+                &b.view(),
+                &ic,
+                &ia,
+                &ib,
+                Complex64::new(1.0, 0.0),
+                Complex64::new(0.0, 0.0),
+            )
+            .unwrap();
+            let elapsed = t.elapsed();
+            black_box(&c);
+            elapsed
+        };
+        let interleaved = median_ms(warmup, nruns, interleaved_run);
+        let reference = c.data().to_vec();
+
+        let (mut cr, mut ci) = (
+            StridedArray::<f64>::col_major(&c_dims),
+            StridedArray::<f64>::col_major(&c_dims),
+        );
+        let split_4m_run = || {
+            let t = Instant::now();
+            real(&mut cr, &ar, &br, 1.0, 0.0);
+            real(&mut cr, &ai, &bi, -1.0, 1.0);
+            real(&mut ci, &ar, &bi, 1.0, 0.0);
+            real(&mut ci, &ai, &br, 1.0, 1.0);
+            let elapsed = t.elapsed();
+            black_box((&cr, &ci));
+            elapsed
+        };
+        let split_4m = median_ms(warmup, nruns, split_4m_run);
+        let check = |cr: &StridedArray<f64>, ci: &StridedArray<f64>, variant: &str| {
+            let scale = reference.iter().map(|z| z.norm()).fold(0.0, f64::max);
+            let diff = reference
+                .iter()
+                .zip(cr.data().iter().zip(ci.data()))
+                .map(|(z, (&r, &i))| (z - Complex64::new(r, i)).norm())
+                .fold(0.0, f64::max);
+            assert!(
+                diff <= 1e-10 * scale.max(1.0),
+                "{}: {variant} differs by {diff:e}",
+                shape.name
+            );
+        };
+        check(&cr, &ci, "split 4M");
+
+        let (mut t1, mut t2) = (
+            StridedArray::<f64>::col_major(&c_dims),
+            StridedArray::<f64>::col_major(&c_dims),
+        );
+        let (mut sa, mut sb) = (
+            StridedArray::<f64>::col_major(&a_dims),
+            StridedArray::<f64>::col_major(&b_dims),
+        );
+        let split_3m_run = || {
+            let t = Instant::now();
+            real(&mut t1, &ar, &br, 1.0, 0.0);
+            real(&mut t2, &ai, &bi, 1.0, 0.0);
+            add(&mut sa, &ar, &ai, 1.0);
+            add(&mut sb, &br, &bi, 1.0);
+            real(&mut ci, &sa, &sb, 1.0, 0.0);
+            for ((c, &x), &y) in ci.data_mut().iter_mut().zip(t1.data()).zip(t2.data()) {
+                *c -= x + y;
+            }
+            add(&mut cr, &t1, &t2, -1.0);
+            let elapsed = t.elapsed();
+            black_box((&cr, &ci));
+            elapsed
+        };
+        let split_3m = median_ms(warmup, nruns, split_3m_run);
+        check(&cr, &ci, "split 3M");
+
+        println!(
+            "{:<24} {:>10} {:>16.3} {:>14.3} {:>7.2}x {:>14.3} {:>7.2}x",
+            shape.name,
+            c_dims.iter().product::<usize>(),
+            interleaved,
+            split_4m,
+            split_4m / interleaved,
+            split_3m,
+            split_3m / interleaved
+        );
+    }
+}
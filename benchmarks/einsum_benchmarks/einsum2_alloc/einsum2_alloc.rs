@@ -0,0 +1,198 @@
+//! Micro-benchmark of the output allocation in a binary einsum.
+//!
+//! strided-einsum2 only has in-place entry points (`einsum2_into`,
+//! `einsum2_into_owned`): the caller passes the output view. The allocating
+//! path is therefore the one every caller without a buffer takes, and the
+//! one strided-opteinsum's tree evaluator takes per step: allocate a
+//! zero-filled column-major output, then contract into it. Per step shape
+//! this binary times
+//!
+//! - `reused C`: `einsum2_into_owned` into one preallocated output, written
+//!   again every run (`beta = 0`);
+//! - `fresh C`: `StridedArray::col_major` plus the same call, i.e. what an
+//!   allocating `einsum2` would do;
+//! - `opteinsum`: the same contraction as a two-leaf `EinsumCode`, the
+//!   allocating API the main runner calls.
+//!
+//! Operands are cloned outside the timed region in all three, so the
+//! difference between the first two is the allocation and first touch of
+//! the output alone.
+//!
+//! Build & run:
+//!   cargo run --release --bin einsum2_alloc
+
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use strided_opteinsum::{EinsumCode, EinsumNode, EinsumOperand};
+use strided_view::StridedArray;
+
+/// Median of `nruns` timings of `run` after `warmup` discarded ones; `setup`
+/// builds each run's input outside the timed region.
+fn median_ms<S, F, I>(warmup: usize, nruns: usize, mut setup: S, mut run: F) -> f64
+where
+    S: FnMut() -> I,
+    F: FnMut(I) -> Duration,
+{
+    for _ in 0..warmup {
+        run(setup());
+    }
+    let mut times: Vec<f64> = (0..nruns)
+        .map(|_| run(setup()).as_secs_f64() * 1e3)
+        .collect();
+    times.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    times[times.len() / 2]
+}
+
+struct StepShape {
+    name: &'static str,
+    ia: &'static str,
+    ib: &'static str,
+    ic: &'static str,
+    dims: &'static [(char, usize)],
+}
+
+const SHAPES: &[StepShape] = &[
+    StepShape {
+        name: "matmul 64",
+        ia: "ij",
+        ib: "jk",
+        ic: "ik",
+        dims: &[('i', 64), ('j', 64), ('k', 64)],
+    },
+    StepShape {
+        name: "matmul 512",
+        ia: "ij",
+        ib: "jk",
+        ic: "ik",
+        dims: &[('i', 512), ('j', 512), ('k', 512)],
+    },
+    StepShape {
+        name: "batched matmul b32 64",
+        ia: "bij",
+        ib: "bjk",
+        ic: "bik",
+        dims: &[('b', 32), ('i', 64), ('j', 64), ('k', 64)],
+    },
+    StepShape {
+        name: "outer product 2048",
+        ia: "i",
+        ib: "j",
+        ic: "ij",
+        dims: &[('i', 2048), ('j', 2048)],
+    },
+    // The late step of tensornetwork_permutation_light_415 (all dims 2), as in
+    // the tn_light_415_late_step case study.
+    StepShape {
+        name: "TN light 415 step 408",
+        ia: "caxydefghizjb",
+        ib: "hklicxmnopdqfrstyjuzgvwe",
+        ic: "abklwmnopqrstuvxyz",
+        dims: &[],
+    },
+];
+
+fn main() {
+    let warmup = 3;
+    let nruns = 15;
+    println!("einsum2 output allocation: reused vs fresh C (median of {nruns}, {warmup} warmup)");
+    println!("{}", "=".repeat(92));
+    println!(
+        "{:<24} {:>10} {:>14} {:>12} {:>10} {:>14} {:>9}",
+        "step", "C elems", "reused C (ms)", "fresh C (ms)", "alloc", "opteinsum (ms)", "vs reused"
+    );
+    for shape in SHAPES {
+        let dims: HashMap<char, usize> = shape.dims.iter().copied().collect();
+        let labels = |s: &str| s.chars().collect::<Vec<char>>();
+        let (ia, ib, ic) = (labels(shape.ia), labels(shape.ib), labels(shape.ic));
+        let extent = |ids: &[char]| {
+            ids.iter()
+                .map(|id| dims.get(id).copied().unwrap_or(2))
+                .collect::<Vec<usize>>()
+        };
+        let (a_dims, b_dims, c_dims) = (extent(&ia), extent(&ib), extent(&ic));
+        let a = StridedArray::<f64>::col_major(&a_dims);
+        let b = StridedArray::<f64>::col_major(&b_dims);
+        let operands = || (a.clone(), b.clone());
+
+        let mut c = StridedArray::<f64>::col_major(&c_dims);
+        let reused = median_ms(warmup, nruns, operands, |(a, b)| {
+            let t = Instant::now();
+            strided_einsum2::einsum2_into_owned(
+                c.view_mut(),
+                a,
+                b,
+                &ic,
+                &ia,
+                &ib,
+                1.0,
+                0.0,
+                false,
+                false,
+            )
+            .unwrap();
+            let elapsed = t.elapsed();
+            black_box(&c);
+            elapsed
+        });
+
+        let fresh = median_ms(warmup, nruns, operands, |(a, b)| {
+            let t = Instant::now();
+            let mut c = StridedArray::<f64>::col_major(&c_dims);
+            strided_einsum2::einsum2_into_owned(
+                c.view_mut(),
+                a,
+                b,
+                &ic,
+                &ia,
+                &ib,
+                1.0,
+                0.0,
+                false,
+                false,
+            )
+            .unwrap();
+            let elapsed = t.elapsed();
+            drop(black_box(c));
+            elapsed
+        });
+
+        let leaf = |ids: &[char], tensor_index| EinsumNode::Leaf {
+            ids: ids.to_vec(),
+            tensor_index,
+        };
+        let code = EinsumCode {
+            root: EinsumNode::Contract {
+                args: vec![leaf(&ia, 0), leaf(&ib, 1)],
+            },
+            output_ids: ic.clone(),
+        };
+        let opteinsum = median_ms(
+            warmup,
+            nruns,
+            || {
+                let (a, b) = operands();
+                vec![EinsumOperand::from(a), EinsumOperand::from(b)]
+            },
+            |ops| {
+                let t = Instant::now();
+                let out = code.evaluate(ops, None).unwrap();
+                let elapsed = t.elapsed();
+                drop(black_box(out));
+                elapsed
+            },
+        );
+
+        println!(
+            "{:<24} {:>10} {:>14.3} {:>12.3} {:>9.1}% {:>14.3} {:>8.2}x",
+            shape.name,
+            c_dims.iter().product::<usize>(),
+            reused,
+            fresh,
+            100.0 * (fresh - reused) / reused,
+            opteinsum,
+            opteinsum / reused
+        );
+    }
+}
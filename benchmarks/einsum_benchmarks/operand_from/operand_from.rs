@@ -0,0 +1,141 @@
+//! Micro-benchmark of wrapping operands in `EinsumOperand` against the
+//! tensor count.
+//!
+//! The runner's `create_operands` allocates each operand as a
+//! `StridedArray` and wraps it with `EinsumOperand::from` before the timed
+//! contraction. For networks of many tiny tensors the wrapping could matter
+//! next to the contraction itself. Per tensor count `n` (`n` 2 x 2 matrices
+//! of the chain `ab,bc,...`) this binary times
+//!
+//! - `alloc`: `StridedArray::col_major` for every operand, nothing else;
+//! - `from`: `EinsumOperand::from` over the already allocated arrays,
+//!   collected into the `Vec` `evaluate` takes;
+//! - `create`: both in one pass, which is what `create_operands` does;
+//! - `contract`: `EinsumCode::evaluate` of the whole chain along the linear
+//!   `((T0 T1) T2) ...` path, operands built outside the timed region.
+//!
+//! Dropping the results happens outside the timed region everywhere.
+//!
+//! Build & run:
+//!   cargo run --release --bin operand_from
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use strided_opteinsum::{EinsumCode, EinsumOperand};
+use strided_view::StridedArray;
+
+#[allow(dead_code)]
+#[path = "../../../src/tree.rs"]
+mod tree;
+
+/// Median of `nruns` timings of `run` after `warmup` discarded ones; `setup`
+/// builds each run's input outside the timed region.
+fn median_us<S, F, I>(warmup: usize, nruns: usize, mut setup: S, mut run: F) -> f64
+where
+    S: FnMut() -> I,
+    F: FnMut(I) -> Duration,
+{
+    for _ in 0..warmup {
+        run(setup());
+    }
+    let mut times: Vec<f64> = (0..nruns)
+        .map(|_| run(setup()).as_secs_f64() * 1e6)
+        .collect();
+    times.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    times[times.len() / 2]
+}
+
+fn arrays(n: usize) -> Vec<StridedArray<f64>> {
+    (0..n).map(|_| StridedArray::col_major(&[2, 2])).collect()
+}
+
+fn main() {
+    let warmup = 3;
+    let nruns = 21;
+    println!(
+        "EinsumOperand::from vs contraction, 2x2 matrix chain (median of {nruns}, {warmup} warmup)"
+    );
+    println!("{}", "=".repeat(86));
+    println!(
+        "{:>8} {:>11} {:>10} {:>7} {:>12} {:>14} {:>14}",
+        "tensors",
+        "alloc (us)",
+        "from (us)",
+        "ns/op",
+        "create (us)",
+        "contract (us)",
+        "from/contract"
+    );
+    for n in [16, 64, 256, 1_024, 2_048] {
+        // Labels past `z` come from Latin Extended, as in `gen-chain`.
+        let labels: Vec<char> = (0..=n as u32)
+            .map(|k| char::from_u32(0x100 + k).unwrap())
+            .collect();
+        let inputs: Vec<Vec<char>> = (0..n).map(|k| vec![labels[k], labels[k + 1]]).collect();
+        let linear: Vec<[usize; 2]> = (0..n - 1)
+            .map(|step| if step == 0 { [0, 1] } else { [0, n - step - 1] })
+            .collect();
+        let code = EinsumCode {
+            root: tree::build_contraction_tree(&inputs, &linear),
+            output_ids: vec![labels[0], labels[n]],
+        };
+
+        let alloc = median_us(
+            warmup,
+            nruns,
+            || (),
+            |()| {
+                let t = Instant::now();
+                let arrays = arrays(n);
+                let elapsed = t.elapsed();
+                drop(black_box(arrays));
+                elapsed
+            },
+        );
+        let from = median_us(
+            warmup,
+            nruns,
+            || arrays(n),
+            |arrays| {
+                let t = Instant::now();
+                let operands: Vec<EinsumOperand> =
+                    arrays.into_iter().map(EinsumOperand::from).collect();
+                let elapsed = t.elapsed();
+                drop(black_box(operands));
+                elapsed
+            },
+        );
+        let create = median_us(
+            warmup,
+            nruns,
+            || (),
+            |()| {
+                let t = Instant::now();
+                let operands: Vec<EinsumOperand> = (0..n)
+                    .map(|_| EinsumOperand::from(StridedArray::<f64>::col_major(&[2, 2])))
+                    .collect();
+                let elapsed = t.elapsed();
+                drop(black_box(operands));
+                elapsed
+            },
+        );
+        let contract = median_us(
+            warmup,
+            nruns,
+            || arrays(n).into_iter().map(EinsumOperand::from).collect(),
+            |operands: Vec<EinsumOperand>| {
+                let t = Instant::now();
+                let out = code.evaluate(operands, None).unwrap();
+                let elapsed = t.elapsed();
+                drop(black_box(out));
+                elapsed
+            },
+        );
+        println!(
+            "{n:>8} {alloc:>11.2} {from:>10.2} {:>7.1} {create:>12.2} {contract:>14.1} {:>13.2}%",
+            from * 1e3 / n as f64,
+            100.0 * from / contract
+        );
+    }
+}
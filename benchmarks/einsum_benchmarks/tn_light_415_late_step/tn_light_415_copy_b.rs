@@ -0,0 +1,239 @@
+//! Copy variants for the scattered-stride B tensor of the TN light 415 late step.
+//!
+//! `tn_light_415_late_step` shows the `strided_perm::copy_into` of B (24 binary
+//! dims, 16M elements, permuted to canonical order) dominating the step. This
+//! binary times the same copy three ways to see whether `copy_into` leaves
+//! performance on the table:
+//!
+//! - `copy_into`: `strided_perm::copy_into`, as used by `prepare_input_owned`.
+//! - nested loop: a plain odometer over the destination in column-major order.
+//! - block-tiled: the leading destination dims and the smallest-stride source
+//!   dims form a tile small enough to stay in cache, so both the reads and the
+//!   writes of a tile touch a few contiguous runs.
+//!
+//! Every variant is checked against `copy_into` before timing, and destination
+//! buffers are allocated outside the timed region.
+//!
+//! Build & run:
+//!   cargo run --release --bin tn_light_415_copy_b
+
+use std::hint::black_box;
+use std::time::Instant;
+use strided_view::StridedArray;
+
+/// Destination and source elements per tile side: 2^6 = 64 each.
+const TILE_DIMS: usize = 6;
+
+fn bench<F: FnMut()>(mut f: F, warmup: usize, nruns: usize) -> (f64, f64) {
+    for _ in 0..warmup {
+        f();
+    }
+    let mut times = Vec::with_capacity(nruns);
+    for _ in 0..nruns {
+        let t = Instant::now();
+        f();
+        times.push(t.elapsed().as_secs_f64() * 1000.0);
+    }
+    times.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let med = times[times.len() / 2];
+    let q1 = times[times.len() / 4];
+    let q3 = times[3 * times.len() / 4];
+    (med, q3 - q1)
+}
+
+fn col_major_strides(dims: &[usize]) -> Vec<isize> {
+    let mut strides = Vec::with_capacity(dims.len());
+    let mut s = 1isize;
+    for &d in dims {
+        strides.push(s);
+        s *= d as isize;
+    }
+    strides
+}
+
+/// `dst[i] = src[offset(i)]`, walking `dims` with a column-major odometer.
+fn nested_loop_copy(
+    dst: &mut [f64],
+    src: &[f64],
+    dims: &[usize],
+    src_strides: &[isize],
+    dst_strides: &[isize],
+) {
+    let mut idx = vec![0usize; dims.len()];
+    let mut src_off = 0isize;
+    let mut dst_off = 0isize;
+    for _ in 0..dims.iter().product::<usize>() {
+        dst[dst_off as usize] = src[src_off as usize];
+        for d in 0..dims.len() {
+            idx[d] += 1;
+            src_off += src_strides[d];
+            dst_off += dst_strides[d];
+            if idx[d] < dims[d] {
+                break;
+            }
+            src_off -= idx[d] as isize * src_strides[d];
+            dst_off -= idx[d] as isize * dst_strides[d];
+            idx[d] = 0;
+        }
+    }
+}
+
+/// `(src, dst)` offsets of every index combination over `axes`.
+fn offset_table(
+    axes: &[usize],
+    dims: &[usize],
+    src_strides: &[isize],
+    dst_strides: &[isize],
+) -> Vec<(isize, isize)> {
+    let mut table = vec![(0isize, 0isize)];
+    for &a in axes {
+        let step = (src_strides[a], dst_strides[a]);
+        table = (0..dims[a] as isize)
+            .flat_map(|i| {
+                table
+                    .iter()
+                    .map(move |&(s, d)| (s + i * step.0, d + i * step.1))
+            })
+            .collect();
+    }
+    table
+}
+
+/// Block-tiled copy: the first `TILE_DIMS` destination dims and the
+/// `TILE_DIMS` remaining dims with the smallest source strides make up one
+/// tile, walked source-major outside and destination-major inside; an
+/// odometer covers the rest.
+fn tiled_copy(
+    dst: &mut [f64],
+    src: &[f64],
+    dims: &[usize],
+    src_strides: &[isize],
+    dst_strides: &[isize],
+) {
+    let rank = dims.len();
+    let dst_axes: Vec<usize> = (0..TILE_DIMS.min(rank)).collect();
+    let mut rest: Vec<usize> = (dst_axes.len()..rank).collect();
+    rest.sort_by_key(|&a| src_strides[a].unsigned_abs());
+    let src_axes: Vec<usize> = rest.drain(..TILE_DIMS.min(rest.len())).collect();
+
+    let inner = offset_table(&dst_axes, dims, src_strides, dst_strides);
+    let outer = offset_table(&src_axes, dims, src_strides, dst_strides);
+    let rest_dims: Vec<usize> = rest.iter().map(|&a| dims[a]).collect();
+    let rest_src: Vec<isize> = rest.iter().map(|&a| src_strides[a]).collect();
+    let rest_dst: Vec<isize> = rest.iter().map(|&a| dst_strides[a]).collect();
+
+    let mut idx = vec![0usize; rest.len()];
+    let mut src_base = 0isize;
+    let mut dst_base = 0isize;
+    for _ in 0..rest_dims.iter().product::<usize>() {
+        for &(so, do_) in &outer {
+            for &(si, di) in &inner {
+                dst[(dst_base + do_ + di) as usize] = src[(src_base + so + si) as usize];
+            }
+        }
+        for d in 0..rest.len() {
+            idx[d] += 1;
+            src_base += rest_src[d];
+            dst_base += rest_dst[d];
+            if idx[d] < rest_dims[d] {
+                break;
+            }
+            src_base -= idx[d] as isize * rest_src[d];
+            dst_base -= idx[d] as isize * rest_dst[d];
+            idx[d] = 0;
+        }
+    }
+}
+
+fn main() {
+    let warmup = 3;
+    let nruns = 15;
+
+    // B: 24 dims of size 2, col-major, filled with its linear index so a
+    // misplaced element is caught.
+    let b_dims: Vec<usize> = vec![2; 24];
+    let mut b = StridedArray::<f64>::col_major(&b_dims);
+    for (i, x) in b.data_mut().iter_mut().enumerate() {
+        *x = i as f64;
+    }
+    // Same right_perm as `tn_light_415_late_step`.
+    let right_perm: Vec<usize> = vec![
+        4, 10, 23, 12, 20, 0, 3, 17, 1, 2, 6, 7, 8, 9, 11, 13, 14, 15, 18, 21, 22, 5, 16, 19,
+    ];
+    let b_perm = b.permuted(&right_perm).unwrap();
+    let dims = b_perm.dims().to_vec();
+    let src_strides = b_perm.strides().to_vec();
+    let dst_strides = col_major_strides(&dims);
+    let total: usize = dims.iter().product();
+
+    println!("TN light 415 late-step: copy variants for scattered B (all dims=2)");
+    println!("B after canonical reorder: dims={dims:?} strides={src_strides:?}");
+    println!("{}", "=".repeat(70));
+
+    let mut reference = StridedArray::<f64>::col_major(&dims);
+    strided_perm::copy_into(&mut reference.view_mut(), &b_perm.view()).unwrap();
+
+    let mut dst = StridedArray::<f64>::col_major(&dims);
+    let (med_copy, iqr_copy) = bench(
+        || {
+            strided_perm::copy_into(&mut dst.view_mut(), &b_perm.view()).unwrap();
+            black_box(dst.data());
+        },
+        warmup,
+        nruns,
+    );
+    println!(
+        "copy_into (16M, scattered):    {:.3} ms (IQR {:.3} ms)",
+        med_copy, iqr_copy
+    );
+
+    let mut dst = vec![0.0f64; total];
+    nested_loop_copy(&mut dst, b_perm.data(), &dims, &src_strides, &dst_strides);
+    assert_eq!(dst, reference.data(), "nested loop copy mismatch");
+    let (med_loop, iqr_loop) = bench(
+        || {
+            nested_loop_copy(&mut dst, b_perm.data(), &dims, &src_strides, &dst_strides);
+            black_box(&dst);
+        },
+        warmup,
+        nruns,
+    );
+    println!(
+        "nested loop:                   {:.3} ms (IQR {:.3} ms)",
+        med_loop, iqr_loop
+    );
+
+    let mut dst = vec![0.0f64; total];
+    tiled_copy(&mut dst, b_perm.data(), &dims, &src_strides, &dst_strides);
+    assert_eq!(dst, reference.data(), "tiled copy mismatch");
+    let (med_tiled, iqr_tiled) = bench(
+        || {
+            tiled_copy(&mut dst, b_perm.data(), &dims, &src_strides, &dst_strides);
+            black_box(&dst);
+        },
+        warmup,
+        nruns,
+    );
+    println!(
+        "block-tiled ({}x{} tile):      {:.3} ms (IQR {:.3} ms)",
+        1 << TILE_DIMS,
+        1 << TILE_DIMS,
+        med_tiled,
+        iqr_tiled
+    );
+
+    // --- Summary ---
+    let bytes = (2 * total * std::mem::size_of::<f64>()) as f64;
+    println!("\n--- Summary (relative to copy_into, read+write GB/s) ---");
+    for (name, med) in [
+        ("copy_into", med_copy),
+        ("nested loop", med_loop),
+        ("block-tiled", med_tiled),
+    ] {
+        println!(
+            "  {name:<12} {med:>9.3} ms  {:>5.2}x  {:>6.2} GB/s",
+            med / med_copy,
+            bytes / (med * 1e-3) / 1e9
+        );
+    }
+}
@@ -0,0 +1,75 @@
+//! Micro-benchmark of `build_contraction_tree` against the tensor count.
+//!
+//! The original implementation removed two nodes from a `Vec` per path step
+//! and appended one, so a step cost up to O(n) shuffling and a whole path
+//! up to O(n^2); nodes now stay in fixed slots. This binary builds trees for
+//! synthetic networks of growing size with two path shapes:
+//!
+//! - `[0, 1]` every step (greedy-like, always the front of the list): both
+//!   removals shifted every remaining node, the worst case.
+//! - linear `((T0 T1) T2) ...` as used by `gen`: `[0, n - 1]` after the
+//!   first step, so only the front removal shifted.
+//!
+//! A steady `ns/n^2` column means a quadratic term dominates; a falling one
+//! means it is gone.
+//!
+//! Build & run:
+//!   cargo run --release --bin tree_build
+
+use std::hint::black_box;
+use std::time::Instant;
+
+#[allow(dead_code)]
+#[path = "../../../src/tree.rs"]
+mod tree;
+
+fn median_us<F: FnMut()>(mut f: F, nruns: usize) -> f64 {
+    f();
+    let mut times: Vec<f64> = (0..nruns)
+        .map(|_| {
+            let t = Instant::now();
+            f();
+            t.elapsed().as_secs_f64() * 1e6
+        })
+        .collect();
+    times.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    times[times.len() / 2]
+}
+
+fn main() {
+    let nruns = 9;
+    println!("build_contraction_tree: time vs tensor count (median of {nruns})");
+    println!("{}", "=".repeat(70));
+    println!(
+        "{:>8} {:>14} {:>10} {:>14} {:>10}",
+        "tensors", "[0,1] (us)", "ns/n^2", "linear (us)", "ns/n^2"
+    );
+    for n in [100, 300, 1_000, 3_000, 10_000, 30_000] {
+        // A ring of rank-2 tensors: labels only need to be distinct per bond.
+        let labels: Vec<char> = (0..n as u32)
+            .map(|k| char::from_u32(0x100 + k).unwrap())
+            .collect();
+        let inputs: Vec<Vec<char>> = (0..n)
+            .map(|k| vec![labels[k], labels[(k + 1) % n]])
+            .collect();
+        let front: Vec<[usize; 2]> = vec![[0, 1]; n - 1];
+        let linear: Vec<[usize; 2]> = (0..n - 1)
+            .map(|step| if step == 0 { [0, 1] } else { [0, n - step - 1] })
+            .collect();
+
+        let t_front = median_us(
+            || drop(black_box(tree::build_contraction_tree(&inputs, &front))),
+            nruns,
+        );
+        let t_linear = median_us(
+            || drop(black_box(tree::build_contraction_tree(&inputs, &linear))),
+            nruns,
+        );
+        let per_n2 = |us: f64| us * 1e3 / (n as f64 * n as f64);
+        println!(
+            "{n:>8} {t_front:>14.1} {:>10.4} {t_linear:>14.1} {:>10.4}",
+            per_n2(t_front),
+            per_n2(t_linear)
+        );
+    }
+}
@@ -0,0 +1,73 @@
+//! `--cpu-set`: restrict the process to a list of cores.
+//!
+//! The mask is set on the main thread before the rayon pool and the BLAS
+//! library start their workers, which inherit it. `std::thread::available_parallelism`
+//! (and so rayon's default pool size) honours the mask on Linux.
+
+/// Parse a core list: comma-separated ids and inclusive ranges (`0,2,4-7`).
+pub fn parse_cpu_list(raw: &str) -> Result<Vec<usize>, String> {
+    let mut cpus = Vec::new();
+    for part in raw.split(',').map(str::trim) {
+        let (lo, hi) = match part.split_once('-') {
+            Some((lo, hi)) => (lo, hi),
+            None => (part, part),
+        };
+        let parse = |s: &str| {
+            s.trim()
+                .parse::<usize>()
+                .map_err(|_| format!("invalid core id {s:?} in {raw:?}"))
+        };
+        let (lo, hi) = (parse(lo)?, parse(hi)?);
+        if lo > hi {
+            return Err(format!("empty core range {part:?}"));
+        }
+        for cpu in lo..=hi {
+            if !cpus.contains(&cpu) {
+                cpus.push(cpu);
+            }
+        }
+    }
+    Ok(cpus)
+}
+
+/// Restrict the calling thread (and every thread it starts later) to `cpus`.
+#[cfg(target_os = "linux")]
+pub fn restrict(cpus: &[usize]) -> Result<(), String> {
+    // SAFETY: `cpu_set_t` is plain bits; CPU_ZERO/CPU_SET only touch the
+    // local set, and pid 0 is the calling thread.
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_ZERO(&mut set);
+        let max = 8 * std::mem::size_of::<libc::cpu_set_t>();
+        for &cpu in cpus {
+            if cpu >= max {
+                return Err(format!(
+                    "core {cpu} is beyond the affinity mask ({max} cores)"
+                ));
+            }
+            libc::CPU_SET(cpu, &mut set);
+        }
+        if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+            return Err(std::io::Error::last_os_error().to_string());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn restrict(_cpus: &[usize]) -> Result<(), String> {
+    Err("CPU affinity is only supported on Linux".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn core_lists_take_ids_and_ranges() {
+        assert_eq!(parse_cpu_list("0,2,4,6").unwrap(), vec![0, 2, 4, 6]);
+        assert_eq!(parse_cpu_list("1-3, 2,8").unwrap(), vec![1, 2, 3, 8]);
+        assert!(parse_cpu_list("3-1").is_err());
+        assert!(parse_cpu_list("0,,1").is_err());
+    }
+}
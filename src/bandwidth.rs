@@ -0,0 +1,51 @@
+//! `--peak-bandwidth auto`: measure the memory bandwidth `GB/s` is compared
+//! against.
+//!
+//! The reference is the best plain copy the library itself achieves: a
+//! `copy_into` between two contiguous column-major buffers, far larger than
+//! any cache, counting one read and one write per element. Copy-bound
+//! instances can't beat it by much, so their `% peak` says how close the
+//! permutation-heavy paths get to streaming speed.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use strided_view::StridedArray;
+
+/// `f64` elements per calibration buffer: 128 MB each.
+pub const CALIBRATION_ELEMENTS: usize = 1 << 24;
+
+/// Timed copies; the fastest one counts.
+pub const CALIBRATION_RUNS: usize = 5;
+
+/// GB/s of the fastest of `runs` contiguous copies of `elements` `f64`s,
+/// after one untimed copy that faults the pages in.
+pub fn copy_gbps(elements: usize, runs: usize) -> f64 {
+    let src = StridedArray::<f64>::from_fn_col_major(&[elements], |i| i[0] as f64);
+    let mut dst = StridedArray::<f64>::col_major(&[elements]);
+    let mut copy = || {
+        strided_perm::copy_into(&mut dst.view_mut(), &src.view()).expect("same shape");
+        black_box(&dst);
+    };
+    copy();
+    let best = (0..runs)
+        .map(|_| {
+            let t0 = Instant::now();
+            copy();
+            t0.elapsed().as_secs_f64()
+        })
+        .fold(f64::INFINITY, f64::min);
+    let bytes = 2.0 * (elements * std::mem::size_of::<f64>()) as f64;
+    bytes / best / 1e9
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_copy_reports_a_finite_rate() {
+        let gbps = copy_gbps(1 << 12, 3);
+        assert!(gbps.is_finite() && gbps > 0.0, "{gbps}");
+    }
+}
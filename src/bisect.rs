@@ -0,0 +1,207 @@
+//! `--bisect`: binary-search a family of scaled instances for the size at
+//! which time per FLOP jumps.
+//!
+//! A family is the instances whose names agree once every run of digits is
+//! ignored (`bin_matmul_256` and `bin_matmul_1024`), sorted by the
+//! `opt_flops` path's `log2_size`. The smallest member is the baseline; a
+//! member is slow when its time per FLOP exceeds `--bisect-threshold` times
+//! the baseline's. The search assumes one cliff, fast below it and slow from
+//! it on, and times about `log2(n) + 2` of the `n` members to find it.
+
+use std::collections::BTreeMap;
+
+use tracing::warn;
+
+use crate::cli::Options;
+use crate::{run_instance, BenchmarkInstance};
+
+/// `name` with every run of digits replaced by `#`.
+pub fn family_key(name: &str) -> String {
+    let mut key = String::with_capacity(name.len());
+    for c in name.chars() {
+        if !c.is_ascii_digit() {
+            key.push(c);
+        } else if !key.ends_with('#') {
+            key.push('#');
+        }
+    }
+    key
+}
+
+/// Families of at least two instances that run `opt_flops`, each sorted by
+/// `log2_size` (then name).
+pub fn families(instances: &[BenchmarkInstance]) -> BTreeMap<String, Vec<&BenchmarkInstance>> {
+    let mut groups: BTreeMap<String, Vec<&BenchmarkInstance>> = BTreeMap::new();
+    for instance in instances.iter().filter(|i| !i.skips("opt_flops")) {
+        groups
+            .entry(family_key(&instance.name))
+            .or_default()
+            .push(instance);
+    }
+    groups.retain(|_, members| members.len() >= 2);
+    for members in groups.values_mut() {
+        members.sort_by(|a, b| {
+            let size = |i: &BenchmarkInstance| i.paths.opt_flops.log2_size;
+            size(a)
+                .total_cmp(&size(b))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+    }
+    groups
+}
+
+/// The first of `n` members for which `is_slow` holds, member 0 being fast
+/// by definition; `None` when the last member is not slow. Only members the
+/// search needs are asked about, the last one first.
+pub fn first_slow<E>(
+    n: usize,
+    mut is_slow: impl FnMut(usize) -> Result<bool, E>,
+) -> Result<Option<usize>, E> {
+    if n < 2 || !is_slow(n - 1)? {
+        return Ok(None);
+    }
+    let (mut fast, mut slow) = (0, n - 1);
+    while slow - fast > 1 {
+        let mid = fast + (slow - fast) / 2;
+        if is_slow(mid)? {
+            slow = mid;
+        } else {
+            fast = mid;
+        }
+    }
+    Ok(Some(slow))
+}
+
+/// Median milliseconds per 10^9 FLOPs of one member.
+fn ms_per_gflop(instance: &BenchmarkInstance, opts: &Options) -> Result<(f64, f64), String> {
+    let path_meta = &instance.paths.opt_flops;
+    let result = run_instance(instance, path_meta, opts, None).map_err(|e| e.to_string())?;
+    let gflops = 10f64.powf(path_meta.log10_flops) / 1e9;
+    Ok((result.median_ms, result.median_ms / gflops))
+}
+
+/// Search every family and print the members timed and the boundary found.
+pub fn run(opts: &Options, instances: &[BenchmarkInstance]) {
+    let opts = opts.for_strategy("opt_flops");
+    let groups = families(instances);
+    if groups.is_empty() {
+        warn!("--bisect: no family of two or more instances (names equal up to digits)");
+        return;
+    }
+    for (key, members) in groups {
+        println!();
+        println!(
+            "Bisect: {key} ({} instances, opt_flops, slow above {}x the smallest's time per FLOP)",
+            members.len(),
+            opts.bisect_threshold
+        );
+        println!(
+            "{:<50} {:>12} {:>12} {:>14} {:>8}",
+            "Instance", "log2SIZE", "Median (ms)", "ms per GFLOP", "Ratio"
+        );
+        println!("{}", "-".repeat(100));
+        // The ratio cell ends in `x`, so rows never end in two plain numbers.
+        let row = |instance: &BenchmarkInstance, ms: f64, per_gflop: f64, ratio: f64| {
+            println!(
+                "{:<50} {:>12.2} {ms:>12.3} {per_gflop:>14.3} {:>8}",
+                instance.name,
+                instance.paths.opt_flops.log2_size,
+                format!("{ratio:.2}x")
+            );
+        };
+        let base = match ms_per_gflop(members[0], &opts) {
+            Ok((ms, per_gflop)) => {
+                row(members[0], ms, per_gflop, 1.0);
+                per_gflop
+            }
+            Err(e) => {
+                warn!(instance = %members[0].name, "--bisect: {e}");
+                continue;
+            }
+        };
+        let mut timed = vec![Some(1.0)];
+        timed.resize(members.len(), None);
+        let found = first_slow(members.len(), |k| {
+            let (ms, per_gflop) = ms_per_gflop(members[k], &opts)?;
+            let ratio = per_gflop / base;
+            row(members[k], ms, per_gflop, ratio);
+            timed[k] = Some(ratio);
+            Ok::<_, String>(ratio > opts.bisect_threshold)
+        });
+        let count = timed.iter().flatten().count();
+        match found {
+            Ok(Some(k)) => {
+                // The search ends on a slow member right after a fast one.
+                let below = k - 1;
+                println!(
+                    "  cliff at {} (log2SIZE {:.2}, {:.2}x), after {} (log2SIZE {:.2}, {:.2}x); timed {count} of {}",
+                    members[k].name,
+                    members[k].paths.opt_flops.log2_size,
+                    timed[k].unwrap_or(f64::NAN),
+                    members[below].name,
+                    members[below].paths.opt_flops.log2_size,
+                    timed[below].unwrap_or(f64::NAN),
+                    members.len()
+                );
+            }
+            Ok(None) => println!(
+                "  no cliff: the largest stays within {}x; timed {count} of {}",
+                opts.bisect_threshold,
+                members.len()
+            ),
+            Err(e) => warn!(family = %key, "--bisect stopped: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate;
+
+    #[test]
+    fn families_group_names_equal_up_to_digits() {
+        assert_eq!(family_key("bin_matmul_256"), "bin_matmul_#");
+        assert_eq!(family_key("sentence_3_12d"), "sentence_#_#d");
+        let instance = |name: &str, n: usize| {
+            generate::build_instance(&generate::GenOptions {
+                format: "ij,jk->ik".into(),
+                dims: [('i', n), ('j', n), ('k', n)].into(),
+                dtype: "float64".into(),
+                name: Some(name.into()),
+                ..Default::default()
+            })
+            .unwrap()
+        };
+        let instances = [
+            instance("mm_64", 64),
+            instance("mm_8", 8),
+            instance("other", 4),
+            instance("mm_16", 16),
+        ];
+        let groups = families(&instances);
+        assert_eq!(groups.len(), 1);
+        let names: Vec<&str> = groups["mm_#"].iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(names, ["mm_8", "mm_16", "mm_64"]);
+    }
+
+    #[test]
+    fn search_finds_the_first_slow_member_with_few_probes() {
+        for cliff in 1..20 {
+            let mut asked = Vec::new();
+            let found = first_slow(20, |k| {
+                asked.push(k);
+                Ok::<_, ()>(k >= cliff)
+            });
+            assert_eq!(found, Ok(Some(cliff)));
+            assert_eq!(asked[0], 19);
+            assert!(asked.len() <= 6, "{cliff}: {asked:?}");
+        }
+        assert_eq!(first_slow(20, |_| Ok::<_, ()>(false)), Ok(None));
+        assert_eq!(first_slow(1, |_| Ok::<_, ()>(true)), Ok(None));
+        assert_eq!(
+            first_slow(5, |k| if k == 4 { Err("oom") } else { Ok(true) }),
+            Err("oom")
+        );
+    }
+}
@@ -0,0 +1,63 @@
+//! Runtime control of the linked BLAS library's thread count.
+//!
+//! OpenBLAS and MKL each expose their own setter and honour different
+//! environment variables. Which one is linked is fixed at build time by the
+//! backend's provider features (`blas-mkl`, `blas-accelerate`, otherwise
+//! OpenBLAS, with Accelerate the default on macOS), so the setter is chosen
+//! with the same `cfg` and called directly.
+
+/// Set the BLAS thread count via the linked vendor's setter.
+///
+/// Returns the vendor name on success, or `None` if the build has no setter
+/// to call (faer build, or Accelerate, which has no thread-count API).
+pub fn set_num_threads(n: usize) -> Option<&'static str> {
+    let n = std::ffi::c_int::try_from(n).ok()?;
+    imp::set_num_threads(n)
+}
+
+#[cfg(all(feature = "blas", feature = "blas-mkl"))]
+mod imp {
+    use std::ffi::c_int;
+
+    extern "C" {
+        fn MKL_Set_Num_Threads(n: c_int);
+    }
+
+    pub fn set_num_threads(n: c_int) -> Option<&'static str> {
+        // SAFETY: MKL declares `void MKL_Set_Num_Threads(int)`.
+        unsafe { MKL_Set_Num_Threads(n) };
+        Some("MKL")
+    }
+}
+
+#[cfg(all(
+    feature = "blas",
+    not(feature = "blas-mkl"),
+    not(feature = "blas-accelerate"),
+    not(target_os = "macos")
+))]
+mod imp {
+    use std::ffi::c_int;
+
+    extern "C" {
+        fn openblas_set_num_threads(n: c_int);
+    }
+
+    pub fn set_num_threads(n: c_int) -> Option<&'static str> {
+        // SAFETY: OpenBLAS declares `void openblas_set_num_threads(int)`.
+        unsafe { openblas_set_num_threads(n) };
+        Some("OpenBLAS")
+    }
+}
+
+// No explicit provider on macOS links Accelerate, like `blas-accelerate`.
+#[cfg(any(
+    not(feature = "blas"),
+    feature = "blas-accelerate",
+    all(not(feature = "blas-mkl"), target_os = "macos")
+))]
+mod imp {
+    pub fn set_num_threads(_n: std::ffi::c_int) -> Option<&'static str> {
+        None
+    }
+}
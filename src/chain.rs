@@ -0,0 +1,183 @@
+//! `gen-chain` subcommand: time a synthetic matrix chain over a range of
+//! tensor counts.
+//!
+//! The chain `ab,bc,cd,...->a<last>` has every label at the same bond
+//! dimension, so the linear `((T0 T1) T2) ...` path `gen` builds is also the
+//! cheapest one: every step is a `bond x bond` matrix product. Time then
+//! grows with the step count alone, which makes the sweep a scaling curve of
+//! the tree evaluator rather than of any particular network.
+
+use tracing::warn;
+
+use crate::cli::Options;
+use crate::generate::{self, GenOptions};
+use crate::{run_instance, BenchmarkInstance};
+
+pub const USAGE: &str = "\
+Usage: strided-rs-benchmark-suite gen-chain --n <N> --bond <D> [OPTIONS]
+
+Times the matrix chain ab,bc,cd,...->a<last> (every label of size D) for
+tensor counts doubling from --from up to N, along the linear path, and prints
+N against the median time.
+
+Any other flag is passed to the regular runner (e.g. --runs, --blas-threads).
+
+Options:
+  --n <N>              Largest tensor count (at least 2)
+  --bond <D>           Size of every label
+  --from <N>           Smallest tensor count (default 2)
+  --dtype <DTYPE>      float64 (default) or complex128
+  -h, --help           Print this help
+";
+
+#[derive(Debug)]
+pub struct ChainOptions {
+    pub n: usize,
+    pub bond: usize,
+    pub from: usize,
+    pub dtype: String,
+}
+
+impl ChainOptions {
+    /// Parse `gen-chain` flags, returning the unrecognised arguments for the
+    /// regular run options.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<(Self, Vec<String>), String> {
+        let (mut n, mut bond) = (None, None);
+        let mut opts = ChainOptions {
+            n: 0,
+            bond: 0,
+            from: 2,
+            dtype: "float64".into(),
+        };
+        let mut rest = Vec::new();
+        let mut args = args.into_iter();
+        while let Some(flag) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("{flag} requires a value"))
+            };
+            let mut count = |min: usize| -> Result<usize, String> {
+                let raw = value()?;
+                match raw.parse() {
+                    Ok(v) if v >= min => Ok(v),
+                    _ => Err(format!("{flag} must be an integer >= {min}, got {raw:?}")),
+                }
+            };
+            match flag.as_str() {
+                "--n" => n = Some(count(2)?),
+                "--bond" => bond = Some(count(1)?),
+                "--from" => opts.from = count(2)?,
+                "--dtype" => opts.dtype = value()?,
+                "-h" | "--help" => {
+                    print!("{USAGE}");
+                    std::process::exit(0);
+                }
+                _ => rest.push(flag.clone()),
+            }
+        }
+        opts.n = n.ok_or("gen-chain requires --n")?;
+        opts.bond = bond.ok_or("gen-chain requires --bond")?;
+        if opts.from > opts.n {
+            return Err(format!(
+                "--from {} is larger than --n {}",
+                opts.from, opts.n
+            ));
+        }
+        if !matches!(opts.dtype.as_str(), "float64" | "complex128") {
+            return Err(format!("unsupported dtype: {}", opts.dtype));
+        }
+        Ok((opts, rest))
+    }
+}
+
+/// Label `k` of a chain: `a`-`z`, then Latin Extended letters, which never
+/// collide with the format string's `,` and `->`.
+fn label(k: usize) -> char {
+    if k < 26 {
+        (b'a' + k as u8) as char
+    } else {
+        char::from_u32(0x100 + (k - 26) as u32).expect("chain label out of range")
+    }
+}
+
+/// `ab,bc,...->a<last>` for `n` tensors.
+fn chain_format(n: usize) -> String {
+    let inputs: Vec<String> = (0..n)
+        .map(|k| [label(k), label(k + 1)].iter().collect())
+        .collect();
+    format!("{}->{}{}", inputs.join(","), label(0), label(n))
+}
+
+/// Tensor counts doubling from `from`, always ending at `n`.
+fn sweep_sizes(from: usize, n: usize) -> Vec<usize> {
+    let mut sizes: Vec<usize> = std::iter::successors(Some(from), |&k| Some(k * 2))
+        .take_while(|&k| k < n)
+        .collect();
+    sizes.push(n);
+    sizes
+}
+
+/// The `n`-tensor chain as a `gen` instance.
+fn chain_instance(n: usize, bond: usize, dtype: &str) -> Result<BenchmarkInstance, String> {
+    generate::build_instance(&GenOptions {
+        format: chain_format(n),
+        dims: (0..=n).map(|k| (label(k), bond)).collect(),
+        dtype: dtype.into(),
+        name: Some(format!("chain_n{n}_bond{bond}")),
+        ..GenOptions::default()
+    })
+}
+
+/// Time every chain of the sweep and print one row per tensor count.
+pub fn run(opts: &Options, chain: &ChainOptions) {
+    println!(
+        "Matrix chain sweep: bond {}, {}, linear path, median of {} runs ({} warmup)",
+        chain.bond, chain.dtype, opts.runs, opts.warmup
+    );
+    println!(
+        "{:>6} {:>10} {:>12} {:>10} {:>13}",
+        "N", "log10FLOPS", "Median (ms)", "IQR (ms)", "Per step (us)"
+    );
+    println!("{}", "-".repeat(55));
+    for n in sweep_sizes(chain.from, chain.n) {
+        let instance = match chain_instance(n, chain.bond, &chain.dtype) {
+            Ok(instance) => instance,
+            Err(e) => {
+                warn!("gen-chain: N = {n}: {e}");
+                continue;
+            }
+        };
+        let path_meta = &instance.paths.opt_flops;
+        match run_instance(&instance, path_meta, opts, None) {
+            Ok(result) => println!(
+                "{n:>6} {:>10.2} {:>12.3} {:>10.3} {:>13.2}",
+                path_meta.log10_flops,
+                result.median_ms,
+                result.iqr_ms(),
+                result.median_ms * 1e3 / (n - 1) as f64
+            ),
+            Err(e) => {
+                warn!("gen-chain: N = {n}: {e}");
+                println!("{n:>6} {:>10.2} {:>12}", path_meta.log10_flops, "error");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chain_has_one_matrix_product_per_step() {
+        assert_eq!(chain_format(3), "ab,bc,cd->ad");
+        assert_eq!(label(26), 'Ā');
+        assert_eq!(sweep_sizes(2, 50), vec![2, 4, 8, 16, 32, 50]);
+        assert_eq!(sweep_sizes(4, 4), vec![4]);
+        let instance = chain_instance(40, 8, "float64").unwrap();
+        assert_eq!(instance.num_tensors, 40);
+        // 39 products of 8 x 8 matrices.
+        let expected = (39.0 * 512f64).log10();
+        assert!((instance.paths.opt_flops.log10_flops - expected).abs() < 1e-12);
+    }
+}
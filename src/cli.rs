@@ -0,0 +1,961 @@
+//! Command-line options for the benchmark runner.
+//!
+//! Environment variables (`BENCH_INSTANCE`, `RAYON_NUM_THREADS`, ...) keep
+//! working as before; flags cover settings that need a value per run.
+
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::affinity;
+use crate::chain::{self, ChainOptions};
+use crate::generate::{self, GenOptions};
+use crate::validate;
+
+pub const USAGE: &str = "\
+Usage: strided-rs-benchmark-suite [OPTIONS]
+       strided-rs-benchmark-suite gen --format <EINSUM> --dims <LIST> [OPTIONS]
+       strided-rs-benchmark-suite gen-chain --n <N> --bond <D> [OPTIONS]
+       strided-rs-benchmark-suite diff <A.json> <B.json>
+       strided-rs-benchmark-suite validate [--data-dir <PATH>]
+
+Subcommands:
+  gen                  Build a synthetic instance and run it (see `gen --help`)
+  gen-chain            Time a matrix chain over doubling tensor counts (see `gen-chain --help`)
+  diff                 Compare the medians of two --output-json files, biggest change first
+  validate             Check every instance of a dataset without running (see `validate --help`)
+
+Options:
+  --blas-threads <N>   Set the linked BLAS library's thread count
+  --cpu-set <LIST>     Restrict the process to these cores, e.g. 0,2,4,6 or 0-3 (Linux)
+  --trace-steps        Time each path step separately and report the noisiest
+  --profile-tree       Sum traced step times by contraction tree level (root = 0)
+  --prefix-sweep       Time every prefix of the path for one instance
+  --bisect             Binary-search each family of scaled instances for a time-per-FLOP cliff
+  --bisect-threshold <X>
+                       Time per FLOP, relative to a family's smallest, counted as slow (default: 2)
+  --isolate-step <INSTANCE>:<STEP>
+                       Time one path step (0-based) alone, split into copies and GEMM
+                       (operands are fresh column-major arrays: the full run's strides are not reproduced)
+  --rerun-regressions <BASELINE.json>
+                       Quick pass vs an --output-json baseline, then re-time regressions
+  --regression-threshold <PCT>
+                       Slowdown counted as a regression (default: 10)
+  --fingerprint        Add a size-independent structure fingerprint column
+  --tree-stats         Add contraction tree depth, width and balance columns
+  --kernel-path        Count steps on the GEMM path; tag rows with other steps [fallback]
+  --show-tree          Print each timed contraction tree as nested parentheses, e.g. ((T0 T1) T2)
+  --flops-per-element  Add a column of path FLOPs per output element
+  --step-stats         Count path steps by kind (GEMM, GEMV, outer, ...) without running
+  --dump-intermediates <PATH>
+                       Write every path step's intermediate labels and shape as JSON
+  --dump-operands <DIR>
+                       Write the selected instance's seeded operands and result as .npy files
+  --warmup <N|STRATEGY=N,...>
+                       Untimed runs per instance, globally and/or per strategy (default: 3)
+  --runs <N|STRATEGY=N,...>
+                       Timed runs per instance, e.g. 15 or opt_flops=10,opt_size=3 (default: 15)
+  --adaptive-warmup    Warm up until consecutive runs stabilize (reports count)
+  --warmup-tolerance <PCT>
+                       Relative difference counted as stable (default: 5)
+  --max-warmup <N>     Cap on adaptive warmup runs (default: 20)
+  --warmup-shared      Warm up every instance first, then time them all with no further warmup
+  --min-mode           Time until the minimum stops improving and report it (Min, Runs columns)
+  --min-stable <K>     Runs without a new minimum that end --min-mode (default: 20)
+  --min-budget <SECS>  Time cap on --min-mode runs per instance (default: 2)
+  --first-result       Add time to the first output element (equals the total: no streaming)
+  --once               One timed run, no warmup (smoke test; not a reliable timing)
+  --profile-planning   Time format parsing + tree building separately
+  --mixed-precision    Report the error of f32-rounded inputs vs pure f64
+  --profile-copy       Add a column with the share of time spent in input copies
+  --cross-check        Compare small instances against NumPy's einsum
+  --verify-checksum    Check seeded-data results against `expected_checksum`
+  --checksum-mode <sum-real|sum-abs|weighted>
+                       Reduction behind the checksum (default: sum-real)
+  --check-determinism  Contract seeded data twice and compare the results bitwise
+  --determinism-repeats <N>
+                       Contract it N times instead; any divergence fails the run
+  --report-sparsity    Add the share of near-zero result elements on seeded data
+  --reuse-output       Also time runs writing into one preallocated output buffer
+  --pool               Also time runs reusing one intermediate buffer pool
+  --include-load       Also time runs from instance file to result, JSON parse included
+  --hugepages          Also time runs on operands backed by transparent huge pages (Linux)
+  --track-memory       Measure peak heap per instance and compare strategies
+  --force              Run instances estimated to need more than available RAM
+  --max-flops <LOG10>  Skip paths whose log10_flops exceeds this
+  --max-size <LOG2>    Skip paths whose log2_size (largest intermediate) exceeds this
+  --cse                Count identical subtrees a shared evaluation would skip
+  --multi-output       Time shared vs recomputed evaluation of `outputs_colmajor`
+  --flat               Add a `flat` pseudo-strategy: one loop over all labels (optimize=False)
+  --fused              Time that loop against opt_flops where it needs no more FLOPs than the path
+  --seeds <N>          Time opt_flops on zeros and N seeded inputs; flag data-dependent timing
+  --force-dtype <DTYPE>
+                       Run every instance as float64 or complex128, ignoring its dtype
+  --accumulate-dtype <DTYPE>
+                       Accumulator dtype; only each instance's own dtype is supported
+  --layout <col|row|both>
+                       Operand memory order; `both` adds row-major columns (default: col)
+  --index-format <char|tokenized|numeric-suffix>
+                       Instance label syntax (default: char)
+  --numeric-suffix-labels
+                       Same as --index-format numeric-suffix (labels like a1, b2)
+  --throughput         Add FLOP/s, minimum memory traffic, and GB/s columns
+  --peak-bandwidth <GB/s|auto>
+                       Add GB/s as a percentage of this peak (auto: measure a large copy; implies --throughput)
+  --gflops-spread      Add min/max FLOP/s over the timed runs and their spread
+  --reproducibility    Add a 0-1 timing reproducibility score and list low scorers
+  --flops-unit <auto|M|G|T>
+                       Prefix of the FLOP/s columns (default: auto, per cell)
+  --shuffle [SEED]     Run instances in seeded random order (results still sorted)
+  --top <N>            Print only the N slowest instances per strategy
+  --summary-only       Print only each strategy's aggregate summary, no rows
+  --time-budget <SECONDS>
+                       Stop starting new instances once the run has taken this long
+  --julia-compatible-output
+                       Print the table exactly as src/main.jl does (no extra columns)
+  --format <table|line>
+                       Result rows as an aligned table or one key=value line each (default: table)
+  --include-file <PATH>
+                       Run only the instances named in this file (one per line)
+  --exclude-file <PATH>
+                       Leave out the instances named in this file
+  --stream             Hold one instance in memory at a time, reading files again per strategy
+  --paths-dir <PATH>   Take each instance's paths from <PATH>/<name>.path.json when it exists
+  --output-order <LABELS>
+                       Reorder the output labels of the one selected instance (BENCH_INSTANCE)
+  --label <TEXT>       Tag stored with recorded results (e.g. a git hash)
+  --sqlite <PATH>      Append results to a SQLite database (`sqlite` feature)
+  --output-json <PATH> Write per-instance summary stats to a JSON file
+  --output-json-samples <PATH>
+                       Same, with every timed run nested as `samples_ns`
+  --output-bincode <PATH>
+                       The same records and samples in bincode (`bincode` feature)
+  --prometheus-output <PATH>
+                       Timing stats as a Prometheus textfile (node_exporter collector)
+  -h, --help           Print this help
+";
+
+/// How instance format strings spell their index labels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexFormat {
+    /// One `char` per label: `"ab,bc->ac"`.
+    Char,
+    /// Bracketed, delimiter-separated labels: `"[i j],[j k]->[i k]"`.
+    Tokenized,
+    /// A letter with an optional decimal suffix per label: `"a1b,ba2->a1a2"`.
+    NumericSuffix,
+}
+
+impl IndexFormat {
+    /// The `--index-format` value.
+    pub fn name(self) -> &'static str {
+        match self {
+            IndexFormat::Char => "char",
+            IndexFormat::Tokenized => "tokenized",
+            IndexFormat::NumericSuffix => "numeric-suffix",
+        }
+    }
+}
+
+/// How `--verify-checksum` reduces a result to one real number (see
+/// [`crate::compare::checksum`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumMode {
+    /// Sum of every real and imaginary part.
+    SumReal,
+    /// Sum of every element's modulus.
+    SumAbs,
+    /// Sum of every part times a fixed pseudo-random weight.
+    Weighted,
+}
+
+impl ChecksumMode {
+    /// The flag value selecting this mode.
+    pub fn name(self) -> &'static str {
+        match self {
+            ChecksumMode::SumReal => "sum-real",
+            ChecksumMode::SumAbs => "sum-abs",
+            ChecksumMode::Weighted => "weighted",
+        }
+    }
+}
+
+/// Reference for the `% peak` column (`--peak-bandwidth`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PeakBandwidth {
+    /// Given on the command line, in GB/s.
+    Given(f64),
+    /// Measured at startup with [`crate::bandwidth::copy_gbps`].
+    Auto,
+}
+
+/// SI prefix of the FLOP/s columns (`--throughput`, `--gflops-spread`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlopsUnit {
+    /// Per cell: M, G, or T, whichever keeps the value at or above 1.
+    Auto,
+    Mega,
+    Giga,
+    Tera,
+}
+
+/// Memory order of the operands built for timing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// Column-major (Fortran order), matching the colmajor shapes.
+    Col,
+    /// Row-major (C order) storage of the same logical tensors.
+    Row,
+    /// Time column-major, and row-major again in extra columns.
+    Both,
+}
+
+/// How `run_suite` prints result rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowFormat {
+    /// Aligned columns under a header.
+    Table,
+    /// One logfmt `key=value` line per row, for grep and awk.
+    Line,
+}
+
+/// What the process was asked to do.
+pub enum Command {
+    Run(Options),
+    Gen(GenOptions, Options),
+    /// Time a synthetic matrix chain over a range of tensor counts.
+    GenChain(ChainOptions, Options),
+    /// Compare two `--output-json` files.
+    Diff(PathBuf, PathBuf),
+    /// Check every instance in a data directory without timing anything.
+    Validate(PathBuf),
+}
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Thread count passed to the linked BLAS vendor's setter.
+    pub blas_threads: Option<usize>,
+    /// Cores the process (and its thread pools) may run on.
+    pub cpu_set: Option<Vec<usize>>,
+    /// Run a per-step traced evaluation after each instance.
+    pub trace_steps: bool,
+    /// Attribute traced step time to contraction tree levels.
+    pub profile_tree: bool,
+    /// Print each timed tree as nested parentheses under its row.
+    pub show_tree: bool,
+    /// GEMM step counts per row, and `[fallback]` tags.
+    pub kernel_path: bool,
+    /// Time each path prefix of a single instance instead of the suite.
+    pub prefix_sweep: bool,
+    /// Search instance families for a time-per-FLOP cliff instead of the
+    /// suite.
+    pub bisect: bool,
+    /// Time per FLOP over the family's smallest member that counts as slow.
+    pub bisect_threshold: f64,
+    /// Instance name and 0-based path step timed alone instead of the suite.
+    pub isolate_step: Option<(String, usize)>,
+    /// `--output-json` file to check for regressions against.
+    pub rerun_regressions: Option<PathBuf>,
+    /// Relative slowdown treated as a regression.
+    pub regression_threshold: f64,
+    /// Show the structural fingerprint column.
+    pub fingerprint: bool,
+    /// Show contraction tree depth and width.
+    pub tree_stats: bool,
+    /// Show path FLOPs divided by the output's element count.
+    pub flops_per_element: bool,
+    /// Classify every path step instead of running the suite.
+    pub step_stats: bool,
+    /// JSON file of every path's intermediates, written instead of running.
+    pub dump_intermediates: Option<PathBuf>,
+    /// Directory the seeded operands of one instance are written to as
+    /// `.npy`, instead of running.
+    pub dump_operands: Option<PathBuf>,
+    /// Fixed warmup runs per instance.
+    pub warmup: usize,
+    /// Timed runs per instance.
+    pub runs: usize,
+    /// `--warmup` overrides for single strategies.
+    pub strategy_warmup: Vec<(&'static str, usize)>,
+    /// `--runs` overrides for single strategies.
+    pub strategy_runs: Vec<(&'static str, usize)>,
+    /// `--once`: a single timed run with no warmup.
+    pub once: bool,
+    /// Warm up until consecutive runs agree instead of a fixed count.
+    pub adaptive_warmup: bool,
+    /// Relative difference (fraction) at which adaptive warmup stops.
+    pub warmup_tolerance: f64,
+    /// Upper bound on adaptive warmup runs.
+    pub max_warmup: usize,
+    /// Run every instance's warmup in one pass before any is timed.
+    pub warmup_shared: bool,
+    /// Time until the minimum is stable instead of a fixed run count.
+    pub min_mode: bool,
+    /// Consecutive runs without a new minimum that end `--min-mode`.
+    pub min_stable: usize,
+    /// Seconds of `--min-mode` runs per instance before giving up on
+    /// stability.
+    pub min_budget: f64,
+    /// Show the time until the first output element is available.
+    pub first_result: bool,
+    /// Report planning (parse + tree build) overhead per instance.
+    pub profile_planning: bool,
+    /// Compare f32-rounded inputs (f64 accumulation) against pure f64.
+    pub mixed_precision: bool,
+    /// Report the fraction of step time spent in input-permutation copies.
+    pub profile_copy: bool,
+    /// Compare results on seeded data against `np.einsum` in a subprocess.
+    pub cross_check: bool,
+    /// Compare results on seeded data with the instance's `expected_checksum`.
+    pub verify_checksum: bool,
+    /// Evaluate seeded data twice and check the results are bit-identical.
+    pub check_determinism: bool,
+    /// `--check-determinism` over this many evaluations, failing the run
+    /// when one diverges; `None` for the plain two-run check, which only warns.
+    pub determinism_repeats: Option<usize>,
+    /// Scan the result on seeded data for near-zero elements.
+    pub report_sparsity: bool,
+    /// Time evaluation into a per-instance output buffer as well.
+    pub reuse_output: bool,
+    /// Time evaluation with a `BufferPool` reused across runs as well.
+    pub pool: bool,
+    /// Time runs that read and parse the instance file first as well.
+    pub include_load: bool,
+    /// Time runs on operands backed by transparent huge pages as well.
+    pub hugepages: bool,
+    /// Measure peak heap growth of one evaluation per instance.
+    pub track_memory: bool,
+    /// Run instances even when their memory estimate exceeds available RAM.
+    pub force: bool,
+    /// Largest path `log10_flops` run.
+    pub max_flops: Option<f64>,
+    /// Largest path `log2_size` run.
+    pub max_size: Option<f64>,
+    /// Report subtrees shared by common subexpression elimination.
+    pub cse: bool,
+    /// Compare shared and naive evaluation of multi-output instances.
+    pub multi_output: bool,
+    /// Time the unoptimized single-loop evaluation as a `flat` strategy.
+    pub flat: bool,
+    /// Time the single-pass evaluation against `opt_flops` where it does
+    /// no extra arithmetic.
+    pub fused: bool,
+    /// Seeded inputs per instance for the data-sensitivity table.
+    pub seeds: Option<usize>,
+    /// Dtype every instance is run as, overriding its declared `dtype`.
+    pub force_dtype: Option<String>,
+    /// Accumulator dtype requested for every contraction.
+    pub accumulate_dtype: Option<String>,
+    /// Memory order of the timed operands.
+    pub layout: Layout,
+    /// Reduction of a result to its `--verify-checksum` value.
+    pub checksum_mode: ChecksumMode,
+    /// Label syntax of the instance format strings.
+    pub index_format: IndexFormat,
+    /// Show FLOP/s, minimum traffic, and effective bandwidth.
+    pub throughput: bool,
+    /// Memory bandwidth the `GB/s` column is compared against.
+    pub peak_bandwidth: Option<PeakBandwidth>,
+    /// Show the FLOP/s range over individual timed runs.
+    pub gflops_spread: bool,
+    /// Show a 0-1 reproducibility score per instance.
+    pub reproducibility: bool,
+    /// Prefix the FLOP/s columns are printed in.
+    pub flops_unit: FlopsUnit,
+    /// Seed for randomizing run order; `--shuffle` without a seed picks one.
+    pub shuffle: Option<u64>,
+    /// Print only the N slowest rows per strategy, after the run.
+    pub top: Option<usize>,
+    /// Suppress per-instance rows, keeping the per-strategy summary.
+    pub summary_only: bool,
+    /// Print result tables in the Julia runner's layout.
+    pub julia_compatible: bool,
+    /// How result rows are printed.
+    pub format: RowFormat,
+    /// Wall-clock seconds after which no new instance is started.
+    pub time_budget: Option<f64>,
+    /// File of instance names to keep.
+    pub include_file: Option<PathBuf>,
+    /// File of instance names to drop.
+    pub exclude_file: Option<PathBuf>,
+    /// Read each instance from disk when it runs instead of loading all.
+    pub stream: bool,
+    /// Directory of `<name>.path.json` files overriding instance paths.
+    pub paths_dir: Option<PathBuf>,
+    /// Output labels replacing the selected instance's own order.
+    pub output_order: Option<String>,
+    /// Free-form tag stored with recorded results.
+    pub label: Option<String>,
+    /// SQLite database that every result is appended to.
+    pub sqlite: Option<PathBuf>,
+    /// JSON file with one summary object per instance and strategy.
+    pub output_json: Option<PathBuf>,
+    /// Like `output_json`, with the individual timed runs nested.
+    pub output_json_samples: Option<PathBuf>,
+    /// The same records, samples included, in bincode.
+    pub output_bincode: Option<PathBuf>,
+    /// Prometheus textfile with the records' timing stats.
+    pub prometheus_output: Option<PathBuf>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            blas_threads: None,
+            cpu_set: None,
+            trace_steps: false,
+            profile_tree: false,
+            show_tree: false,
+            kernel_path: false,
+            prefix_sweep: false,
+            bisect: false,
+            bisect_threshold: 2.0,
+            isolate_step: None,
+            rerun_regressions: None,
+            regression_threshold: 0.10,
+            fingerprint: false,
+            tree_stats: false,
+            flops_per_element: false,
+            step_stats: false,
+            dump_intermediates: None,
+            dump_operands: None,
+            warmup: crate::NUM_WARMUP,
+            runs: crate::NUM_TIMED,
+            strategy_warmup: Vec::new(),
+            strategy_runs: Vec::new(),
+            once: false,
+            adaptive_warmup: false,
+            warmup_tolerance: 0.05,
+            max_warmup: 20,
+            warmup_shared: false,
+            min_mode: false,
+            min_stable: 20,
+            min_budget: 2.0,
+            first_result: false,
+            profile_planning: false,
+            mixed_precision: false,
+            profile_copy: false,
+            cross_check: false,
+            verify_checksum: false,
+            check_determinism: false,
+            determinism_repeats: None,
+            report_sparsity: false,
+            reuse_output: false,
+            pool: false,
+            include_load: false,
+            hugepages: false,
+            track_memory: false,
+            force: false,
+            max_flops: None,
+            max_size: None,
+            cse: false,
+            multi_output: false,
+            flat: false,
+            fused: false,
+            seeds: None,
+            force_dtype: None,
+            accumulate_dtype: None,
+            layout: Layout::Col,
+            checksum_mode: ChecksumMode::SumReal,
+            index_format: IndexFormat::Char,
+            throughput: false,
+            peak_bandwidth: None,
+            gflops_spread: false,
+            reproducibility: false,
+            flops_unit: FlopsUnit::Auto,
+            shuffle: None,
+            top: None,
+            summary_only: false,
+            julia_compatible: false,
+            format: RowFormat::Table,
+            include_file: None,
+            exclude_file: None,
+            stream: false,
+            paths_dir: None,
+            output_order: None,
+            time_budget: None,
+            label: None,
+            sqlite: None,
+            output_json: None,
+            output_json_samples: None,
+            output_bincode: None,
+            prometheus_output: None,
+        }
+    }
+}
+
+impl Options {
+    /// Parse the process arguments, exiting on `--help` or error.
+    pub fn from_env() -> Command {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        let (usage, parsed) = match args.first().map(String::as_str) {
+            Some("gen") => (generate::USAGE, parse_gen(&args[1..])),
+            Some("gen-chain") => (chain::USAGE, parse_gen_chain(&args[1..])),
+            Some("diff") => (USAGE, parse_diff(&args[1..])),
+            Some("validate") => (
+                validate::USAGE,
+                validate::parse(&args[1..]).map(Command::Validate),
+            ),
+            _ => (USAGE, Self::parse(args).map(Command::Run)),
+        };
+        match parsed {
+            Ok(command) => command,
+            Err(e) => {
+                eprintln!("error: {e}");
+                eprintln!();
+                eprint!("{usage}");
+                std::process::exit(2);
+            }
+        }
+    }
+
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut opts = Options::default();
+        let mut args = args.into_iter().peekable();
+        let mut counts_set = false;
+        while let Some(arg) = args.next() {
+            // Accept both `--flag value` and `--flag=value`.
+            let (flag, inline) = match arg.split_once('=') {
+                Some((f, v)) if f.starts_with("--") => (f.to_string(), Some(v.to_string())),
+                _ => (arg, None),
+            };
+            let mut value = || {
+                inline
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| format!("{flag} requires a value"))
+            };
+            match flag.as_str() {
+                "--blas-threads" => opts.blas_threads = Some(parse_value(&flag, &value()?)?),
+                "--cpu-set" => opts.cpu_set = Some(affinity::parse_cpu_list(&value()?)?),
+                "--trace-steps" => opts.trace_steps = true,
+                "--profile-tree" => opts.profile_tree = true,
+                "--prefix-sweep" => opts.prefix_sweep = true,
+                "--bisect" => opts.bisect = true,
+                "--bisect-threshold" => {
+                    let factor: f64 = parse_value(&flag, &value()?)?;
+                    if !(factor > 1.0 && factor.is_finite()) {
+                        return Err(format!("--bisect-threshold must be above 1, got {factor}"));
+                    }
+                    opts.bisect_threshold = factor;
+                }
+                "--isolate-step" => {
+                    let raw = value()?;
+                    let (name, step) = raw.rsplit_once(':').ok_or_else(|| {
+                        format!("--isolate-step takes <instance>:<step>, got {raw:?}")
+                    })?;
+                    opts.isolate_step = Some((name.to_string(), parse_value(&flag, step)?));
+                }
+                "--rerun-regressions" => opts.rerun_regressions = Some(PathBuf::from(value()?)),
+                "--regression-threshold" => {
+                    let pct: f64 = parse_value(&flag, &value()?)?;
+                    if !(pct >= 0.0 && pct.is_finite()) {
+                        return Err(format!(
+                            "--regression-threshold must be non-negative, got {pct}"
+                        ));
+                    }
+                    opts.regression_threshold = pct / 100.0;
+                }
+                "--fingerprint" => opts.fingerprint = true,
+                "--tree-stats" => opts.tree_stats = true,
+                "--kernel-path" => opts.kernel_path = true,
+                "--show-tree" => opts.show_tree = true,
+                "--flops-per-element" => opts.flops_per_element = true,
+                "--step-stats" => opts.step_stats = true,
+                "--dump-intermediates" => opts.dump_intermediates = Some(PathBuf::from(value()?)),
+                "--dump-operands" => opts.dump_operands = Some(PathBuf::from(value()?)),
+                "--warmup" => {
+                    let (global, per_strategy) = parse_counts(&flag, &value()?, 0)?;
+                    opts.warmup = global.unwrap_or(opts.warmup);
+                    opts.strategy_warmup = per_strategy;
+                    counts_set = true;
+                }
+                "--runs" => {
+                    let (global, per_strategy) = parse_counts(&flag, &value()?, 1)?;
+                    opts.runs = global.unwrap_or(opts.runs);
+                    opts.strategy_runs = per_strategy;
+                    counts_set = true;
+                }
+                "--adaptive-warmup" => opts.adaptive_warmup = true,
+                "--warmup-shared" => opts.warmup_shared = true,
+                "--once" => {
+                    opts.once = true;
+                    opts.warmup = 0;
+                    opts.runs = 1;
+                }
+                "--profile-planning" => opts.profile_planning = true,
+                "--mixed-precision" => opts.mixed_precision = true,
+                "--profile-copy" => opts.profile_copy = true,
+                "--cross-check" => opts.cross_check = true,
+                "--verify-checksum" => opts.verify_checksum = true,
+                "--check-determinism" => opts.check_determinism = true,
+                "--determinism-repeats" => {
+                    let n: usize = parse_value(&flag, &value()?)?;
+                    if n < 2 {
+                        return Err("--determinism-repeats must be at least 2".into());
+                    }
+                    opts.determinism_repeats = Some(n);
+                    opts.check_determinism = true;
+                }
+                "--report-sparsity" => opts.report_sparsity = true,
+                "--reuse-output" => opts.reuse_output = true,
+                "--pool" => opts.pool = true,
+                "--include-load" => opts.include_load = true,
+                "--hugepages" => opts.hugepages = true,
+                "--track-memory" => opts.track_memory = true,
+                "--force" => opts.force = true,
+                "--max-flops" => opts.max_flops = Some(parse_value(&flag, &value()?)?),
+                "--max-size" => opts.max_size = Some(parse_value(&flag, &value()?)?),
+                "--cse" => opts.cse = true,
+                "--multi-output" => opts.multi_output = true,
+                "--flat" => opts.flat = true,
+                "--fused" => opts.fused = true,
+                "--seeds" => {
+                    let n: usize = parse_value(&flag, &value()?)?;
+                    if n < 2 {
+                        return Err("--seeds must be at least 2".into());
+                    }
+                    opts.seeds = Some(n);
+                }
+                "--force-dtype" => {
+                    let dtype = value()?;
+                    if !matches!(dtype.as_str(), "float64" | "complex128") {
+                        return Err(format!(
+                            "--force-dtype must be float64 or complex128, got {dtype:?}"
+                        ));
+                    }
+                    opts.force_dtype = Some(dtype);
+                }
+                "--accumulate-dtype" => {
+                    let dtype = value()?;
+                    if !matches!(dtype.as_str(), "float64" | "complex128") {
+                        return Err(format!(
+                            "--accumulate-dtype must be float64 or complex128, got {dtype:?}"
+                        ));
+                    }
+                    opts.accumulate_dtype = Some(dtype);
+                }
+                "--layout" => {
+                    opts.layout = match value()?.as_str() {
+                        "col" => Layout::Col,
+                        "row" => Layout::Row,
+                        "both" => Layout::Both,
+                        other => {
+                            return Err(format!(
+                                "--layout must be col, row, or both, got {other:?}"
+                            ))
+                        }
+                    }
+                }
+                "--checksum-mode" => {
+                    opts.checksum_mode = match value()?.as_str() {
+                        "sum-real" => ChecksumMode::SumReal,
+                        "sum-abs" => ChecksumMode::SumAbs,
+                        "weighted" => ChecksumMode::Weighted,
+                        other => {
+                            let expected = "sum-real, sum-abs, or weighted";
+                            return Err(format!(
+                                "--checksum-mode must be {expected}, got {other:?}"
+                            ));
+                        }
+                    }
+                }
+                "--index-format" => {
+                    opts.index_format = match value()?.as_str() {
+                        "char" => IndexFormat::Char,
+                        "tokenized" => IndexFormat::Tokenized,
+                        "numeric-suffix" => IndexFormat::NumericSuffix,
+                        other => {
+                            let expected = "char, tokenized or numeric-suffix";
+                            return Err(format!(
+                                "--index-format must be {expected}, got {other:?}"
+                            ));
+                        }
+                    }
+                }
+                "--numeric-suffix-labels" => opts.index_format = IndexFormat::NumericSuffix,
+                "--throughput" => opts.throughput = true,
+                "--peak-bandwidth" => {
+                    let raw = value()?;
+                    opts.peak_bandwidth = Some(if raw == "auto" {
+                        PeakBandwidth::Auto
+                    } else {
+                        let gbps: f64 = parse_value(&flag, &raw)?;
+                        if !(gbps > 0.0 && gbps.is_finite()) {
+                            return Err(format!("--peak-bandwidth must be positive, got {raw}"));
+                        }
+                        PeakBandwidth::Given(gbps)
+                    });
+                    opts.throughput = true;
+                }
+                "--gflops-spread" => opts.gflops_spread = true,
+                "--reproducibility" => opts.reproducibility = true,
+                "--flops-unit" => {
+                    opts.flops_unit = match value()?.as_str() {
+                        "auto" => FlopsUnit::Auto,
+                        "M" => FlopsUnit::Mega,
+                        "G" => FlopsUnit::Giga,
+                        "T" => FlopsUnit::Tera,
+                        other => {
+                            return Err(format!(
+                                "--flops-unit must be auto, M, G, or T, got {other:?}"
+                            ))
+                        }
+                    }
+                }
+                "--shuffle" => {
+                    // The seed is optional: only a numeric next argument is taken.
+                    let raw = inline
+                        .clone()
+                        .or_else(|| args.next_if(|a| a.parse::<u64>().is_ok()));
+                    opts.shuffle = Some(match raw {
+                        Some(raw) => parse_value(&flag, &raw)?,
+                        None => clock_seed(),
+                    });
+                }
+                "--top" => opts.top = Some(parse_value(&flag, &value()?)?),
+                "--summary-only" => opts.summary_only = true,
+                "--julia-compatible-output" => opts.julia_compatible = true,
+                "--format" => {
+                    opts.format = match value()?.as_str() {
+                        "table" => RowFormat::Table,
+                        "line" => RowFormat::Line,
+                        other => {
+                            return Err(format!("--format must be table or line, got {other:?}"))
+                        }
+                    }
+                }
+                "--time-budget" => {
+                    let secs: f64 = parse_value(&flag, &value()?)?;
+                    if !(secs > 0.0 && secs.is_finite()) {
+                        return Err(format!("--time-budget must be positive, got {secs}"));
+                    }
+                    opts.time_budget = Some(secs);
+                }
+                "--include-file" => opts.include_file = Some(PathBuf::from(value()?)),
+                "--exclude-file" => opts.exclude_file = Some(PathBuf::from(value()?)),
+                "--stream" => opts.stream = true,
+                "--paths-dir" => opts.paths_dir = Some(PathBuf::from(value()?)),
+                "--output-order" => opts.output_order = Some(value()?),
+                "--label" => opts.label = Some(value()?),
+                "--sqlite" => opts.sqlite = Some(PathBuf::from(value()?)),
+                "--output-json" => opts.output_json = Some(PathBuf::from(value()?)),
+                "--output-json-samples" => opts.output_json_samples = Some(PathBuf::from(value()?)),
+                "--output-bincode" => opts.output_bincode = Some(PathBuf::from(value()?)),
+                "--prometheus-output" => {
+                    opts.prometheus_output = Some(PathBuf::from(value()?));
+                }
+                "--warmup-tolerance" => {
+                    let pct: f64 = parse_value(&flag, &value()?)?;
+                    if !(pct > 0.0 && pct.is_finite()) {
+                        return Err(format!("--warmup-tolerance must be positive, got {pct}"));
+                    }
+                    opts.warmup_tolerance = pct / 100.0;
+                }
+                "--min-mode" => opts.min_mode = true,
+                "--first-result" => opts.first_result = true,
+                "--min-stable" => {
+                    opts.min_stable = parse_value(&flag, &value()?)?;
+                    if opts.min_stable == 0 {
+                        return Err("--min-stable must be at least 1".into());
+                    }
+                }
+                "--min-budget" => {
+                    let secs: f64 = parse_value(&flag, &value()?)?;
+                    if !(secs > 0.0 && secs.is_finite()) {
+                        return Err(format!("--min-budget must be positive, got {secs}"));
+                    }
+                    opts.min_budget = secs;
+                }
+                "--max-warmup" => {
+                    opts.max_warmup = parse_value(&flag, &value()?)?;
+                    if opts.max_warmup < 2 {
+                        return Err("--max-warmup must be at least 2".into());
+                    }
+                }
+                "-h" | "--help" => {
+                    print!("{USAGE}");
+                    std::process::exit(0);
+                }
+                other => return Err(format!("unknown argument: {other}")),
+            }
+        }
+        if opts.julia_compatible && opts.format == RowFormat::Line {
+            return Err("--julia-compatible-output prints the table; \
+                        it can't be combined with --format line"
+                .into());
+        }
+        if opts.julia_compatible && (opts.summary_only || opts.top.is_some()) {
+            return Err("--julia-compatible-output prints every row; \
+                        it can't be combined with --summary-only or --top"
+                .into());
+        }
+        if opts.stream {
+            let whole = [
+                ("--prefix-sweep", opts.prefix_sweep),
+                ("--bisect", opts.bisect),
+                ("--isolate-step", opts.isolate_step.is_some()),
+                ("--rerun-regressions", opts.rerun_regressions.is_some()),
+                ("--step-stats", opts.step_stats),
+                ("--dump-intermediates", opts.dump_intermediates.is_some()),
+                ("--dump-operands", opts.dump_operands.is_some()),
+                ("--flat", opts.flat),
+                ("--fused", opts.fused),
+                ("--seeds", opts.seeds.is_some()),
+                ("--multi-output", opts.multi_output),
+                ("--track-memory", opts.track_memory),
+                ("--warmup-shared", opts.warmup_shared),
+            ];
+            if let Some((flag, _)) = whole.iter().find(|(_, set)| *set) {
+                return Err(format!(
+                    "{flag} needs every instance at once; it can't be combined with --stream"
+                ));
+            }
+        }
+        if opts.hugepages && !cfg!(target_os = "linux") {
+            return Err("--hugepages needs Linux transparent huge pages".into());
+        }
+        // Checked here rather than when writing, so a long run isn't lost.
+        if opts.output_bincode.is_some() && !cfg!(feature = "bincode") {
+            return Err(
+                "--output-bincode: built without bincode support (rebuild with --features bincode)"
+                    .into(),
+            );
+        }
+        if opts.output_order.is_some() && opts.index_format != IndexFormat::Char {
+            return Err(format!(
+                "--output-order names single-character labels; it can't be combined with \
+                 --index-format {}",
+                opts.index_format.name()
+            ));
+        }
+        if opts.once && opts.adaptive_warmup {
+            return Err("--once skips warmup; it can't be combined with --adaptive-warmup".into());
+        }
+        if opts.once && counts_set {
+            return Err("--once fixes one run and no warmup; drop --warmup/--runs".into());
+        }
+        if opts.min_mode && opts.once {
+            return Err("--once fixes a single run; it can't be combined with --min-mode".into());
+        }
+        if opts.warmup_shared && (opts.once || opts.adaptive_warmup) {
+            return Err(
+                "--warmup-shared runs the fixed --warmup count up front; it can't be combined \
+                 with --once or --adaptive-warmup"
+                    .into(),
+            );
+        }
+        if opts.adaptive_warmup && !opts.strategy_warmup.is_empty() {
+            return Err(
+                "--adaptive-warmup decides the warmup itself; drop the per-strategy --warmup"
+                    .into(),
+            );
+        }
+        Ok(opts)
+    }
+
+    /// Warmup and timed run counts for `strategy`: its override if one was
+    /// given, the global `--warmup`/`--runs` otherwise.
+    pub fn counts(&self, strategy: &str) -> (usize, usize) {
+        let lookup = |overrides: &[(&str, usize)], global| {
+            overrides
+                .iter()
+                .find(|(s, _)| *s == strategy)
+                .map_or(global, |&(_, n)| n)
+        };
+        (
+            lookup(&self.strategy_warmup, self.warmup),
+            lookup(&self.strategy_runs, self.runs),
+        )
+    }
+
+    /// These options with `strategy`'s counts as the global ones, for code
+    /// that reads `warmup`/`runs` directly.
+    pub fn for_strategy(&self, strategy: &str) -> Options {
+        let (warmup, runs) = self.counts(strategy);
+        Options {
+            warmup,
+            runs,
+            ..self.clone()
+        }
+    }
+}
+
+/// `--warmup`/`--runs`: comma-separated items, either a bare count (the
+/// global value) or `STRATEGY=N`. Every count must be at least `min`.
+#[allow(clippy::type_complexity)]
+fn parse_counts(
+    flag: &str,
+    raw: &str,
+    min: usize,
+) -> Result<(Option<usize>, Vec<(&'static str, usize)>), String> {
+    let mut global = None;
+    let mut per_strategy: Vec<(&'static str, usize)> = Vec::new();
+    for item in raw.split(',').map(str::trim) {
+        let (count, strategy) = match item.split_once('=') {
+            Some((name, n)) => {
+                let Some(&(known, _)) = crate::STRATEGIES.iter().find(|(s, _)| *s == name.trim())
+                else {
+                    let names: Vec<&str> = crate::STRATEGIES.iter().map(|(s, _)| *s).collect();
+                    return Err(format!(
+                        "{flag}: unknown strategy {:?} (expected one of {})",
+                        name.trim(),
+                        names.join(", ")
+                    ));
+                };
+                (n.trim(), Some(known))
+            }
+            None => (item, None),
+        };
+        let count: usize = parse_value(flag, count)?;
+        if count < min {
+            return Err(format!("{flag} must be at least {min}, got {count}"));
+        }
+        match strategy {
+            Some(s) if per_strategy.iter().any(|(p, _)| *p == s) => {
+                return Err(format!("{flag}: {s} given twice"));
+            }
+            Some(s) => per_strategy.push((s, count)),
+            None if global.is_some() => return Err(format!("{flag}: two global counts")),
+            None => global = Some(count),
+        }
+    }
+    Ok((global, per_strategy))
+}
+
+/// `diff` takes exactly two files.
+fn parse_diff(args: &[String]) -> Result<Command, String> {
+    match args {
+        [flag] if flag == "-h" || flag == "--help" => {
+            print!("{USAGE}");
+            std::process::exit(0);
+        }
+        [a, b] => Ok(Command::Diff(PathBuf::from(a), PathBuf::from(b))),
+        _ => Err("diff takes two --output-json files".into()),
+    }
+}
+
+/// `gen` flags are consumed first; anything left over is a run option.
+fn parse_gen(args: &[String]) -> Result<Command, String> {
+    let (gen, rest) = GenOptions::parse(args.iter().cloned())?;
+    Ok(Command::Gen(gen, Options::parse(rest)?))
+}
+
+/// `gen-chain` flags likewise, the rest being run options.
+fn parse_gen_chain(args: &[String]) -> Result<Command, String> {
+    let (chain, rest) = ChainOptions::parse(args.iter().cloned())?;
+    Ok(Command::GenChain(chain, Options::parse(rest)?))
+}
+
+/// Seed for `--shuffle` without a value; printed in the header so the run
+/// can be repeated.
+fn clock_seed() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+fn parse_value<T: FromStr>(flag: &str, raw: &str) -> Result<T, String> {
+    raw.parse()
+        .map_err(|_| format!("invalid value for {flag}: {raw:?}"))
+}
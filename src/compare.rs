@@ -0,0 +1,204 @@
+//! Comparing contraction results element by element.
+
+use num_complex::Complex64;
+use strided_kernel::copy_into;
+use strided_opteinsum::{EinsumOperand, StridedData};
+use strided_view::StridedArray;
+
+use crate::cli::ChecksumMode;
+use crate::fill::SplitMix64;
+
+/// The elements of `data` in column-major order (the evaluator may return
+/// permuted strides).
+pub fn dense_col_major<T: Copy + Default + Send + Sync>(data: &StridedData<T>) -> Vec<T> {
+    let view = data.as_view();
+    let mut dest = StridedArray::<T>::col_major(view.dims());
+    copy_into(&mut dest.view_mut(), &view).expect("copy_into failed");
+    dest.data().to_vec()
+}
+
+/// Materialize a strided result in column-major order, widened to complex
+/// so both dtypes compare alike.
+pub fn col_major_values(op: &EinsumOperand) -> Vec<Complex64> {
+    match op {
+        EinsumOperand::F64(data) => dense_col_major(data)
+            .into_iter()
+            .map(|x| Complex64::new(x, 0.0))
+            .collect(),
+        EinsumOperand::C64(data) => dense_col_major(data),
+    }
+}
+
+/// `max |a - b| / max |reference|`, normalized by the largest reference
+/// element so entries near zero don't blow up the ratio.
+pub fn max_rel_error(reference: &[Complex64], other: &[Complex64]) -> f64 {
+    assert_eq!(reference.len(), other.len(), "result sizes differ");
+    let scale = reference.iter().map(|x| x.norm()).fold(0.0, f64::max);
+    let diff = reference
+        .iter()
+        .zip(other)
+        .map(|(a, b)| (a - b).norm())
+        .fold(0.0, f64::max);
+    if scale > 0.0 {
+        diff / scale
+    } else {
+        diff
+    }
+}
+
+/// Seed of the [`ChecksumMode::Weighted`] weights; fixed so checksums
+/// compare across runs and builds.
+const CHECKSUM_WEIGHT_SEED: u64 = 0xc4ec;
+
+/// `(checksum, scale)` of a result. The scale is the same reduction over
+/// magnitudes, which bounds how much rounding the checksum can pick up when
+/// terms cancel.
+///
+/// - [`ChecksumMode::SumReal`]: every real and imaginary part summed. Terms
+///   can cancel, so a result that is wrong by a permutation or by
+///   compensating errors can still match.
+/// - [`ChecksumMode::SumAbs`]: every element's modulus summed. Nothing
+///   cancels, but signs and phases are invisible.
+/// - [`ChecksumMode::Weighted`]: each real and imaginary part times its own
+///   weight in `[0.5, 1.5)`, drawn per position from a fixed seed. Moving a
+///   value to another element changes the sum, so the checksum follows
+///   individual elements rather than the total.
+pub fn checksum(values: &[Complex64], mode: ChecksumMode) -> (f64, f64) {
+    match mode {
+        ChecksumMode::SumReal => values.iter().fold((0.0, 0.0), |(sum, scale), x| {
+            (sum + x.re + x.im, scale + x.re.abs() + x.im.abs())
+        }),
+        ChecksumMode::SumAbs => {
+            let sum = values.iter().map(|x| x.norm()).sum();
+            (sum, sum)
+        }
+        ChecksumMode::Weighted => {
+            let mut rng = SplitMix64::new(CHECKSUM_WEIGHT_SEED);
+            let mut weight = || 1.0 + 0.5 * rng.next_f64();
+            values.iter().fold((0.0, 0.0), |(sum, scale), x| {
+                let (wr, wi) = (weight(), weight());
+                (
+                    sum + wr * x.re + wi * x.im,
+                    scale + wr * x.re.abs() + wi * x.im.abs(),
+                )
+            })
+        }
+    }
+}
+
+/// `None` when `a` and `b` are bit-for-bit identical (signs of zero and NaN
+/// payloads included), otherwise the largest absolute element difference.
+pub fn bitwise_diff(a: &[Complex64], b: &[Complex64]) -> Option<f64> {
+    assert_eq!(a.len(), b.len(), "result sizes differ");
+    let bits = |x: &Complex64| (x.re.to_bits(), x.im.to_bits());
+    if a.iter().zip(b).all(|(x, y)| bits(x) == bits(y)) {
+        return None;
+    }
+    Some(
+        a.iter()
+            .zip(b)
+            .map(|(x, y)| (x - y).norm())
+            .fold(0.0, f64::max),
+    )
+}
+
+/// Share of `values` whose magnitude is at most `rel` times the largest
+/// one, i.e. zero up to rounding. An all-zero result counts as all near-zero.
+pub fn near_zero_fraction(values: &[Complex64], rel: f64) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let max = values.iter().map(|x| x.norm()).fold(0.0, f64::max);
+    let near_zero = values.iter().filter(|x| x.norm() <= rel * max).count();
+    near_zero as f64 / values.len() as f64
+}
+
+/// Relative tolerance for two `f64` evaluations of the same contraction
+/// whose largest intermediate has `2^log2_size` elements.
+///
+/// Different backends (faer vs BLAS, NumPy) sum in different orders, and the
+/// rounding from reassociation grows roughly like `sqrt(n) * eps` in the
+/// number of accumulated terms. Scaling by the intermediate size keeps large
+/// instances from being flagged for ordinary rounding while still holding
+/// small ones to a tight bound.
+pub fn tolerance(log2_size: f64) -> f64 {
+    let n = log2_size.exp2().max(1.0);
+    (TOLERANCE_FACTOR * f64::EPSILON * n.sqrt()).max(TOLERANCE_FLOOR)
+}
+
+/// Headroom over the `sqrt(n) * eps` estimate.
+const TOLERANCE_FACTOR: f64 = 1024.0;
+/// Smallest tolerance used, for tiny contractions.
+const TOLERANCE_FLOOR: f64 = 1e-12;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn near_zero_is_relative_to_the_largest_element() {
+        let values: Vec<Complex64> = [4.0, 1e-14, 0.0, -2.0]
+            .iter()
+            .map(|&re| Complex64::new(re, 0.0))
+            .collect();
+        assert_eq!(near_zero_fraction(&values, 1e-12), 0.5);
+        assert_eq!(near_zero_fraction(&values[2..3], 1e-12), 1.0);
+        assert_eq!(near_zero_fraction(&[], 1e-12), 0.0);
+    }
+
+    #[test]
+    fn tolerance_grows_with_intermediate_size() {
+        assert_eq!(tolerance(0.0), TOLERANCE_FLOOR);
+        assert!(tolerance(24.0) > tolerance(16.0));
+        assert!(tolerance(24.0) < 1e-8);
+    }
+
+    #[test]
+    fn checksum_sums_both_parts() {
+        let v = [Complex64::new(1.5, -2.0), Complex64::new(-0.5, 4.0)];
+        assert_eq!(checksum(&v, ChecksumMode::SumReal), (3.0, 8.0));
+        let v = [Complex64::new(3.0, -4.0), Complex64::new(0.0, 1.0)];
+        assert_eq!(checksum(&v, ChecksumMode::SumAbs), (6.0, 6.0));
+    }
+
+    #[test]
+    fn weighted_checksum_sees_moved_elements() {
+        let v = [
+            Complex64::new(1.0, 2.0),
+            Complex64::new(-1.0, 0.5),
+            Complex64::new(0.0, -3.0),
+        ];
+        let swapped = [v[1], v[0], v[2]];
+        let sum = |v: &[Complex64], mode| checksum(v, mode).0;
+        assert_eq!(
+            sum(&v, ChecksumMode::SumReal),
+            sum(&swapped, ChecksumMode::SumReal)
+        );
+        assert_ne!(
+            sum(&v, ChecksumMode::Weighted),
+            sum(&swapped, ChecksumMode::Weighted)
+        );
+        // Fixed weights: the same result always gives the same checksum.
+        assert_eq!(
+            sum(&v, ChecksumMode::Weighted),
+            sum(&v, ChecksumMode::Weighted)
+        );
+    }
+
+    #[test]
+    fn bitwise_diff_sees_rounding_and_zero_sign() {
+        let a = [Complex64::new(1.0, 0.0), Complex64::new(0.1 + 0.2, 0.0)];
+        assert_eq!(bitwise_diff(&a, &a), None);
+        let b = [Complex64::new(1.0, 0.0), Complex64::new(0.3, 0.0)];
+        assert_eq!(bitwise_diff(&a, &b), Some((0.1 + 0.2) - 0.3));
+        let c = [Complex64::new(1.0, -0.0), a[1]];
+        assert_eq!(bitwise_diff(&a, &c), Some(0.0));
+    }
+
+    #[test]
+    fn rel_error_is_normalized_by_largest_reference() {
+        let a = [Complex64::new(100.0, 0.0), Complex64::new(1e-9, 0.0)];
+        let b = [Complex64::new(100.0, 0.0), Complex64::new(2e-9, 0.0)];
+        assert!(max_rel_error(&a, &b) < 1e-10);
+    }
+}
@@ -0,0 +1,176 @@
+//! `--cross-check`: compare small instances against NumPy's `einsum`.
+//!
+//! The seeded operands are written to a temporary JSON file and a Python
+//! subprocess contracts them twice: with the column-major format string on
+//! Fortran-ordered arrays (checks the contraction itself), and with the
+//! row-major `format_string`/`shapes` on C-ordered views of the same memory
+//! (checks the column-major transform of the dataset).
+
+use std::process::Command;
+
+use num_complex::Complex64;
+use serde::Deserialize;
+
+use crate::{compare, fill, plan, BenchmarkInstance, PathMeta};
+
+/// Instances whose operands or largest intermediate exceed this many
+/// elements are not cross-checked.
+pub const MAX_ELEMENTS: usize = 1 << 20;
+
+const SCRIPT: &str = r#"
+import json, sys
+import numpy as np
+
+with open(sys.argv[1]) as f:
+    job = json.load(f)
+
+def operand(flat, shape, order):
+    a = np.array(flat, dtype=float)
+    if job["dtype"] == "complex128":
+        a = a[:, 0] + 1j * a[:, 1]
+    return a.reshape(shape, order=order)
+
+def contract(fmt, shapes, order):
+    inputs, output = fmt.split("->")
+    labels = {}
+    for c in fmt.replace(",", "").replace("->", ""):
+        labels.setdefault(c, len(labels))
+    args = []
+    for flat, shape, ids in zip(job["operands"], shapes, inputs.split(",")):
+        args += [operand(flat, shape, order), [labels[c] for c in ids]]
+    args.append([labels[c] for c in output])
+    r = np.asarray(np.einsum(*args, optimize="greedy")).ravel(order=order)
+    if job["dtype"] == "complex128":
+        return [[x.real, x.imag] for x in r]
+    return r.tolist()
+
+out = {"colmajor": contract(job["format_colmajor"], job["shapes_colmajor"], "F")}
+if job["format"] is not None:
+    out["rowmajor"] = contract(job["format"], job["shapes"], "C")
+json.dump(out, sys.stdout)
+"#;
+
+#[derive(Deserialize)]
+struct Reply {
+    colmajor: Vec<serde_json::Value>,
+    rowmajor: Option<Vec<serde_json::Value>>,
+}
+
+/// Relative errors of the Rust result against NumPy.
+pub struct CrossCheck {
+    /// Against `einsum` on the column-major format string.
+    pub colmajor: f64,
+    /// Against `einsum` on the row-major `format_string`, when present.
+    pub rowmajor: Option<f64>,
+}
+
+/// The first of `python3`/`python` that can import numpy, if any.
+pub fn find_python() -> Option<&'static str> {
+    ["python3", "python"].into_iter().find(|py| {
+        Command::new(py)
+            .args(["-c", "import numpy"])
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false)
+    })
+}
+
+/// Whether `instance` is small enough to cross-check under `path_meta`.
+pub fn is_small(instance: &BenchmarkInstance, path_meta: &PathMeta) -> bool {
+    let inputs: usize = instance
+        .shapes_colmajor
+        .iter()
+        .map(|s| s.iter().product::<usize>())
+        .sum();
+    inputs <= MAX_ELEMENTS && path_meta.log2_size <= (MAX_ELEMENTS as f64).log2()
+}
+
+/// Contract `instance` on seeded data in Rust and in NumPy and compare.
+pub fn cross_check(
+    python: &str,
+    instance: &BenchmarkInstance,
+    path_meta: &PathMeta,
+) -> Result<CrossCheck, String> {
+    let operands = fill::random_operands(
+        &instance.shapes_colmajor,
+        &instance.dtype,
+        instance.hermitian.as_deref().unwrap_or(&[]),
+        fill::DEFAULT_SEED,
+        |x| x,
+    );
+    let flat: Vec<Vec<Complex64>> = operands.iter().map(compare::col_major_values).collect();
+    let result = plan(instance, path_meta)
+        .evaluate(operands, None)
+        .map_err(|e| format!("backend error: {e}"))?;
+    let ours = compare::col_major_values(&result);
+
+    let complex = instance.dtype == "complex128";
+    let encode = |values: &[Complex64]| -> Vec<serde_json::Value> {
+        values
+            .iter()
+            .map(|x| {
+                if complex {
+                    serde_json::json!([x.re, x.im])
+                } else {
+                    serde_json::json!(x.re)
+                }
+            })
+            .collect()
+    };
+    let job = serde_json::json!({
+        "dtype": instance.dtype,
+        "format_colmajor": instance.format_string_colmajor,
+        "shapes_colmajor": instance.shapes_colmajor,
+        "format": instance.format_string,
+        "shapes": instance.shapes,
+        "operands": flat.iter().map(|op| encode(op)).collect::<Vec<_>>(),
+    });
+
+    let path = std::env::temp_dir().join(format!(
+        "strided-cross-check-{}-{}.json",
+        std::process::id(),
+        instance.name
+    ));
+    std::fs::write(&path, job.to_string()).map_err(|e| format!("write {}: {e}", path.display()))?;
+    let output = Command::new(python)
+        .arg("-c")
+        .arg(SCRIPT)
+        .arg(&path)
+        .output();
+    let _ = std::fs::remove_file(&path);
+    let output = output.map_err(|e| format!("failed to run {python}: {e}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let last = stderr.lines().last().unwrap_or("").trim();
+        return Err(format!("numpy failed: {last}"));
+    }
+    let reply: Reply = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("unreadable numpy output: {e}"))?;
+
+    let decode = |values: Vec<serde_json::Value>| -> Result<Vec<Complex64>, String> {
+        values
+            .into_iter()
+            .map(|v| match v {
+                serde_json::Value::Array(pair) if pair.len() == 2 => Ok(Complex64::new(
+                    pair[0].as_f64().unwrap_or(f64::NAN),
+                    pair[1].as_f64().unwrap_or(f64::NAN),
+                )),
+                v => Ok(Complex64::new(
+                    v.as_f64().ok_or("non-numeric numpy output")?,
+                    0.0,
+                )),
+            })
+            .collect()
+    };
+    let error = |theirs: Vec<Complex64>| {
+        if theirs.len() == ours.len() {
+            compare::max_rel_error(&ours, &theirs)
+        } else {
+            f64::INFINITY
+        }
+    };
+    Ok(CrossCheck {
+        colmajor: error(decode(reply.colmajor)?),
+        rowmajor: reply.rowmajor.map(decode).transpose()?.map(error),
+    })
+}
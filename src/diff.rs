@@ -0,0 +1,113 @@
+//! `diff` subcommand: compare the medians of two `--output-json` files.
+//!
+//! Pure post-processing over the file schema ([`regress::read_baseline`]),
+//! no contraction machinery. Rows are `(instance, strategy)` pairs timed in
+//! both files, biggest change first; a change is measured as `|ln(b / a)|`,
+//! so a 2x slowdown and a 2x speedup rank the same.
+
+use std::path::Path;
+
+use crate::regress;
+
+/// One `(instance, strategy)` timed in both files.
+pub struct DiffRow {
+    pub instance: String,
+    pub strategy: String,
+    pub median_a: f64,
+    pub median_b: f64,
+}
+
+impl DiffRow {
+    /// `median_b / median_a`: above 1 when `b` is slower.
+    pub fn ratio(&self) -> f64 {
+        self.median_b / self.median_a
+    }
+}
+
+/// Rows timed in both files, biggest change first, plus how many entries
+/// only `a` and only `b` have a median for (a skip counts as missing).
+pub fn diff(a: &Path, b: &Path) -> Result<(Vec<DiffRow>, usize, usize), String> {
+    let a = regress::read_baseline(a)?;
+    let b = regress::read_baseline(b)?;
+    let mut rows: Vec<DiffRow> = a
+        .iter()
+        .filter_map(|(key, &median_a)| {
+            Some(DiffRow {
+                instance: key.0.clone(),
+                strategy: key.1.clone(),
+                median_a,
+                median_b: *b.get(key)?,
+            })
+        })
+        .collect();
+    sort_by_change(&mut rows);
+    let only_a = a.len() - rows.len();
+    let only_b = b.len() - rows.len();
+    Ok((rows, only_a, only_b))
+}
+
+fn sort_by_change(rows: &mut [DiffRow]) {
+    rows.sort_by(|x, y| {
+        let change = |r: &DiffRow| r.ratio().ln().abs();
+        change(y)
+            .total_cmp(&change(x))
+            .then_with(|| (&x.instance, &x.strategy).cmp(&(&y.instance, &y.strategy)))
+    });
+}
+
+/// Print the comparison table. Ratio cells end in `x`, so log parsers don't
+/// read the rows as results.
+pub fn print(a: &Path, b: &Path) -> Result<(), String> {
+    let (rows, only_a, only_b) = diff(a, b)?;
+    println!("A: {}", a.display());
+    println!("B: {}", b.display());
+    println!();
+    println!(
+        "{:<50} {:<10} {:>13} {:>13} {:>8}",
+        "Instance", "Strategy", "A (ms)", "B (ms)", "B/A"
+    );
+    println!("{}", "-".repeat(98));
+    for row in &rows {
+        println!(
+            "{:<50} {:<10} {:>13.3} {:>13.3} {:>7.2}x",
+            row.instance,
+            row.strategy,
+            row.median_a,
+            row.median_b,
+            row.ratio()
+        );
+    }
+    println!();
+    println!(
+        "{} compared; {only_a} timed only in A, {only_b} timed only in B",
+        rows.len()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(instance: &str, median_a: f64, median_b: f64) -> DiffRow {
+        DiffRow {
+            instance: instance.into(),
+            strategy: "opt_flops".into(),
+            median_a,
+            median_b,
+        }
+    }
+
+    #[test]
+    fn speedups_and_slowdowns_rank_by_factor() {
+        let mut rows = vec![
+            row("same", 1.0, 1.0),
+            row("slower_1.5x", 2.0, 3.0),
+            row("faster_3x", 3.0, 1.0),
+            row("slower_2x", 1.0, 2.0),
+        ];
+        sort_by_change(&mut rows);
+        let order: Vec<&str> = rows.iter().map(|r| r.instance.as_str()).collect();
+        assert_eq!(order, ["faster_3x", "slower_2x", "slower_1.5x", "same"]);
+    }
+}
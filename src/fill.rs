@@ -0,0 +1,155 @@
+//! Deterministic pseudo-random operand data.
+//!
+//! Timing runs use zero-filled operands; anything that compares results
+//! needs non-trivial data that is identical across runs and backends.
+
+use num_complex::Complex64;
+use strided_opteinsum::EinsumOperand;
+use strided_view::StridedArray;
+
+/// Seed used when a check needs random data and no seed was given.
+pub const DEFAULT_SEED: u64 = 0x5eed;
+
+/// SplitMix64: tiny, fast, and good enough for test data.
+pub struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        SplitMix64(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[-1, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 52) as f64 - 1.0
+    }
+}
+
+/// Column-major operands filled with uniform `[-1, 1)` values.
+///
+/// Each tensor draws from its own stream derived from `seed`, so operand `t`
+/// does not change when other shapes do. Operands flagged in `hermitian`
+/// (square rank-2, see [`validate_hermitian`]) are filled as Hermitian
+/// matrices: the upper triangle is drawn and mirrored conjugated, with a
+/// real diagonal. Every value is passed through `round` (e.g. to emulate
+/// reduced-precision storage).
+pub fn random_operands(
+    shapes: &[Vec<usize>],
+    dtype: &str,
+    hermitian: &[bool],
+    seed: u64,
+    round: fn(f64) -> f64,
+) -> Vec<EinsumOperand<'static>> {
+    shapes
+        .iter()
+        .enumerate()
+        .map(|(t, shape)| {
+            let mut rng = SplitMix64::new(seed ^ (t as u64).wrapping_mul(0xd1b5_4a32_d192_ed03));
+            let herm = hermitian.get(t).copied().unwrap_or(false);
+            match dtype {
+                "float64" => {
+                    let mut arr = StridedArray::<f64>::col_major(shape);
+                    fill(arr.data_mut(), shape, herm, || round(rng.next_f64()), |x| x);
+                    EinsumOperand::from(arr)
+                }
+                "complex128" => {
+                    let mut arr = StridedArray::<Complex64>::col_major(shape);
+                    let mut draw = || Complex64::new(round(rng.next_f64()), round(rng.next_f64()));
+                    if herm {
+                        fill(arr.data_mut(), shape, true, draw, |x| x.conj());
+                        // A Hermitian diagonal is real.
+                        let n = shape[0];
+                        for i in 0..n {
+                            arr.data_mut()[i * (n + 1)].im = 0.0;
+                        }
+                    } else {
+                        arr.data_mut().iter_mut().for_each(|x| *x = draw());
+                    }
+                    EinsumOperand::from(arr)
+                }
+                other => panic!("unsupported dtype: {other}"),
+            }
+        })
+        .collect()
+}
+
+/// Fill column-major `data`; with `hermitian`, draw the upper triangle of the
+/// square matrix and mirror it through `mirror`.
+fn fill<T: Copy>(
+    data: &mut [T],
+    shape: &[usize],
+    hermitian: bool,
+    mut draw: impl FnMut() -> T,
+    mirror: impl Fn(T) -> T,
+) {
+    if !hermitian {
+        data.iter_mut().for_each(|x| *x = draw());
+        return;
+    }
+    let n = shape[0];
+    for j in 0..n {
+        for i in 0..=j {
+            let v = draw();
+            data[i + j * n] = v;
+            data[j + i * n] = mirror(v);
+        }
+    }
+}
+
+/// Check `hermitian` hints against the operand shapes: one flag per
+/// operand, and flagged operands must be square rank-2.
+pub fn validate_hermitian(hermitian: &[bool], shapes: &[Vec<usize>]) -> Result<(), String> {
+    if hermitian.len() != shapes.len() {
+        return Err(format!(
+            "{} hermitian flags for {} operands",
+            hermitian.len(),
+            shapes.len()
+        ));
+    }
+    for (t, (&herm, shape)) in hermitian.iter().zip(shapes).enumerate() {
+        if herm && !(shape.len() == 2 && shape[0] == shape[1]) {
+            return Err(format!(
+                "operand {t} is flagged hermitian but has shape {shape:?} (needs square rank-2)"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Round to the nearest `f32`, keeping `f64` storage.
+pub fn round_f32(x: f64) -> f64 {
+    x as f32 as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compare::col_major_values;
+
+    #[test]
+    fn hermitian_fill_is_hermitian() {
+        let ops = random_operands(&[vec![4, 4]], "complex128", &[true], DEFAULT_SEED, |x| x);
+        let a = col_major_values(&ops[0]);
+        for i in 0..4 {
+            for j in 0..4 {
+                assert_eq!(a[i + 4 * j], a[j + 4 * i].conj());
+            }
+        }
+        assert!(a.iter().any(|x| x.im != 0.0));
+    }
+
+    #[test]
+    fn hermitian_hint_requires_square_rank2() {
+        assert!(validate_hermitian(&[true, false], &[vec![3, 3], vec![3, 4, 5]]).is_ok());
+        assert!(validate_hermitian(&[true], &[vec![3, 4]]).is_err());
+        assert!(validate_hermitian(&[true], &[vec![3, 3, 3]]).is_err());
+        assert!(validate_hermitian(&[false], &[vec![3], vec![3]]).is_err());
+    }
+}
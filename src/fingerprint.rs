@@ -0,0 +1,93 @@
+//! Size-independent structural fingerprint of a contraction.
+//!
+//! The contraction is viewed as a bipartite graph between operands (plus the
+//! output) and index labels; dimensions are ignored. Colors are refined
+//! Weisfeiler–Lehman style until stable and the final color multiset is
+//! hashed. Relabeling indices or reordering operands does not change the
+//! fingerprint, so two instances with equal fingerprints are the same
+//! network at different bond dimensions (WL can, rarely, also merge
+//! structurally different graphs).
+
+use std::collections::HashMap;
+
+/// FNV-1a, chosen over `DefaultHasher` so fingerprints stay stable across
+/// Rust releases and can be compared between runs.
+fn fnv1a(words: impl IntoIterator<Item = u64>) -> u64 {
+    let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+    for w in words {
+        for b in w.to_le_bytes() {
+            h ^= u64::from(b);
+            h = h.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+    h
+}
+
+const TENSOR: u64 = 1;
+const OUTPUT: u64 = 2;
+const LABEL: u64 = 3;
+
+/// Structural fingerprint of `inputs -> output`.
+pub fn fingerprint(input_indices: &[Vec<char>], output_indices: &[char]) -> u64 {
+    // Operand nodes 0..n, the output node n, then one node per label.
+    let mut label_node: HashMap<char, usize> = HashMap::new();
+    let operands: Vec<&[char]> = input_indices
+        .iter()
+        .map(Vec::as_slice)
+        .chain(std::iter::once(output_indices))
+        .collect();
+    let first_label = operands.len();
+    let mut adj: Vec<Vec<usize>> = vec![Vec::new(); operands.len()];
+    for (t, ids) in operands.iter().enumerate() {
+        for &id in *ids {
+            let node = *label_node.entry(id).or_insert_with(|| {
+                adj.push(Vec::new());
+                adj.len() - 1
+            });
+            // Repeated labels (traces, diagonals) keep their multiplicity.
+            adj[t].push(node);
+            adj[node].push(t);
+        }
+    }
+
+    let mut colors: Vec<u64> = (0..adj.len())
+        .map(|v| {
+            let kind = if v < first_label - 1 {
+                TENSOR
+            } else if v == first_label - 1 {
+                OUTPUT
+            } else {
+                LABEL
+            };
+            fnv1a([kind, adj[v].len() as u64])
+        })
+        .collect();
+
+    let distinct = |c: &[u64]| {
+        let mut v = c.to_vec();
+        v.sort_unstable();
+        v.dedup();
+        v.len()
+    };
+    let mut classes = distinct(&colors);
+    for _ in 0..adj.len() {
+        let next: Vec<u64> = adj
+            .iter()
+            .enumerate()
+            .map(|(v, nbrs)| {
+                let mut nc: Vec<u64> = nbrs.iter().map(|&u| colors[u]).collect();
+                nc.sort_unstable();
+                fnv1a(std::iter::once(colors[v]).chain(nc))
+            })
+            .collect();
+        let next_classes = distinct(&next);
+        colors = next;
+        if next_classes == classes {
+            break;
+        }
+        classes = next_classes;
+    }
+
+    colors.sort_unstable();
+    fnv1a(colors)
+}
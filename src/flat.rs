@@ -0,0 +1,188 @@
+//! `--flat`: the `np.einsum(..., optimize=False)` baseline.
+//!
+//! strided-opteinsum has no flat evaluation: an n-ary `Contract` node is
+//! handed to its greedy optimizer and still runs pairwise. This module
+//! instead loops once over every combination of every label, multiplying
+//! one element of each operand per iteration, as NumPy's unoptimized
+//! `c_einsum` does. The cost is the product of all dimensions, which is what
+//! the path optimizer saves; only instances under [`MAX_LOG10_ITERATIONS`]
+//! are run.
+
+use std::collections::HashMap;
+use std::ops::{AddAssign, Mul};
+
+use num_complex::Complex64;
+use strided_opteinsum::{EinsumOperand, StridedData};
+use strided_view::StridedArray;
+
+/// Largest flat iteration space (`log10` of the product of all dims) run.
+pub const MAX_LOG10_ITERATIONS: f64 = 9.0;
+
+/// Distinct labels in order of first appearance, inputs then output.
+fn all_labels(input_indices: &[Vec<char>], output_indices: &[char]) -> Vec<char> {
+    let mut labels: Vec<char> = Vec::new();
+    for &id in input_indices.iter().flatten().chain(output_indices) {
+        if !labels.contains(&id) {
+            labels.push(id);
+        }
+    }
+    labels
+}
+
+/// `log10` of the flat loop's iteration count: the product of every
+/// label's dimension (the dataset's FLOP convention for a single step).
+pub fn log10_iterations(
+    input_indices: &[Vec<char>],
+    output_indices: &[char],
+    dims: &HashMap<char, usize>,
+) -> f64 {
+    all_labels(input_indices, output_indices)
+        .iter()
+        .map(|id| (dims[id] as f64).log10())
+        .sum()
+}
+
+/// Column-major stride of each label in a tensor with `ids`; a label that
+/// repeats (`aa`) sums its strides, so the loop walks the diagonal.
+fn label_strides(ids: &[char], labels: &[char], dims: &HashMap<char, usize>) -> Vec<isize> {
+    let mut strides = vec![0isize; labels.len()];
+    let mut s = 1isize;
+    for id in ids {
+        let l = labels.iter().position(|x| x == id).unwrap();
+        strides[l] += s;
+        s *= dims[id] as isize;
+    }
+    strides
+}
+
+fn contract<T>(
+    inputs: &[&[T]],
+    in_strides: &[Vec<isize>],
+    out: &mut [T],
+    out_strides: &[isize],
+    extents: &[usize],
+) where
+    T: Copy + Mul<Output = T> + AddAssign,
+{
+    let n = extents.len();
+    let mut idx = vec![0usize; n];
+    let mut offs = vec![0isize; inputs.len()];
+    let mut out_off = 0isize;
+    let total: usize = extents.iter().product();
+    for _ in 0..total {
+        let mut prod = inputs[0][offs[0] as usize];
+        for (t, input) in inputs.iter().enumerate().skip(1) {
+            prod = prod * input[offs[t] as usize];
+        }
+        out[out_off as usize] += prod;
+        for d in 0..n {
+            idx[d] += 1;
+            for (off, s) in offs.iter_mut().zip(in_strides) {
+                *off += s[d];
+            }
+            out_off += out_strides[d];
+            if idx[d] < extents[d] {
+                break;
+            }
+            let back = idx[d] as isize;
+            for (off, s) in offs.iter_mut().zip(in_strides) {
+                *off -= back * s[d];
+            }
+            out_off -= back * out_strides[d];
+            idx[d] = 0;
+        }
+    }
+}
+
+/// Column-major buffer of an owned operand (as `create_operands` and
+/// `fill::random_operands` build them).
+fn dense<'a, T>(data: &'a StridedData<'_, T>) -> &'a [T] {
+    data.as_array().data()
+}
+
+/// Contract `operands` (column-major, owned, all one dtype) in a single
+/// flat loop. Returns the column-major output.
+pub fn evaluate(
+    input_indices: &[Vec<char>],
+    output_indices: &[char],
+    dims: &HashMap<char, usize>,
+    operands: &[EinsumOperand<'_>],
+) -> Result<EinsumOperand<'static>, String> {
+    if operands.is_empty() {
+        return Err("no operands".into());
+    }
+    let labels = all_labels(input_indices, output_indices);
+    let extents: Vec<usize> = labels.iter().map(|id| dims[id]).collect();
+    let in_strides: Vec<Vec<isize>> = input_indices
+        .iter()
+        .map(|ids| label_strides(ids, &labels, dims))
+        .collect();
+    let out_strides = label_strides(output_indices, &labels, dims);
+    let out_shape: Vec<usize> = output_indices.iter().map(|id| dims[id]).collect();
+    match &operands[0] {
+        EinsumOperand::F64(_) => {
+            let inputs = operands
+                .iter()
+                .map(|op| match op {
+                    EinsumOperand::F64(data) => Ok(dense(data)),
+                    EinsumOperand::C64(_) => Err("mixed dtypes".to_string()),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut out = StridedArray::<f64>::col_major(&out_shape);
+            contract(&inputs, &in_strides, out.data_mut(), &out_strides, &extents);
+            Ok(out.into())
+        }
+        EinsumOperand::C64(_) => {
+            let inputs = operands
+                .iter()
+                .map(|op| match op {
+                    EinsumOperand::C64(data) => Ok(dense(data)),
+                    EinsumOperand::F64(_) => Err("mixed dtypes".to_string()),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut out = StridedArray::<Complex64>::col_major(&out_shape);
+            contract(&inputs, &in_strides, out.data_mut(), &out_strides, &extents);
+            Ok(out.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compare::{col_major_values, max_rel_error};
+    use crate::{build_contraction_tree, fill, parse_format_string, path};
+    use strided_opteinsum::EinsumCode;
+
+    #[test]
+    fn flat_loop_matches_pairwise_tree() {
+        let (inputs, output) = parse_format_string("ab,bc,cad->db");
+        let shapes = vec![vec![2, 3], vec![3, 4], vec![4, 2, 5]];
+        let dims = path::dim_map(&inputs, &shapes).unwrap();
+        assert!((log10_iterations(&inputs, &output, &dims) - 120f64.log10()).abs() < 1e-12);
+        for dtype in ["float64", "complex128"] {
+            let seeded = || fill::random_operands(&shapes, dtype, &[], 3, |x| x);
+            let flat = evaluate(&inputs, &output, &dims, &seeded()).unwrap();
+            let code = EinsumCode {
+                root: build_contraction_tree(&inputs, &[[0, 1], [0, 1]]),
+                output_ids: output.clone(),
+            };
+            let tree = code.evaluate(seeded(), None).unwrap();
+            assert!(max_rel_error(&col_major_values(&tree), &col_major_values(&flat)) < 1e-12);
+        }
+    }
+
+    #[test]
+    fn repeated_label_walks_the_diagonal() {
+        // aa,ab->b: the pairwise backend rejects this; the flat loop doesn't.
+        let (inputs, output) = parse_format_string("aa,ab->b");
+        let dims = path::dim_map(&inputs, &[vec![2, 2], vec![2, 3]]).unwrap();
+        let diag =
+            StridedArray::<f64>::from_fn_col_major(&[2, 2], |i| (1 + i[0] + 2 * i[1]) as f64);
+        let ones = StridedArray::<f64>::from_fn_col_major(&[2, 3], |_| 1.0);
+        let out = evaluate(&inputs, &output, &dims, &[diag.into(), ones.into()]).unwrap();
+        // diag = [[1, 3], [2, 4]]: trace 1 + 4 = 5 in every output entry.
+        let values: Vec<f64> = col_major_values(&out).iter().map(|c| c.re).collect();
+        assert_eq!(values, vec![5.0; 3]);
+    }
+}
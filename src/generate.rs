@@ -0,0 +1,241 @@
+//! `gen` subcommand: build a synthetic instance from a format string and a
+//! dimension map, without going through the Python generator.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::{fill, path};
+use crate::{parse_format_string, BenchmarkInstance, PathInfo, PathMeta};
+
+pub const USAGE: &str = "\
+Usage: strided-rs-benchmark-suite gen --format <EINSUM> --dims <a=N,b=M,...> [OPTIONS]
+
+Builds an instance (column-major labels, linear contraction path) and runs it,
+or writes it as instance JSON with --out.
+
+Any other flag is passed to the regular runner (e.g. --blas-threads).
+
+Options:
+  --format <EINSUM>    Column-major format string, e.g. \"ab,bc->ac\"
+  --dims <LIST>        Comma-separated label sizes, e.g. a=100,b=200,c=50
+  --dtype <DTYPE>      float64 (default) or complex128
+  --name <NAME>        Instance name (default: gen_<format>)
+  --hermitian <LIST>   Operand indices filled as Hermitian in seeded data, e.g. 0,2
+  --out <PATH>         Write instance JSON instead of running it
+  -h, --help           Print this help
+";
+
+#[derive(Debug, Default)]
+pub struct GenOptions {
+    pub format: String,
+    pub dims: HashMap<char, usize>,
+    pub dtype: String,
+    pub name: Option<String>,
+    /// Operand indices carrying a Hermitian hint.
+    pub hermitian: Vec<usize>,
+    pub out: Option<PathBuf>,
+}
+
+impl GenOptions {
+    /// Parse `gen` flags, returning the unrecognised arguments for the
+    /// regular run options.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<(Self, Vec<String>), String> {
+        let mut opts = GenOptions {
+            dtype: "float64".into(),
+            ..Default::default()
+        };
+        let mut rest = Vec::new();
+        let mut args = args.into_iter();
+        while let Some(flag) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("{flag} requires a value"))
+            };
+            match flag.as_str() {
+                "--format" => opts.format = value()?,
+                "--dims" => opts.dims = parse_dims(&value()?)?,
+                "--dtype" => opts.dtype = value()?,
+                "--name" => opts.name = Some(value()?),
+                "--hermitian" => {
+                    opts.hermitian = value()?
+                        .split(',')
+                        .map(|t| {
+                            t.trim()
+                                .parse()
+                                .map_err(|_| format!("invalid --hermitian operand index {t:?}"))
+                        })
+                        .collect::<Result<_, _>>()?
+                }
+                "--out" => opts.out = Some(PathBuf::from(value()?)),
+                "-h" | "--help" => {
+                    print!("{USAGE}");
+                    std::process::exit(0);
+                }
+                _ => rest.push(flag.clone()),
+            }
+        }
+        if opts.format.is_empty() {
+            return Err("gen requires --format".into());
+        }
+        if !matches!(opts.dtype.as_str(), "float64" | "complex128") {
+            return Err(format!("unsupported dtype: {}", opts.dtype));
+        }
+        Ok((opts, rest))
+    }
+}
+
+/// Parse `a=100,b=200` into a label -> size map.
+fn parse_dims(s: &str) -> Result<HashMap<char, usize>, String> {
+    let mut dims = HashMap::new();
+    for entry in s.split(',').filter(|e| !e.is_empty()) {
+        let (label, size) = entry
+            .split_once('=')
+            .ok_or_else(|| format!("invalid --dims entry {entry:?} (expected label=size)"))?;
+        let mut chars = label.trim().chars();
+        let (Some(id), None) = (chars.next(), chars.next()) else {
+            return Err(format!("--dims label {label:?} must be a single character"));
+        };
+        let size: usize = size
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid size in --dims entry {entry:?}"))?;
+        if dims.insert(id, size).is_some() {
+            return Err(format!("label '{id}' given twice in --dims"));
+        }
+    }
+    Ok(dims)
+}
+
+/// Build the instance described by `opts`.
+pub fn build_instance(opts: &GenOptions) -> Result<BenchmarkInstance, String> {
+    if !opts.format.contains("->") {
+        return Err("--format must contain '->'".into());
+    }
+    let (input_indices, output_indices) = parse_format_string(&opts.format);
+
+    let mut shapes = Vec::with_capacity(input_indices.len());
+    for ids in &input_indices {
+        let shape = ids
+            .iter()
+            .map(|id| {
+                opts.dims
+                    .get(id)
+                    .copied()
+                    .ok_or_else(|| format!("label '{id}' has no size in --dims"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        shapes.push(shape);
+    }
+    for id in &output_indices {
+        if !input_indices.iter().any(|ids| ids.contains(id)) {
+            return Err(format!("output label '{id}' does not appear in any input"));
+        }
+    }
+    if let Some(extra) = opts
+        .dims
+        .keys()
+        .find(|id| !input_indices.iter().any(|ids| ids.contains(id)))
+    {
+        return Err(format!(
+            "--dims label '{extra}' is not used by the format string"
+        ));
+    }
+
+    let hermitian = if opts.hermitian.is_empty() {
+        None
+    } else {
+        let mut flags = vec![false; shapes.len()];
+        for &t in &opts.hermitian {
+            *flags
+                .get_mut(t)
+                .ok_or_else(|| format!("--hermitian operand {t} out of range"))? = true;
+        }
+        fill::validate_hermitian(&flags, &shapes)?;
+        Some(flags)
+    };
+
+    let path = path::linear_path(input_indices.len());
+    let dims = path::dim_map(&input_indices, &shapes)?;
+    let steps = path::simulate(&input_indices, &output_indices, &dims, &path)?;
+    let (log10_flops, log2_size) = path::cost(&steps);
+    let meta = || PathMeta {
+        path: path.clone(),
+        log2_size,
+        log10_flops,
+    };
+
+    Ok(BenchmarkInstance {
+        name: opts.name.clone().unwrap_or_else(|| {
+            format!(
+                "gen_{}",
+                opts.format.replace(',', "_").replace("->", "_to_")
+            )
+        }),
+        format_string: None,
+        shapes: None,
+        hermitian,
+        alpha: None,
+        beta: None,
+        expected_checksum: None,
+        outputs_colmajor: None,
+        expected_output_rank: None,
+        skip_strategies: Vec::new(),
+        buffer_size: None,
+        views: None,
+        weight: None,
+        format_string_colmajor: opts.format.clone(),
+        shapes_colmajor: shapes,
+        dtype: opts.dtype.clone(),
+        num_tensors: input_indices.len(),
+        paths: PathInfo {
+            opt_size: meta(),
+            opt_flops: meta(),
+            physical: PathMeta::missing(),
+        },
+        source: None,
+    })
+}
+
+/// Serialize an instance in the on-disk schema, including the row-major
+/// fields that `generate_dataset.py` writes (labels and shapes reversed).
+pub fn instance_json(instance: &BenchmarkInstance) -> serde_json::Value {
+    let (inputs, output) = instance
+        .format_string_colmajor
+        .split_once("->")
+        .expect("format_string must contain '->'");
+    let reverse = |s: &str| s.chars().rev().collect::<String>();
+    let format_string = format!(
+        "{}->{}",
+        inputs.split(',').map(reverse).collect::<Vec<_>>().join(","),
+        reverse(output)
+    );
+    let shapes: Vec<Vec<usize>> = instance
+        .shapes_colmajor
+        .iter()
+        .map(|s| s.iter().rev().copied().collect())
+        .collect();
+    let meta = |m: &PathMeta| {
+        serde_json::json!({
+            "path": m.path,
+            "log2_size": m.log2_size,
+            "log10_flops": m.log10_flops,
+        })
+    };
+    let mut json = serde_json::json!({
+        "name": instance.name,
+        "format_string": format_string,
+        "shapes": shapes,
+        "dtype": instance.dtype,
+        "num_tensors": instance.num_tensors,
+        "paths": {
+            "opt_size": meta(&instance.paths.opt_size),
+            "opt_flops": meta(&instance.paths.opt_flops),
+        },
+        "format_string_colmajor": instance.format_string_colmajor,
+        "shapes_colmajor": instance.shapes_colmajor,
+    });
+    if let Some(hermitian) = &instance.hermitian {
+        json["hermitian"] = serde_json::json!(hermitian);
+    }
+    json
+}
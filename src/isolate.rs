@@ -0,0 +1,183 @@
+//! `--isolate-step <instance>:<step>`: time one path step on its own.
+//!
+//! The step's two operands are built fresh, zero-filled and column-major,
+//! with the labels they carry at that point of the path (an intermediate's
+//! in the canonical order [`path::simulate`] replays), so the contraction
+//! permutes them into GEMM order as in the full run. Only the label order
+//! is reproduced, not the strides the full run left an intermediate with.
+//! Like the `tn_light_415_late_step` micro-benchmark, the step is also
+//! timed in parts: the copy each operand needs to become GEMM-contiguous,
+//! and the contraction again on operands already stored in GEMM order,
+//! which is about the GEMM alone.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use strided_einsum2::Einsum2Plan;
+use strided_opteinsum::{EinsumCode, EinsumError, EinsumNode, EinsumOperand};
+
+use crate::path::{self, PathStep};
+use crate::trace::prepare_operand_time;
+use crate::{create_operands, parse_format_string, BenchmarkInstance, PathMeta};
+
+/// One isolated step and the medians of its parts.
+pub struct IsolatedStep {
+    /// The step as replayed on labels.
+    pub step: PathStep,
+    pub left_shape: Vec<usize>,
+    pub right_shape: Vec<usize>,
+    /// The whole binary contraction, output allocation included.
+    pub full: Duration,
+    /// `prepare_input_owned` on each operand (zero when it needs no copy).
+    pub copy_left: Duration,
+    pub copy_right: Duration,
+    /// The contraction on operands already in GEMM order; `None` when the
+    /// step first sums out labels only one operand carries, so there is no
+    /// single GEMM order to store them in.
+    pub gemm: Option<Duration>,
+}
+
+/// Binary tree contracting leaves 0 and 1 into `output`.
+fn pair_code(left: &[char], right: &[char], output: &[char]) -> EinsumCode {
+    let leaf = |ids: &[char], tensor_index| EinsumNode::Leaf {
+        ids: ids.to_vec(),
+        tensor_index,
+    };
+    EinsumCode {
+        root: EinsumNode::Contract {
+            args: vec![leaf(left, 0), leaf(right, 1)],
+        },
+        output_ids: output.to_vec(),
+    }
+}
+
+/// Median of `runs` samples after `warmup` discarded ones.
+fn median(
+    warmup: usize,
+    runs: usize,
+    mut sample: impl FnMut() -> Result<Duration, EinsumError>,
+) -> Result<Duration, EinsumError> {
+    for _ in 0..warmup {
+        sample()?;
+    }
+    let mut samples = (0..runs).map(|_| sample()).collect::<Result<Vec<_>, _>>()?;
+    samples.sort();
+    Ok(samples[samples.len() / 2])
+}
+
+/// Time step `step` (0-based) of `path_meta`'s path in isolation.
+pub fn isolate_step(
+    instance: &BenchmarkInstance,
+    path_meta: &PathMeta,
+    step: usize,
+    warmup: usize,
+    runs: usize,
+) -> Result<IsolatedStep, String> {
+    let (input_indices, output_indices) = parse_format_string(&instance.format_string_colmajor);
+    let dims = path::dim_map(&input_indices, &instance.shapes_colmajor)?;
+    let steps = path::simulate(&input_indices, &output_indices, &dims, &path_meta.path)?;
+    let Some(step) = steps.into_iter().nth(step) else {
+        return Err(format!(
+            "step {step} out of range: the path has {} steps",
+            path_meta.path.len()
+        ));
+    };
+    let shape = |ids: &[char]| ids.iter().map(|id| dims[id]).collect::<Vec<usize>>();
+    let (left_shape, right_shape) = (shape(&step.left), shape(&step.right));
+    let operands =
+        |l: &[usize], r: &[usize]| create_operands(&[l.to_vec(), r.to_vec()], &instance.dtype);
+    let err = |e: EinsumError| e.to_string();
+
+    let code = pair_code(&step.left, &step.right, &step.output);
+    let full = median(warmup, runs, || {
+        let ops = operands(&left_shape, &right_shape);
+        let t0 = Instant::now();
+        black_box(code.evaluate(ops, None)?);
+        Ok(t0.elapsed())
+    })
+    .map_err(err)?;
+
+    let plan =
+        Einsum2Plan::new(&step.left, &step.right, &step.output).map_err(|e| e.to_string())?;
+    let (mut copy_left, mut copy_right) = (Vec::with_capacity(runs), Vec::with_capacity(runs));
+    for _ in 0..runs {
+        let ops = operands(&left_shape, &right_shape);
+        let (l, r) = match (&ops[0], &ops[1]) {
+            (EinsumOperand::F64(a), EinsumOperand::F64(b)) => (
+                prepare_operand_time(a, &plan.left_perm, plan.lo.len(), plan.sum.len()),
+                prepare_operand_time(b, &plan.right_perm, plan.sum.len(), plan.ro.len()),
+            ),
+            (EinsumOperand::C64(a), EinsumOperand::C64(b)) => (
+                prepare_operand_time(a, &plan.left_perm, plan.lo.len(), plan.sum.len()),
+                prepare_operand_time(b, &plan.right_perm, plan.sum.len(), plan.ro.len()),
+            ),
+            _ => (Duration::ZERO, Duration::ZERO),
+        };
+        copy_left.push(l);
+        copy_right.push(r);
+    }
+    copy_left.sort();
+    copy_right.sort();
+
+    let in_gemm_order =
+        plan.left_perm.len() == step.left.len() && plan.right_perm.len() == step.right.len();
+    let gemm = if in_gemm_order {
+        let permute =
+            |ids: &[char], perm: &[usize]| perm.iter().map(|&p| ids[p]).collect::<Vec<char>>();
+        let (left, right) = (
+            permute(&step.left, &plan.left_perm),
+            permute(&step.right, &plan.right_perm),
+        );
+        let (ls, rs) = (shape(&left), shape(&right));
+        let code = pair_code(&left, &right, &step.output);
+        let gemm = median(warmup, runs, || {
+            let ops = operands(&ls, &rs);
+            let t0 = Instant::now();
+            black_box(code.evaluate(ops, None)?);
+            Ok(t0.elapsed())
+        })
+        .map_err(err)?;
+        Some(gemm)
+    } else {
+        None
+    };
+
+    Ok(IsolatedStep {
+        step,
+        left_shape,
+        right_shape,
+        full,
+        copy_left: copy_left[runs / 2],
+        copy_right: copy_right[runs / 2],
+        gemm,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::{self, GenOptions};
+
+    #[test]
+    fn isolated_step_has_the_replayed_operands() {
+        let instance = generate::build_instance(&GenOptions {
+            format: "ab,cb,cd->ad".into(),
+            dims: [('a', 2), ('b', 3), ('c', 4), ('d', 5)]
+                .into_iter()
+                .collect(),
+            dtype: "float64".into(),
+            ..GenOptions::default()
+        })
+        .unwrap();
+        let path_meta = &instance.paths.opt_flops;
+        // Step 1 contracts T2 (now first in the list) with the (T0 T1)
+        // intermediate appended after it.
+        let isolated = isolate_step(&instance, path_meta, 1, 0, 3).unwrap();
+        assert_eq!(isolated.step.left, vec!['c', 'd']);
+        assert_eq!(isolated.step.right, vec!['a', 'c']);
+        assert_eq!(isolated.left_shape, vec![4, 5]);
+        assert_eq!(isolated.right_shape, vec![2, 4]);
+        assert!(isolated.gemm.is_some());
+        assert!(isolate_step(&instance, path_meta, 2, 0, 1).is_err());
+    }
+}
@@ -1,10 +1,50 @@
+use std::collections::{BTreeMap, HashMap};
 use std::hint::black_box;
-use std::path::Path;
-use std::time::Instant;
+use std::io::{IsTerminal, Read};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 use serde::Deserialize;
-use strided_opteinsum::{EinsumCode, EinsumError, EinsumNode, EinsumOperand};
+use strided_opteinsum::{BufferPool, EinsumCode, EinsumError, EinsumOperand};
 use strided_view::StridedArray;
+use tracing::{debug, error, info, warn};
+use tracing_subscriber::EnvFilter;
+
+mod affinity;
+mod bandwidth;
+mod bisect;
+mod blas;
+mod chain;
+mod cli;
+mod compare;
+mod crosscheck;
+mod diff;
+mod fill;
+mod fingerprint;
+mod flat;
+mod generate;
+mod hugepages;
+mod isolate;
+mod memory;
+mod multi;
+mod npy;
+mod path;
+mod prefix;
+mod regress;
+mod report;
+mod shared;
+mod sqlite;
+mod trace;
+mod tree;
+mod validate;
+
+use cli::{
+    ChecksumMode, Command, FlopsUnit, IndexFormat, Layout, Options, PeakBandwidth, RowFormat,
+};
+use tree::build_contraction_tree;
+
+#[global_allocator]
+static GLOBAL: memory::TrackingAlloc = memory::TrackingAlloc;
 
 // ---------------------------------------------------------------------------
 // JSON schema
@@ -13,17 +53,159 @@ use strided_view::StridedArray;
 #[derive(Deserialize)]
 struct BenchmarkInstance {
     name: String,
+    /// Row-major originals; absent for hand-written or generated instances.
+    #[serde(default)]
+    format_string: Option<String>,
+    #[serde(default)]
+    shapes: Option<Vec<Vec<usize>>>,
+    /// Per-operand Hermitian hint for seeded data (square rank-2 only).
+    #[serde(default)]
+    hermitian: Option<Vec<bool>>,
+    /// Scaling of the final contraction, `out = alpha * result + beta * out`
+    /// (default 1, 0). The real value is used for complex instances too.
+    #[serde(default)]
+    alpha: Option<f64>,
+    #[serde(default)]
+    beta: Option<f64>,
+    /// Known-good [`compare::checksum`] of the result on seeded data
+    /// (`fill::DEFAULT_SEED`), checked by `--verify-checksum` in the
+    /// `--checksum-mode` it was pinned with.
+    #[serde(default)]
+    expected_checksum: Option<f64>,
+    /// Several output label sets computed from the same network
+    /// (`--multi-output`), e.g. `["ad", "c"]`. The format string's own
+    /// output is what the regular timing uses.
+    #[serde(default)]
+    outputs_colmajor: Option<Vec<String>>,
+    /// Rank the format string's output must have; a mismatch aborts the run
+    /// before anything is timed.
+    #[serde(default)]
+    expected_output_rank: Option<usize>,
+    /// Strategies not run for this instance (`"opt_flops"`, `"opt_size"`,
+    /// `"physical"`); their rows print `n/a` and their `paths` entry may be
+    /// omitted.
+    #[serde(default)]
+    skip_strategies: Vec<String>,
+    /// Elements of one buffer every operand is a view into, with one
+    /// [`shared::ViewLayout`] per operand in `views`; without them, each
+    /// operand is its own column-major array.
+    #[serde(default)]
+    buffer_size: Option<usize>,
+    #[serde(default)]
+    views: Option<Vec<shared::ViewLayout>>,
+    /// Relative frequency of the instance in the workload the dataset
+    /// stands for (default 1), weighting its GFLOP/s in the summary's
+    /// geometric mean.
+    #[serde(default)]
+    weight: Option<f64>,
     format_string_colmajor: String,
     shapes_colmajor: Vec<Vec<usize>>,
     dtype: String,
     num_tensors: usize,
     paths: PathInfo,
+    /// File the instance was read from (`--include-load`).
+    #[serde(skip)]
+    source: Option<PathBuf>,
+}
+
+impl BenchmarkInstance {
+    /// Also true for `physical` when the instance has no such path.
+    fn skips(&self, strategy: &str) -> bool {
+        self.skip_strategies.iter().any(|s| s == strategy)
+            || (strategy == "physical" && self.paths.physical.is_missing())
+    }
+
+    /// Describe a disagreement between the format string's output rank and
+    /// `expected_output_rank`, if there is one.
+    fn output_rank_mismatch(&self) -> Option<String> {
+        let expected = self.expected_output_rank?;
+        let (_, output) = parse_format_string(&self.format_string_colmajor);
+        (output.len() != expected).then(|| {
+            format!(
+                "{}: output '{}' has rank {}, expected_output_rank is {expected}",
+                self.name,
+                self.format_string_colmajor.split_once("->").unwrap().1,
+                output.len()
+            )
+        })
+    }
+
+    /// Describe the first operand with a zero-sized dimension, if any. The
+    /// result of such a contraction is empty (or all zeros) and the backend
+    /// doesn't survive evaluating it, so these instances are never run.
+    fn zero_sized_operand(&self) -> Option<String> {
+        let (t, shape) = self
+            .shapes_colmajor
+            .iter()
+            .enumerate()
+            .find(|(_, shape)| shape.contains(&0))?;
+        Some(format!("[empty] tensor {t} has shape {shape:?}"))
+    }
+
+    /// The axis permutation when the instance is one tensor whose output only
+    /// reorders its labels (`ab->ba`), so [`transpose`] can run it instead of
+    /// the einsum machinery. Scaled instances keep the general path, which
+    /// applies `alpha` and `beta`.
+    fn pure_transpose(&self) -> Option<Vec<usize>> {
+        let (inputs, output) = parse_format_string(&self.format_string_colmajor);
+        let [input] = inputs.as_slice() else {
+            return None;
+        };
+        let distinct = |ids: &[char]| ids.iter().enumerate().all(|(k, id)| !ids[..k].contains(id));
+        if self.scaling().is_some()
+            || output.len() != input.len()
+            || !distinct(input)
+            || !distinct(&output)
+        {
+            return None;
+        }
+        output
+            .iter()
+            .map(|id| input.iter().position(|x| x == id))
+            .collect()
+    }
+
+    /// The zeroed buffer behind `views`, when the instance has one.
+    fn shared_buffer(&self) -> Option<shared::Buffer> {
+        self.views.as_ref()?;
+        Some(shared::Buffer::zeroed(self.buffer_size?, &self.dtype))
+    }
+
+    /// The operands [`run_instance`] times: views into `buffer` when the
+    /// instance has one (whatever `row_major` says), else fresh arrays.
+    fn timed_operands<'a>(
+        &self,
+        buffer: Option<&'a shared::Buffer>,
+        row_major: bool,
+    ) -> Vec<EinsumOperand<'a>> {
+        match (buffer, &self.views) {
+            (Some(buffer), Some(views)) => buffer.operands(views, &self.shapes_colmajor),
+            _ => create_operands_in(&self.shapes_colmajor, &self.dtype, row_major),
+        }
+    }
+
+    /// `(alpha, beta)` when either differs from the default `(1, 0)`.
+    fn scaling(&self) -> Option<(f64, f64)> {
+        let scaling = (self.alpha.unwrap_or(1.0), self.beta.unwrap_or(0.0));
+        (scaling != (1.0, 0.0)).then_some(scaling)
+    }
+
+    fn weight(&self) -> f64 {
+        self.weight.unwrap_or(1.0)
+    }
 }
 
+/// A strategy listed in `skip_strategies` may leave its entry out.
 #[derive(Deserialize)]
 struct PathInfo {
+    #[serde(default = "PathMeta::missing")]
     opt_size: PathMeta,
+    #[serde(default = "PathMeta::missing")]
     opt_flops: PathMeta,
+    /// A physically preferred order (e.g. causal or time-ordered), timed as
+    /// the `physical` strategy; optional, unlike the other two.
+    #[serde(default = "PathMeta::missing")]
+    physical: PathMeta,
 }
 
 #[derive(Deserialize)]
@@ -33,8 +215,122 @@ struct PathMeta {
     log10_flops: f64,
 }
 
+impl PathMeta {
+    /// Placeholder for an absent `paths` entry. JSON has no NaN, so a NaN
+    /// cost can only mean the entry was missing.
+    fn missing() -> Self {
+        PathMeta {
+            path: Vec::new(),
+            log2_size: f64::NAN,
+            log10_flops: f64::NAN,
+        }
+    }
+
+    fn is_missing(&self) -> bool {
+        self.log10_flops.is_nan()
+    }
+}
+
+/// A `--paths-dir` sidecar, `<name>.path.json`: each strategy it lists
+/// replaces the instance's own entry.
+#[derive(Deserialize)]
+struct SidecarPaths {
+    #[serde(default)]
+    opt_size: Option<SidecarPath>,
+    #[serde(default)]
+    opt_flops: Option<SidecarPath>,
+    #[serde(default)]
+    physical: Option<SidecarPath>,
+}
+
+/// A sidecar path; costs left out are recomputed from the path itself.
+#[derive(Deserialize)]
+struct SidecarPath {
+    path: Vec<[usize; 2]>,
+    #[serde(default)]
+    log2_size: Option<f64>,
+    #[serde(default)]
+    log10_flops: Option<f64>,
+}
+
+/// Replace `instance`'s paths with those in `dir`'s sidecar for it, after
+/// replaying each on the instance's labels. `Ok(false)` when there is none.
+fn apply_sidecar_paths(dir: &Path, instance: &mut BenchmarkInstance) -> Result<bool, String> {
+    let file = dir.join(format!("{}.path.json", instance.name));
+    let json_str = match std::fs::read_to_string(&file) {
+        Ok(s) => s,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(format!("{}: {e}", file.display())),
+    };
+    let in_file = |e: String| format!("{}: {e}", file.display());
+    let sidecar: SidecarPaths =
+        serde_json::from_str(&json_str).map_err(|e| in_file(e.to_string()))?;
+    let (input_indices, output_indices) = parse_format_string(&instance.format_string_colmajor);
+    let dims = path::dim_map(&input_indices, &instance.shapes_colmajor)?;
+    for (entry, slot) in [
+        (sidecar.opt_size, &mut instance.paths.opt_size),
+        (sidecar.opt_flops, &mut instance.paths.opt_flops),
+        (sidecar.physical, &mut instance.paths.physical),
+    ] {
+        let Some(entry) = entry else { continue };
+        let steps =
+            path::simulate(&input_indices, &output_indices, &dims, &entry.path).map_err(in_file)?;
+        let (log10_flops, log2_size) = path::cost(&steps);
+        *slot = PathMeta {
+            path: entry.path,
+            log2_size: entry.log2_size.unwrap_or(log2_size),
+            log10_flops: entry.log10_flops.unwrap_or(log10_flops),
+        };
+    }
+    Ok(true)
+}
+
+/// `--output-order`: replace the output labels with `order`, which must be
+/// a permutation of them. Only the final transpose changes; the paths and
+/// their costs stay valid. The row-major `format_string`, when present,
+/// gets the reversed order, and a pinned checksum is dropped.
+fn set_output_order(instance: &mut BenchmarkInstance, order: &str) -> Result<(), String> {
+    let (inputs, natural) = instance
+        .format_string_colmajor
+        .split_once("->")
+        .ok_or("format_string must contain '->'")?;
+    let mut sorted: Vec<char> = order.chars().collect();
+    let mut expected: Vec<char> = natural.chars().collect();
+    sorted.sort_unstable();
+    expected.sort_unstable();
+    if sorted != expected {
+        return Err(format!(
+            "'{order}' is not a permutation of the output labels '{natural}'"
+        ));
+    }
+    if order == natural {
+        return Ok(());
+    }
+    info!(
+        "{}: output '{natural}' reordered to '{order}'",
+        instance.name
+    );
+    instance.format_string_colmajor = format!("{inputs}->{order}");
+    if let Some(rowmajor) = &mut instance.format_string {
+        let (inputs, _) = rowmajor
+            .split_once("->")
+            .ok_or("format_string must contain '->'")?;
+        *rowmajor = format!("{inputs}->{}", order.chars().rev().collect::<String>());
+    }
+    instance.expected_checksum = None;
+    Ok(())
+}
+
 type PathStrategy = (&'static str, fn(&PathInfo) -> &PathMeta);
 
+/// Every strategy, in the order the suite runs them. `physical` is optional
+/// and runs only when some instance has it.
+const STRATEGIES: &[PathStrategy] = &[
+    ("opt_flops", |p| &p.opt_flops),
+    ("opt_size", |p| &p.opt_size),
+    ("physical", |p| &p.physical),
+];
+
 // ---------------------------------------------------------------------------
 // Format string parsing
 // ---------------------------------------------------------------------------
@@ -52,67 +348,171 @@ fn parse_format_string(s: &str) -> (Vec<Vec<char>>, Vec<char>) {
     (input_indices, output_indices)
 }
 
-// ---------------------------------------------------------------------------
-// Contraction path -> EinsumNode tree
-// ---------------------------------------------------------------------------
+/// Tokenized variant of [`parse_format_string`] for `--index-format tokenized`.
+///
+/// Each operand is a bracketed list of labels separated by spaces or commas,
+/// so labels can be multi-character or numeric:
+/// `"[i0 i1],[i1 bond_2]->[i0 bond_2]"`. Every distinct label is mapped to a
+/// private-use `char` (assigned in order of first appearance and recorded in
+/// `labels`), which keeps the rest of the runner on `char` ids while lifting
+/// the practical cap on distinct indices.
+fn parse_format_string_tokenized(
+    s: &str,
+    labels: &mut HashMap<String, char>,
+) -> Result<(Vec<Vec<char>>, Vec<char>), String> {
+    let (inputs_str, output_str) = s
+        .split_once("->")
+        .ok_or("format_string must contain '->'")?;
+    let mut ids = |group: &str| -> Result<Vec<char>, String> {
+        let inner = group
+            .trim()
+            .strip_prefix('[')
+            .and_then(|g| g.strip_suffix(']'))
+            .filter(|g| !g.contains(['[', ']']))
+            .ok_or("expected a bracketed label list like [i j]")?;
+        Ok(inner
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|t| !t.is_empty())
+            .map(|token| intern_label(labels, token))
+            .collect())
+    };
+    let mut input_indices = Vec::new();
+    let mut rest = inputs_str.trim();
+    while !rest.is_empty() {
+        let end = rest
+            .find(']')
+            .ok_or_else(|| format!("operand {}: unclosed '['", input_indices.len()))?;
+        input_indices.push(ids(&rest[..=end])?);
+        rest = rest[end + 1..].trim_start();
+        rest = rest.strip_prefix(',').unwrap_or(rest).trim_start();
+    }
+    let output_indices = ids(output_str)?;
+    Ok((input_indices, output_indices))
+}
 
-/// Convert a flat contraction path (list of index pairs) into a nested EinsumNode tree.
+/// Parse a format string whose labels are a letter with an optional decimal
+/// suffix (`a`, `a1`, `b12`, `--numeric-suffix-labels`), mapping each
+/// distinct label to a `char` as [`parse_format_string_tokenized`] does.
 ///
-/// Path convention (opt_einsum / cotengra):
-/// - Each step [i, j] refers to the current list of tensors
-/// - Remove higher index first, then lower; contract; append result to end
-fn build_contraction_tree(input_indices: &[Vec<char>], path: &[[usize; 2]]) -> EinsumNode {
-    let mut nodes: Vec<EinsumNode> = input_indices
-        .iter()
-        .enumerate()
-        .map(|(i, ids)| EinsumNode::Leaf {
-            ids: ids.clone(),
-            tensor_index: i,
-        })
-        .collect();
+/// Digits always extend the label before them, so the split is unique:
+/// `a12b` is `a12` then `b`. A digit with no letter before it, and a suffix
+/// with a leading zero (`a01`, easily read as `a1`), are rejected.
+fn parse_format_string_numeric_suffix(
+    s: &str,
+    labels: &mut HashMap<String, char>,
+) -> Result<(Vec<Vec<char>>, Vec<char>), String> {
+    let (inputs_str, output_str) = s
+        .split_once("->")
+        .ok_or("format_string must contain '->'")?;
+    let mut ids = |operand: &str| -> Result<Vec<char>, String> {
+        let operand = operand.trim();
+        let mut ids = Vec::new();
+        let mut chars = operand.char_indices().peekable();
+        while let Some((start, c)) = chars.next() {
+            if !c.is_alphabetic() {
+                return Err(format!("{operand:?}: '{c}' does not follow a label letter"));
+            }
+            let mut end = start + c.len_utf8();
+            while let Some(&(i, d)) = chars.peek().filter(|(_, d)| d.is_ascii_digit()) {
+                end = i + d.len_utf8();
+                chars.next();
+            }
+            let label = &operand[start..end];
+            let suffix = &label[c.len_utf8()..];
+            if suffix.len() > 1 && suffix.starts_with('0') {
+                return Err(format!("{operand:?}: label {label:?} has a leading zero"));
+            }
+            ids.push(intern_label(labels, label));
+        }
+        Ok(ids)
+    };
+    let input_indices = inputs_str
+        .split(',')
+        .map(&mut ids)
+        .collect::<Result<Vec<_>, _>>()?;
+    let output_indices = ids(output_str)?;
+    Ok((input_indices, output_indices))
+}
 
-    for &pair in path {
-        let (i, j) = if pair[0] < pair[1] {
-            (pair[0], pair[1])
-        } else {
-            (pair[1], pair[0])
-        };
-        let node_j = nodes.remove(j);
-        let node_i = nodes.remove(i);
-        nodes.push(EinsumNode::Contract {
-            args: vec![node_i, node_j],
-        });
-    }
+/// The `char` standing for `token`, handing out the next free one for a
+/// token not seen before.
+fn intern_label(labels: &mut HashMap<String, char>, token: &str) -> char {
+    let next = labels.len() as u32;
+    *labels
+        .entry(token.to_string())
+        .or_insert_with(|| char::from_u32(TOKEN_LABEL_BASE + next).expect("label space exhausted"))
+}
 
-    assert_eq!(
-        nodes.len(),
-        1,
-        "contraction path should reduce to a single node"
-    );
-    nodes.pop().unwrap()
+/// First `char` handed out by [`intern_label`] (Supplementary Private Use
+/// Area-A, 65 534 code points).
+const TOKEN_LABEL_BASE: u32 = 0xF0000;
+
+/// A format string parser that maps its labels to `char`s.
+type LabelParser =
+    fn(&str, &mut HashMap<String, char>) -> Result<(Vec<Vec<char>>, Vec<char>), String>;
+
+/// Rewrite an instance's format strings, read with `parse`, into the `char`
+/// form the rest of the runner parses.
+fn detokenize(instance: &mut BenchmarkInstance, parse: LabelParser) -> Result<(), String> {
+    let render = |(inputs, output): (Vec<Vec<char>>, Vec<char>)| {
+        let inputs: Vec<String> = inputs.iter().map(|ids| ids.iter().collect()).collect();
+        format!(
+            "{}->{}",
+            inputs.join(","),
+            output.iter().collect::<String>()
+        )
+    };
+    let mut labels = HashMap::new();
+    instance.format_string_colmajor = render(parse(&instance.format_string_colmajor, &mut labels)?);
+    if let Some(rowmajor) = &instance.format_string {
+        // Same label table, so both strings name the same indices.
+        let known = labels.len();
+        let converted = render(parse(rowmajor, &mut labels)?);
+        if labels.len() != known {
+            return Err("format_string uses labels absent from format_string_colmajor".into());
+        }
+        instance.format_string = Some(converted);
+    }
+    Ok(())
 }
 
 // ---------------------------------------------------------------------------
 // Benchmark runner
 // ---------------------------------------------------------------------------
 
+/// One zero-filled column-major buffer per format-string operand.
+///
+/// An operand that appears twice (`ab,ab->`) is two independent buffers, never
+/// an alias: `num_tensors` counts it twice and each copy is read from its own
+/// memory, matching how the Julia runner and the dataset treat it.
 fn create_operands(shapes: &[Vec<usize>], dtype: &str) -> Vec<EinsumOperand<'static>> {
+    create_operands_in(shapes, dtype, false)
+}
+
+/// [`create_operands`] in either memory order: `row_major` stores the same
+/// logical tensors (same dims and labels) with reversed strides (`--layout`).
+fn create_operands_in(
+    shapes: &[Vec<usize>],
+    dtype: &str,
+    row_major: bool,
+) -> Vec<EinsumOperand<'static>> {
+    fn alloc<T: Copy + Default>(shape: &[usize], row_major: bool) -> StridedArray<T> {
+        if row_major {
+            StridedArray::row_major(shape)
+        } else {
+            StridedArray::col_major(shape)
+        }
+    }
     match dtype {
         "float64" => shapes
             .iter()
-            .map(|shape| {
-                let arr = StridedArray::<f64>::col_major(shape);
-                EinsumOperand::from(arr)
-            })
+            .map(|shape| EinsumOperand::from(alloc::<f64>(shape, row_major)))
             .collect(),
         "complex128" => {
             use num_complex::Complex64;
             shapes
                 .iter()
-                .map(|shape| {
-                    let arr = StridedArray::<Complex64>::col_major(shape);
-                    EinsumOperand::from(arr)
-                })
+                .map(|shape| EinsumOperand::from(alloc::<Complex64>(shape, row_major)))
                 .collect()
         }
         other => panic!("unsupported dtype: {other}"),
@@ -123,6 +523,60 @@ struct BenchResult {
     median_ms: f64,
     q1_ms: f64,
     q3_ms: f64,
+    /// Warmup runs actually performed (varies with `--adaptive-warmup`).
+    warmup_runs: usize,
+    /// `--min-mode`: the minimum in milliseconds, the timed runs it took,
+    /// and whether it held for `--min-stable` runs (`false` when the
+    /// `--min-budget` ran out first).
+    min_runs: Option<(f64, usize, bool)>,
+    /// Median planning time and, within it, tree build time in microseconds
+    /// (`--profile-planning`).
+    plan_us: Option<(f64, f64)>,
+    /// Max relative error of f32-rounded inputs vs f64 (`--mixed-precision`).
+    mixed_err: Option<f64>,
+    /// Fraction of step time spent copying inputs for GEMM (`--profile-copy`).
+    copy_tax: Option<f64>,
+    /// Relative error against NumPy, filled in by the suite (`--cross-check`).
+    numpy_err: Option<f64>,
+    /// Median with a preallocated, reused output buffer (`--reuse-output`).
+    reuse_ms: Option<f64>,
+    /// Median on row-major operands (`--layout both`).
+    row_ms: Option<f64>,
+    /// Median on operands backed by huge pages (`--hugepages`); `None` when
+    /// no operand holds a whole huge page.
+    huge_ms: Option<f64>,
+    /// Median with intermediates drawn from a reused `BufferPool` (`--pool`).
+    pool_ms: Option<f64>,
+    /// Median from instance file to result, and the fraction of it spent
+    /// reading and parsing the file (`--include-load`).
+    load_ms: Option<(f64, f64)>,
+    /// `(FLOP/s, GB/s)` from the median (`--throughput`).
+    throughput: Option<(f64, f64)>,
+    /// `(min, median, max)` FLOP/s over the individual timed runs
+    /// (`--gflops-spread`).
+    gflops_spread: Option<(f64, f64, f64)>,
+    /// `--verify-checksum` outcome, when the instance has an expected value.
+    checksum: Option<ChecksumCheck>,
+    /// `--check-determinism`: `Some(None)` when every evaluation matched the
+    /// first bit for bit, `Some(Some((k, d)))` with the first repeat that
+    /// didn't (1-based) and its largest absolute difference.
+    rerun_diff: Option<Option<(usize, f64)>>,
+    /// `--report-sparsity`: share of near-zero elements in the result on
+    /// seeded data (see [`result_sparsity`]).
+    sparsity: Option<f64>,
+    /// Peak heap growth of one evaluation, in bytes (`--track-memory`).
+    peak_bytes: Option<usize>,
+    /// Subtrees `--cse` would share (see [`tree::shared_subtrees`]).
+    cse_shared: Option<usize>,
+    /// Every timed run in execution order, in nanoseconds.
+    samples_ns: Vec<u64>,
+}
+
+/// Seeded-data checksum of one instance against its `expected_checksum`.
+struct ChecksumCheck {
+    actual: f64,
+    expected: f64,
+    ok: bool,
 }
 
 impl BenchResult {
@@ -131,13 +585,58 @@ impl BenchResult {
     }
 }
 
+/// `--min-mode` stopping rule: stable once `needed` consecutive runs have
+/// not set a strictly lower minimum.
+struct MinTracker {
+    best: Duration,
+    since_best: usize,
+    needed: usize,
+}
+
+impl MinTracker {
+    fn new(needed: usize) -> Self {
+        MinTracker {
+            best: Duration::MAX,
+            since_best: 0,
+            needed,
+        }
+    }
+
+    /// Record one run; `true` once the minimum is stable.
+    fn push(&mut self, t: Duration) -> bool {
+        if t < self.best {
+            self.best = t;
+            self.since_best = 0;
+        } else {
+            self.since_best += 1;
+        }
+        self.since_best >= self.needed
+    }
+}
+
+/// IQR, as a fraction of the median, that scores 0.5 in [`reproducibility`].
+const REPRO_HALF_IQR: f64 = 0.05;
+
+/// Scores under this are flagged as timings not to trust.
+const REPRO_FLAG_BELOW: f64 = 0.5;
+
+/// 0-1 reproducibility of a timing from its within-run spread: 1 with no
+/// spread, 0.5 when the IQR is [`REPRO_HALF_IQR`] of the median, towards 0
+/// as it widens.
+fn reproducibility(median_ms: f64, iqr_ms: f64) -> f64 {
+    let relative = if median_ms > 0.0 {
+        iqr_ms / median_ms
+    } else {
+        0.0
+    };
+    1.0 / (1.0 + relative / REPRO_HALF_IQR)
+}
+
 const NUM_WARMUP: usize = 3;
 const NUM_TIMED: usize = 15;
 
-fn run_instance(
-    instance: &BenchmarkInstance,
-    path_meta: &PathMeta,
-) -> Result<BenchResult, EinsumError> {
+/// Parse the format string and build the contraction tree for one strategy.
+fn plan(instance: &BenchmarkInstance, path_meta: &PathMeta) -> EinsumCode {
     let (input_indices, output_indices) = parse_format_string(&instance.format_string_colmajor);
     assert_eq!(
         input_indices.len(),
@@ -146,28 +645,390 @@ fn run_instance(
     );
 
     let root = build_contraction_tree(&input_indices, &path_meta.path);
-    let code = EinsumCode {
+    EinsumCode {
         root,
         output_ids: output_indices,
+    }
+}
+
+/// A pure transpose (see [`BenchmarkInstance::pure_transpose`]): `permute`
+/// the operand's view, then `copy_into` a fresh column-major array. The
+/// einsum path would hand back the permuted view without copying, which
+/// times nothing; this is the copy a later contraction would pay.
+fn transpose(
+    operand: EinsumOperand<'_>,
+    perm: &[usize],
+) -> Result<EinsumOperand<'static>, EinsumError> {
+    fn copy<T: Copy + Default>(
+        data: &strided_opteinsum::StridedData<'_, T>,
+        perm: &[usize],
+    ) -> Result<StridedArray<T>, EinsumError> {
+        let view = data.as_view().permute(perm)?;
+        let mut out = StridedArray::<T>::col_major(view.dims());
+        strided_perm::copy_into(&mut out.view_mut(), &view)?;
+        Ok(out)
+    }
+    Ok(match &operand {
+        EinsumOperand::F64(data) => copy(data, perm)?.into(),
+        EinsumOperand::C64(data) => copy(data, perm)?.into(),
+    })
+}
+
+/// Output buffer allocated once per instance for `--reuse-output`.
+enum OutputBuffer {
+    F64(StridedArray<f64>),
+    C64(StridedArray<num_complex::Complex64>),
+}
+
+impl OutputBuffer {
+    fn new(instance: &BenchmarkInstance) -> Result<Self, String> {
+        let (input_indices, output_indices) = parse_format_string(&instance.format_string_colmajor);
+        let dims = path::dim_map(&input_indices, &instance.shapes_colmajor)?;
+        let shape: Vec<usize> = output_indices
+            .iter()
+            .map(|id| {
+                dims.get(id)
+                    .copied()
+                    .ok_or_else(|| format!("output label '{id}' not in any input"))
+            })
+            .collect::<Result<_, _>>()?;
+        match instance.dtype.as_str() {
+            "float64" => Ok(OutputBuffer::F64(StridedArray::col_major(&shape))),
+            "complex128" => Ok(OutputBuffer::C64(StridedArray::col_major(&shape))),
+            other => Err(format!("unsupported dtype: {other}")),
+        }
+    }
+
+    /// Write `alpha * result + beta * buffer` into the buffer.
+    fn evaluate_into(
+        &mut self,
+        code: &EinsumCode,
+        operands: Vec<EinsumOperand<'_>>,
+        (alpha, beta): (f64, f64),
+    ) -> Result<(), EinsumError> {
+        match self {
+            OutputBuffer::F64(arr) => {
+                code.evaluate_into(operands, arr.view_mut(), alpha, beta, None)
+            }
+            OutputBuffer::C64(arr) => code.evaluate_into(
+                operands,
+                arr.view_mut(),
+                num_complex::Complex64::new(alpha, 0.0),
+                num_complex::Complex64::new(beta, 0.0),
+                None,
+            ),
+        }
+    }
+}
+
+/// One contraction as the timed runs do it, given the instance's
+/// [`BenchmarkInstance::pure_transpose`] and [`BenchmarkInstance::scaling`].
+/// Scaled instances contract into the output buffer so `beta` has an
+/// accumulator to scale, returning `None`; everything else allocates a
+/// fresh result.
+fn evaluate_once<'a>(
+    code: &EinsumCode,
+    transpose_perm: Option<&[usize]>,
+    scaling: Option<(f64, f64)>,
+    operands: Vec<EinsumOperand<'a>>,
+    output: Option<&mut OutputBuffer>,
+) -> Result<Option<EinsumOperand<'a>>, EinsumError> {
+    if let Some(perm) = transpose_perm {
+        let operand = operands.into_iter().next().expect("one operand");
+        return transpose(operand, perm).map(Some);
+    }
+    match (scaling, output) {
+        (Some(scaling), Some(buf)) => {
+            buf.evaluate_into(code, operands, scaling)?;
+            Ok(None)
+        }
+        _ => code.evaluate(operands, None).map(Some),
+    }
+}
+
+/// `--warmup-shared`: the untimed runs [`run_instance`] would do, on their
+/// own, so they can all happen before any instance is timed.
+fn warm_up(
+    instance: &BenchmarkInstance,
+    path_meta: &PathMeta,
+    opts: &Options,
+    mut output: Option<&mut OutputBuffer>,
+) -> Result<(), EinsumError> {
+    let code = plan(instance, path_meta);
+    let transpose_perm = instance.pure_transpose();
+    let buffer = instance.shared_buffer();
+    for _ in 0..opts.warmup {
+        let operands = instance.timed_operands(buffer.as_ref(), opts.layout == Layout::Row);
+        black_box(evaluate_once(
+            &code,
+            transpose_perm.as_deref(),
+            instance.scaling(),
+            operands,
+            output.as_deref_mut(),
+        )?);
+    }
+    Ok(())
+}
+
+fn run_instance(
+    instance: &BenchmarkInstance,
+    path_meta: &PathMeta,
+    opts: &Options,
+    mut output: Option<&mut OutputBuffer>,
+) -> Result<BenchResult, EinsumError> {
+    let code = plan(instance, path_meta);
+    let scaling = instance.scaling();
+    let transpose_perm = instance.pure_transpose();
+    let buffer = instance.shared_buffer();
+    let mut evaluate = |operands| {
+        evaluate_once(
+            &code,
+            transpose_perm.as_deref(),
+            scaling,
+            operands,
+            output.as_deref_mut(),
+        )
     };
 
+    // Planning overhead: the same work as above, repeated and timed on its own.
+    // The tree build alone is timed too: it grows with the tensor count.
+    let plan_us = opts.profile_planning.then(|| {
+        let median_us = |f: &dyn Fn()| {
+            let mut samples: Vec<f64> = (0..NUM_TIMED)
+                .map(|_| {
+                    let t0 = Instant::now();
+                    f();
+                    t0.elapsed().as_secs_f64() * 1e6
+                })
+                .collect();
+            samples.sort_by(|a, b| a.total_cmp(b));
+            samples[samples.len() / 2]
+        };
+        let (input_indices, _) = parse_format_string(&instance.format_string_colmajor);
+        let plan_us = median_us(&|| {
+            black_box(plan(instance, path_meta));
+        });
+        let tree_us = median_us(&|| {
+            black_box(build_contraction_tree(&input_indices, &path_meta.path));
+        });
+        (plan_us, tree_us)
+    });
+
+    let operands = || instance.timed_operands(buffer.as_ref(), opts.layout == Layout::Row);
+
     // Warmup
-    for _ in 0..NUM_WARMUP {
-        let operands = create_operands(&instance.shapes_colmajor, &instance.dtype);
-        code.evaluate(operands, None)?;
-    }
+    let warmup_runs = if opts.adaptive_warmup {
+        // Keep warming until two consecutive runs agree within the tolerance,
+        // so DVFS ramp-up is over before the timed loop starts.
+        let mut prev: Option<f64> = None;
+        let mut runs = 0;
+        while runs < opts.max_warmup {
+            let operands = operands();
+            let t0 = Instant::now();
+            black_box(evaluate(operands)?);
+            let t = t0.elapsed().as_secs_f64();
+            runs += 1;
+            if let Some(p) = prev {
+                if (t - p).abs() <= opts.warmup_tolerance * p.max(t) {
+                    break;
+                }
+            }
+            prev = Some(t);
+        }
+        runs
+    } else {
+        for _ in 0..opts.warmup {
+            let operands = operands();
+            evaluate(operands)?;
+        }
+        opts.warmup
+    };
 
     // Timed runs
-    let mut durations = Vec::with_capacity(NUM_TIMED);
-    for _ in 0..NUM_TIMED {
-        let operands = create_operands(&instance.shapes_colmajor, &instance.dtype);
+    let mut timed_run = || -> Result<Duration, EinsumError> {
+        let operands = operands();
         let t0 = Instant::now();
-        let result = code.evaluate(operands, None)?;
+        let result = evaluate(operands)?;
         let elapsed = t0.elapsed();
         black_box(&result);
-        durations.push(elapsed);
-    }
+        Ok(elapsed)
+    };
+    let mut durations = Vec::with_capacity(opts.runs);
+    let min_runs = if opts.min_mode {
+        // Short runs until the minimum stops moving: the fastest run is the
+        // one least disturbed by the machine, whatever the spread.
+        let budget = Duration::from_secs_f64(opts.min_budget);
+        let start = Instant::now();
+        let mut tracker = MinTracker::new(opts.min_stable);
+        let stable = loop {
+            let elapsed = timed_run()?;
+            durations.push(elapsed);
+            if tracker.push(elapsed) {
+                break true;
+            }
+            if start.elapsed() >= budget {
+                break false;
+            }
+        };
+        Some((tracker.best.as_secs_f64() * 1e3, durations.len(), stable))
+    } else {
+        for _ in 0..opts.runs {
+            durations.push(timed_run()?);
+        }
+        None
+    };
+
+    // `--layout both`: the same warmup and timed runs on row-major operands.
+    let row_ms = if opts.layout == Layout::Both {
+        let row = || create_operands_in(&instance.shapes_colmajor, &instance.dtype, true);
+        for _ in 0..opts.warmup {
+            evaluate(row())?;
+        }
+        let mut samples = Vec::with_capacity(opts.runs);
+        for _ in 0..opts.runs {
+            let operands = row();
+            let t0 = Instant::now();
+            let result = evaluate(operands)?;
+            samples.push(t0.elapsed());
+            black_box(&result);
+        }
+        samples.sort();
+        Some(samples[samples.len() / 2].as_secs_f64() * 1e3)
+    } else {
+        None
+    };
+
+    // `--hugepages`: the same warmup and timed runs on operands whose
+    // buffers are advised as huge pages and faulted in before timing. They
+    // are column-major, so row-major runs have nothing to compare against.
+    let huge_ms = if opts.hugepages && opts.layout != Layout::Row {
+        let huge = || {
+            hugepages::create_operands(&instance.shapes_colmajor, &instance.dtype)
+                .map_err(EinsumError::Internal)
+        };
+        let (first, advised) = huge()?;
+        if advised > 0 {
+            evaluate(first)?;
+            for _ in 1..opts.warmup {
+                evaluate(huge()?.0)?;
+            }
+            let mut samples = Vec::with_capacity(opts.runs);
+            for _ in 0..opts.runs {
+                let (operands, _) = huge()?;
+                let t0 = Instant::now();
+                let result = evaluate(operands)?;
+                samples.push(t0.elapsed());
+                black_box(&result);
+            }
+            samples.sort();
+            Some(samples[samples.len() / 2].as_secs_f64() * 1e3)
+        } else {
+            None
+        }
+    } else {
+        None
+    };
+
+    // `--reuse-output`: the same runs again, writing into the shared output
+    // buffer instead of allocating a fresh result each time. Scaled instances
+    // have the buffer either way; without the flag it serves the main runs only.
+    let reuse_ms = match output.filter(|_| opts.reuse_output) {
+        Some(buf) => {
+            let scaling = scaling.unwrap_or((1.0, 0.0));
+            buf.evaluate_into(&code, operands(), scaling)?;
+            let mut samples = Vec::with_capacity(opts.runs);
+            for _ in 0..opts.runs {
+                let operands = operands();
+                let t0 = Instant::now();
+                buf.evaluate_into(&code, operands, scaling)?;
+                samples.push(t0.elapsed());
+            }
+            samples.sort();
+            Some(samples[samples.len() / 2].as_secs_f64() * 1e3)
+        }
+        None => None,
+    };
+
+    // `--pool`: the same runs again with one `BufferPool` kept across them.
+    // The first (untimed) run fills it with a buffer for every intermediate,
+    // so timed runs allocate only the result. Scaled instances go through
+    // the output buffer and are left out, as are pure transposes: the pooled
+    // evaluator returns their permuted view without copying anything.
+    let pool_ms = if opts.pool && scaling.is_none() && transpose_perm.is_none() {
+        let mut pool = BufferPool::new();
+        for _ in 0..opts.warmup.max(1) {
+            black_box(code.evaluate_with_pool(operands(), None, Some(&mut pool))?);
+        }
+        let mut samples = Vec::with_capacity(opts.runs);
+        for _ in 0..opts.runs {
+            let operands = operands();
+            let t0 = Instant::now();
+            let result = code.evaluate_with_pool(operands, None, Some(&mut pool))?;
+            samples.push(t0.elapsed());
+            black_box(&result);
+        }
+        samples.sort();
+        Some(samples[samples.len() / 2].as_secs_f64() * 1e3)
+    } else {
+        None
+    };
+
+    // `--include-load`: the same runs again, each reading and parsing the
+    // instance file and planning the tree before contracting. The parsed
+    // instance only stands in for the real one: the run still contracts the
+    // one already prepared (detokenized, sidecar paths applied). Scaled
+    // instances are left out, as with `--pool`.
+    let load_ms = match (&instance.source, opts.include_load && scaling.is_none()) {
+        (Some(file), true) => {
+            let parse_err = |e: String| EinsumError::ParseError(format!("{}: {e}", file.display()));
+            let end_to_end = || -> Result<(Duration, Duration), EinsumError> {
+                let operands = operands();
+                let t0 = Instant::now();
+                let json_str = read_instance_file(file).map_err(|e| parse_err(e.to_string()))?;
+                let parsed = serde_json::from_str::<BenchmarkInstance>(&json_str)
+                    .map_err(|e| parse_err(e.to_string()))?;
+                black_box(parsed);
+                let load = t0.elapsed();
+                let code = plan(instance, path_meta);
+                let result = match &transpose_perm {
+                    Some(perm) => {
+                        let operand = operands.into_iter().next().expect("one operand");
+                        transpose(operand, perm)?
+                    }
+                    None => code.evaluate(operands, None)?,
+                };
+                let total = t0.elapsed();
+                black_box(result);
+                Ok((total, load))
+            };
+            for _ in 0..opts.warmup {
+                end_to_end()?;
+            }
+            let mut samples = (0..opts.runs)
+                .map(|_| end_to_end())
+                .collect::<Result<Vec<_>, _>>()?;
+            samples.sort();
+            let (total, load) = samples[samples.len() / 2];
+            Some((
+                total.as_secs_f64() * 1e3,
+                load.as_secs_f64() / total.as_secs_f64(),
+            ))
+        }
+        _ => None,
+    };
+
+    // One more run outside the timed loop, so allocator bookkeeping can't
+    // skew the timings.
+    let peak_bytes = if opts.track_memory {
+        let operands = create_operands(&instance.shapes_colmajor, &instance.dtype);
+        let (result, peak) = memory::measure(|| code.evaluate(operands, None));
+        black_box(result?);
+        Some(peak)
+    } else {
+        None
+    };
 
+    let samples_ns = durations.iter().map(|d| d.as_nanos() as u64).collect();
     durations.sort();
     let median = durations[durations.len() / 2];
     let q1 = durations[durations.len() / 4];
@@ -176,124 +1037,3833 @@ fn run_instance(
         median_ms: median.as_secs_f64() * 1e3,
         q1_ms: q1.as_secs_f64() * 1e3,
         q3_ms: q3.as_secs_f64() * 1e3,
+        warmup_runs,
+        min_runs,
+        plan_us,
+        mixed_err: if opts.mixed_precision {
+            Some(mixed_precision_error(instance, &code)?)
+        } else {
+            None
+        },
+        checksum: match instance.expected_checksum {
+            Some(expected) if opts.verify_checksum => Some(verify_checksum(
+                instance,
+                path_meta,
+                &code,
+                expected,
+                opts.checksum_mode,
+            )?),
+            _ => None,
+        },
+        rerun_diff: if opts.check_determinism {
+            Some(check_determinism(
+                instance,
+                &code,
+                opts.determinism_repeats.unwrap_or(2),
+            )?)
+        } else {
+            None
+        },
+        sparsity: if opts.report_sparsity {
+            Some(result_sparsity(instance, path_meta, &code)?)
+        } else {
+            None
+        },
+        copy_tax: if opts.profile_copy {
+            Some(trace::copy_tax(instance, path_meta)?)
+        } else {
+            None
+        },
+        numpy_err: None,
+        reuse_ms,
+        row_ms,
+        huge_ms,
+        pool_ms,
+        load_ms,
+        throughput: opts.throughput.then(|| {
+            let secs = median.as_secs_f64();
+            let flops = 10f64.powf(path_meta.log10_flops) / secs;
+            let gbps = min_traffic_bytes(instance).unwrap_or(f64::NAN) / secs / 1e9;
+            (flops, gbps)
+        }),
+        gflops_spread: opts.gflops_spread.then(|| {
+            let flops = |d: &Duration| 10f64.powf(path_meta.log10_flops) / d.as_secs_f64();
+            (
+                flops(&durations[durations.len() - 1]),
+                flops(&median),
+                flops(&durations[0]),
+            )
+        }),
+        peak_bytes,
+        cse_shared: opts.cse.then(|| tree::shared_subtrees(&code.root)),
+        samples_ns,
     })
 }
 
-// ---------------------------------------------------------------------------
-// Backend name (compile-time)
-// ---------------------------------------------------------------------------
-
-#[cfg(all(feature = "faer", not(feature = "blas")))]
-const BACKEND_NAME: &str = "strided-opteinsum(faer)";
-#[cfg(all(feature = "blas", not(feature = "faer")))]
-const BACKEND_NAME: &str = "strided-opteinsum(blas)";
-
-// ---------------------------------------------------------------------------
-// Main
-// ---------------------------------------------------------------------------
-
-fn load_instances() -> Vec<BenchmarkInstance> {
-    let data_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("data/instances");
-    let mut paths: Vec<_> = std::fs::read_dir(&data_dir)
-        .unwrap_or_else(|e| panic!("failed to read {}: {e}", data_dir.display()))
-        .filter_map(|entry| {
-            let path = entry.ok()?.path();
-            if path.extension().and_then(|e| e.to_str()) == Some("json") {
-                Some(path)
-            } else {
-                None
-            }
-        })
-        .collect();
-    paths.sort();
+/// Max relative error of evaluating `code` on inputs rounded to `f32`
+/// against the same inputs at full `f64` precision.
+///
+/// strided-opteinsum only has `f64`/`Complex64` operands, so the rounded
+/// inputs are still stored (and accumulated) as `f64`: this measures the
+/// accuracy side of f32 storage, not its bandwidth savings.
+fn mixed_precision_error(
+    instance: &BenchmarkInstance,
+    code: &EinsumCode,
+) -> Result<f64, EinsumError> {
+    let eval = |round: fn(f64) -> f64| -> Result<_, EinsumError> {
+        let operands = fill::random_operands(
+            &instance.shapes_colmajor,
+            &instance.dtype,
+            instance.hermitian.as_deref().unwrap_or(&[]),
+            fill::DEFAULT_SEED,
+            round,
+        );
+        Ok(compare::col_major_values(&code.evaluate(operands, None)?))
+    };
+    let reference = eval(|x| x)?;
+    let mixed = eval(fill::round_f32)?;
+    Ok(compare::max_rel_error(&reference, &mixed))
+}
 
-    paths
-        .iter()
-        .filter_map(|path| {
-            let json_str = match std::fs::read_to_string(path) {
-                Ok(s) => s,
-                Err(e) => {
-                    eprintln!("Warning: skip {} (read failed: {e})", path.display());
-                    return None;
-                }
-            };
-            match serde_json::from_str(&json_str) {
-                Ok(instance) => Some(instance),
-                Err(e) => {
-                    eprintln!("Warning: skip {} (parse failed: {e})", path.display());
-                    None
-                }
-            }
-        })
-        .collect()
+/// Evaluate `code` on seeded data (zeros would be all sparse) and return the
+/// share of result elements within [`compare::tolerance`] of zero, relative
+/// to the largest one: exact zeros plus cancellations down to rounding.
+fn result_sparsity(
+    instance: &BenchmarkInstance,
+    path_meta: &PathMeta,
+    code: &EinsumCode,
+) -> Result<f64, EinsumError> {
+    let operands = fill::random_operands(
+        &instance.shapes_colmajor,
+        &instance.dtype,
+        instance.hermitian.as_deref().unwrap_or(&[]),
+        fill::DEFAULT_SEED,
+        |x| x,
+    );
+    let values = compare::col_major_values(&code.evaluate(operands, None)?);
+    Ok(compare::near_zero_fraction(
+        &values,
+        compare::tolerance(path_meta.log2_size),
+    ))
 }
 
-fn main() {
-    let data_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("data/instances");
-    let mut instances = load_instances();
-    if let Ok(filter) = std::env::var("BENCH_INSTANCE") {
-        instances.retain(|i| i.name == filter);
-        if instances.is_empty() {
-            eprintln!("BENCH_INSTANCE={filter:?}: no matching instance found");
-            std::process::exit(1);
+/// Evaluate `code` `repeats` times on the same seeded data (zeros would
+/// always agree) and return the first repeat whose result differs from the
+/// first one's, with their [`compare::bitwise_diff`]. Later repeats are not
+/// evaluated.
+fn check_determinism(
+    instance: &BenchmarkInstance,
+    code: &EinsumCode,
+    repeats: usize,
+) -> Result<Option<(usize, f64)>, EinsumError> {
+    let eval = || -> Result<_, EinsumError> {
+        let operands = fill::random_operands(
+            &instance.shapes_colmajor,
+            &instance.dtype,
+            instance.hermitian.as_deref().unwrap_or(&[]),
+            fill::DEFAULT_SEED,
+            |x| x,
+        );
+        Ok(compare::col_major_values(&code.evaluate(operands, None)?))
+    };
+    let first = eval()?;
+    for k in 2..=repeats {
+        if let Some(diff) = compare::bitwise_diff(&first, &eval()?) {
+            return Ok(Some((k, diff)));
         }
     }
+    Ok(None)
+}
 
-    let rayon_threads = std::env::var("RAYON_NUM_THREADS").unwrap_or_else(|_| "unset".into());
-    let omp_threads = std::env::var("OMP_NUM_THREADS").unwrap_or_else(|_| "unset".into());
-
-    println!("{BACKEND_NAME} benchmark suite");
-    println!("==================================");
-    println!(
-        "Loaded {} instances from {}",
-        instances.len(),
-        data_dir.display()
+/// Evaluate `code` on seeded data and compare the result's checksum with
+/// `expected`, within [`compare::tolerance`] of the checksum's scale.
+fn verify_checksum(
+    instance: &BenchmarkInstance,
+    path_meta: &PathMeta,
+    code: &EinsumCode,
+    expected: f64,
+    mode: ChecksumMode,
+) -> Result<ChecksumCheck, EinsumError> {
+    let operands = fill::random_operands(
+        &instance.shapes_colmajor,
+        &instance.dtype,
+        instance.hermitian.as_deref().unwrap_or(&[]),
+        fill::DEFAULT_SEED,
+        |x| x,
     );
-    println!("Backend: {BACKEND_NAME}");
-    println!("RAYON_NUM_THREADS={rayon_threads}, OMP_NUM_THREADS={omp_threads}");
-    println!("Timing: median of {NUM_TIMED} runs ({NUM_WARMUP} warmup)");
+    let (actual, scale) = compare::checksum(
+        &compare::col_major_values(&code.evaluate(operands, None)?),
+        mode,
+    );
+    let tol = compare::tolerance(path_meta.log2_size) * scale.max(expected.abs());
+    Ok(ChecksumCheck {
+        actual,
+        expected,
+        ok: (actual - expected).abs() <= tol,
+    })
+}
 
-    let strategies: &[PathStrategy] = &[
-        ("opt_flops", |p| &p.opt_flops),
-        ("opt_size", |p| &p.opt_size),
-    ];
+/// Path FLOPs (`10^log10_flops`) per element of the resolved output: high
+/// for deep reductions, around 1 for light transforms.
+fn flops_per_output_element(instance: &BenchmarkInstance, path_meta: &PathMeta) -> Option<f64> {
+    let (input_indices, output_indices) = parse_format_string(&instance.format_string_colmajor);
+    let dims = path::dim_map(&input_indices, &instance.shapes_colmajor).ok()?;
+    Some(10f64.powf(path_meta.log10_flops) / path::output_elements(&output_indices, &dims))
+}
 
-    for &(strategy_name, get_path) in strategies {
-        println!();
-        println!("Strategy: {strategy_name}");
-        println!(
-            "{:<50} {:>8} {:>10} {:>12} {:>12} {:>10}",
-            "Instance", "Tensors", "log10FLOPS", "log2SIZE", "Median (ms)", "IQR (ms)"
-        );
-        println!("{}", "-".repeat(108));
+/// Bytes one evaluation is estimated to hold at its peak: every operand
+/// plus the strategy's largest intermediate (`2^log2_size` elements).
+fn estimated_peak_bytes(instance: &BenchmarkInstance, path_meta: &PathMeta) -> f64 {
+    let elem_bytes = match instance.dtype.as_str() {
+        "complex128" => 16.0,
+        _ => 8.0,
+    };
+    let operands: f64 = instance
+        .shapes_colmajor
+        .iter()
+        .map(|s| s.iter().map(|&d| d as f64).product::<f64>())
+        .sum();
+    (operands + path_meta.log2_size.exp2()) * elem_bytes
+}
 
-        for instance in &instances {
-            let path_meta = get_path(&instance.paths);
-            match run_instance(instance, path_meta) {
-                Ok(result) => {
-                    println!(
-                        "{:<50} {:>8} {:>10.2} {:>12.2} {:>12.3} {:>10.3}",
-                        instance.name,
-                        instance.num_tensors,
-                        path_meta.log10_flops,
-                        path_meta.log2_size,
-                        result.median_ms,
-                        result.iqr_ms(),
-                    );
+/// Minimum bytes an evaluation must read and write (inputs + output).
+fn min_traffic_bytes(instance: &BenchmarkInstance) -> Option<f64> {
+    let (input_indices, output_indices) = parse_format_string(&instance.format_string_colmajor);
+    let dims = path::dim_map(&input_indices, &instance.shapes_colmajor).ok()?;
+    let elem_bytes = match instance.dtype.as_str() {
+        "complex128" => 16.0,
+        _ => 8.0,
+    };
+    Some(path::min_traffic_elements(&instance.shapes_colmajor, &output_indices, &dims) * elem_bytes)
+}
+
+/// Cross-check one instance against NumPy, warning on stderr about
+/// failures and mismatches. Returns the column-major relative error.
+fn report_cross_check(
+    python: &str,
+    instance: &BenchmarkInstance,
+    path_meta: &PathMeta,
+) -> Option<f64> {
+    match crosscheck::cross_check(python, instance, path_meta) {
+        Ok(check) => {
+            let tol = compare::tolerance(path_meta.log2_size);
+            if check.colmajor > tol {
+                warn!(
+                    instance = %instance.name,
+                    "numpy mismatch: rel err {:.2e} > tolerance {tol:.1e}",
+                    check.colmajor
+                );
+            }
+            if let Some(err) = check.rowmajor.filter(|&e| e > tol) {
+                warn!(
+                    instance = %instance.name,
+                    "row-major format_string disagrees with colmajor: rel err {err:.2e}"
+                );
+            }
+            Some(check.colmajor)
+        }
+        Err(e) => {
+            warn!(instance = %instance.name, "cross-check failed: {e}");
+            None
+        }
+    }
+}
+
+/// Most rows `--profile-tree` prints per instance; deeper trees are shown
+/// in bands of consecutive levels.
+const MAX_TREE_LEVEL_ROWS: usize = 12;
+
+/// `--profile-tree`: traced per-step medians summed by tree level (0 is the
+/// root contraction). No line ends in two numbers, so log parsers skip them.
+fn report_tree_levels(
+    opts: &Options,
+    instance: &BenchmarkInstance,
+    path_meta: &PathMeta,
+) -> Vec<String> {
+    let samples = match trace::StepSamples::collect(instance, path_meta, opts.warmup, opts.runs) {
+        Ok(samples) => samples,
+        Err(e) => {
+            warn!(instance = %instance.name, "trace failed: {e}");
+            return Vec::new();
+        }
+    };
+    let levels = tree::step_levels(instance.num_tensors, &path_meta.path);
+    let Some(&deepest) = levels.iter().max() else {
+        return Vec::new();
+    };
+    let mut by_level = vec![(0usize, 0f64); deepest + 1];
+    for (step, &level) in levels.iter().enumerate() {
+        by_level[level].0 += 1;
+        by_level[level].1 += samples.median(step);
+    }
+    let total: f64 = by_level.iter().map(|&(_, ms)| ms).sum();
+    if total <= 0.0 {
+        return Vec::new();
+    }
+    // Where the time sits, from 0 (all at the root) to 1 (all at the
+    // deepest level).
+    let centre = if deepest == 0 {
+        0.0
+    } else {
+        by_level
+            .iter()
+            .enumerate()
+            .map(|(level, &(_, ms))| level as f64 * ms)
+            .sum::<f64>()
+            / (total * deepest as f64)
+    };
+    let shape = if centre < 1.0 / 3.0 {
+        "root-heavy"
+    } else if centre > 2.0 / 3.0 {
+        "leaf-heavy"
+    } else {
+        "spread"
+    };
+    let mut lines = vec![format!(
+        "  tree levels: {} steps over {} levels, time centred at {:.0}% of the depth ({shape})",
+        levels.len(),
+        deepest + 1,
+        centre * 100.0
+    )];
+    let band = (deepest + 1).div_ceil(MAX_TREE_LEVEL_ROWS);
+    for (b, chunk) in by_level.chunks(band).enumerate() {
+        let (steps, ms) = chunk
+            .iter()
+            .fold((0, 0.0), |(n, t), &(steps, ms)| (n + steps, t + ms));
+        let (first, last) = (b * band, b * band + chunk.len() - 1);
+        let label = if first == last {
+            format!("level {first}")
+        } else {
+            format!("levels {first}-{last}")
+        };
+        lines.push(format!(
+            "    {label:<14} {steps:>6} steps {ms:>12.3} ms {:>7}",
+            format!("{:.1}%", 100.0 * ms / total)
+        ));
+    }
+    lines
+}
+
+/// Describe the step responsible for most of an instance's timing variance.
+fn report_step_variance(
+    opts: &Options,
+    instance: &BenchmarkInstance,
+    strategy: &str,
+    path_meta: &PathMeta,
+) -> Option<String> {
+    let (warmup, runs) = opts.counts(strategy);
+    let samples = match trace::StepSamples::collect(instance, path_meta, warmup, runs) {
+        Ok(samples) => samples,
+        Err(e) => {
+            warn!(instance = %instance.name, "trace failed: {e}");
+            return None;
+        }
+    };
+    let (step, cv, share) = samples.noisiest()?;
+    Some(format!(
+        "  noisiest step {}/{}: CV {:.1}%, median {:.3} ms, {:.0}% of step variance",
+        step,
+        path_meta.path.len(),
+        cv * 100.0,
+        samples.median(step),
+        share * 100.0,
+    ))
+}
+
+// ---------------------------------------------------------------------------
+// Backend name (compile-time)
+// ---------------------------------------------------------------------------
+
+#[cfg(all(feature = "faer", not(feature = "blas")))]
+const BACKEND_NAME: &str = "strided-opteinsum(faer)";
+#[cfg(all(feature = "blas", not(feature = "faer")))]
+const BACKEND_NAME: &str = "strided-opteinsum(blas)";
+
+// ---------------------------------------------------------------------------
+// Main
+// ---------------------------------------------------------------------------
+
+/// Whether `path` is an instance file: `.json`, or `.json.gz` for
+/// gzip-compressed instances.
+fn is_instance_file(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    name.ends_with(".json") || name.ends_with(".json.gz")
+}
+
+/// Read an instance file, decompressing `.gz` files on the fly.
+fn read_instance_file(path: &Path) -> std::io::Result<String> {
+    if path.extension().and_then(|e| e.to_str()) == Some("gz") {
+        let mut json = String::new();
+        flate2::read::GzDecoder::new(std::fs::File::open(path)?).read_to_string(&mut json)?;
+        Ok(json)
+    } else {
+        std::fs::read_to_string(path)
+    }
+}
+
+/// Instance files in `data_dir`, sorted by name.
+fn instance_paths(data_dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<_> = std::fs::read_dir(data_dir)
+        .unwrap_or_else(|e| panic!("failed to read {}: {e}", data_dir.display()))
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            is_instance_file(&path).then_some(path)
+        })
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Read and validate one instance file; the reason (after a warning) if it
+/// can't be run.
+fn load_instance(path: &Path) -> Result<BenchmarkInstance, String> {
+    let loaded = read_instance_file(path)
+        .map_err(|e| format!("read failed: {e}"))
+        .and_then(|json| {
+            serde_json::from_str::<BenchmarkInstance>(&json)
+                .map_err(|e| format!("parse failed: {e}"))
+        })
+        .and_then(|mut instance| {
+            instance.source = Some(path.to_path_buf());
+            match load_problems(&instance).into_iter().next() {
+                Some(first) => Err(first),
+                None => Ok(instance),
+            }
+        });
+    if let Err(reason) = &loaded {
+        warn!("skip {} ({reason})", path.display());
+    }
+    loaded
+}
+
+/// An instance file's name without `.json` or `.json.gz`, which is the
+/// instance's name in a consistent dataset.
+fn file_stem(path: &Path) -> &str {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .map(|n| n.trim_end_matches(".gz").trim_end_matches(".json"))
+        .unwrap_or_default()
+}
+
+/// Everything that keeps a parsed instance from being run, in the order
+/// [`load_instance`] reports the first of them.
+fn load_problems(instance: &BenchmarkInstance) -> Vec<String> {
+    let mut problems = Vec::new();
+    if !instance.format_string_colmajor.contains("->") {
+        problems.push("format_string_colmajor must contain '->'".into());
+    }
+    problems.extend(instance.zero_sized_operand());
+    if let Some(bad) = [instance.alpha, instance.beta]
+        .into_iter()
+        .flatten()
+        .find(|x| !x.is_finite())
+    {
+        problems.push(format!("alpha/beta must be finite, got {bad}"));
+    }
+    if let Some(bad) = instance.weight.filter(|w| !(w.is_finite() && *w > 0.0)) {
+        problems.push(format!("weight must be positive and finite, got {bad}"));
+    }
+    if let Some(bad) = instance
+        .skip_strategies
+        .iter()
+        .find(|s| !STRATEGIES.iter().any(|(name, _)| name == s))
+    {
+        problems.push(format!("unknown strategy in skip_strategies: {bad:?}"));
+    }
+    if let Some((name, _)) = STRATEGIES
+        .iter()
+        .find(|(name, get_path)| get_path(&instance.paths).is_missing() && !instance.skips(name))
+    {
+        problems.push(format!("paths.{name} missing"));
+    }
+    let views = match (instance.buffer_size, &instance.views) {
+        (Some(size), Some(views)) => shared::validate(size, views, &instance.shapes_colmajor),
+        (None, None) => Ok(()),
+        _ => Err("buffer_size and views must be given together".into()),
+    };
+    if let Err(e) = views {
+        problems.push(format!("invalid shared buffer: {e}"));
+    }
+    if let Some(outputs) = instance
+        .outputs_colmajor
+        .as_ref()
+        .filter(|_| instance.format_string_colmajor.contains("->"))
+    {
+        let (inputs, _) = parse_format_string(&instance.format_string_colmajor);
+        if let Err(e) = multi::validate_outputs(&inputs, outputs) {
+            problems.push(format!("invalid outputs_colmajor: {e}"));
+        }
+    }
+    if let Some(hermitian) = &instance.hermitian {
+        if let Err(e) = fill::validate_hermitian(hermitian, &instance.shapes_colmajor) {
+            problems.push(format!("invalid hermitian hint: {e}"));
+        }
+    }
+    problems
+}
+
+/// Instance names from an `--include-file`/`--exclude-file`: one per line,
+/// blank lines and `#` comments ignored.
+fn read_name_list(path: &Path) -> Result<Vec<String>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    Ok(text
+        .lines()
+        .map(|line| line.split('#').next().unwrap().trim())
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// Which instances the command line selects (`BENCH_INSTANCE`,
+/// `--include-file`, `--exclude-file`) and how they are adjusted before
+/// running (`--index-format tokenized`, `--force-dtype`).
+struct Selection<'a> {
+    opts: &'a Options,
+    /// `BENCH_INSTANCE`: the only instance name to run.
+    name: Option<String>,
+    /// Each name list with its file; `true` keeps the names, `false` drops them.
+    lists: Vec<(&'a Path, Vec<String>, bool)>,
+}
+
+impl<'a> Selection<'a> {
+    /// Read the name lists, exiting with status 2 if one can't be read.
+    fn new(opts: &'a Options) -> Self {
+        let lists = [(&opts.include_file, true), (&opts.exclude_file, false)]
+            .into_iter()
+            .filter_map(|(list, include)| {
+                let list = list.as_deref()?;
+                let names = read_name_list(list).unwrap_or_else(|e| {
+                    error!("{e}");
+                    std::process::exit(2);
+                });
+                Some((list, names, include))
+            })
+            .collect();
+        Selection {
+            opts,
+            name: std::env::var("BENCH_INSTANCE").ok(),
+            lists,
+        }
+    }
+
+    fn matches_name(&self, instance: &BenchmarkInstance) -> bool {
+        self.name.as_ref().is_none_or(|name| *name == instance.name)
+    }
+
+    /// Whether an instance named `name` could be selected, for a file that
+    /// didn't load and so has only its file name.
+    fn could_select(&self, name: &str) -> bool {
+        self.name.as_ref().is_none_or(|n| n == name)
+            && self
+                .lists
+                .iter()
+                .all(|(_, names, include)| names.iter().any(|n| n == name) == *include)
+    }
+
+    /// Warn about listed names that no loaded instance has.
+    fn warn_unknown(&self, loaded: &[String]) {
+        for (list, names, _) in &self.lists {
+            for name in names.iter().filter(|n| !loaded.contains(n)) {
+                warn!("{}: no instance named {name:?}", list.display());
+            }
+        }
+    }
+
+    /// The instance as it runs, or `None` if a name list leaves it out or
+    /// (after a warning) its tokenized labels can't be converted or its
+    /// `--paths-dir` sidecar can't be used.
+    fn apply(&self, mut instance: BenchmarkInstance) -> Option<BenchmarkInstance> {
+        if self
+            .lists
+            .iter()
+            .any(|(_, names, include)| names.contains(&instance.name) != *include)
+        {
+            return None;
+        }
+        let parse: Option<LabelParser> = match self.opts.index_format {
+            IndexFormat::Char => None,
+            IndexFormat::Tokenized => Some(parse_format_string_tokenized),
+            IndexFormat::NumericSuffix => Some(parse_format_string_numeric_suffix),
+        };
+        if let Some(parse) = parse {
+            if let Err(e) = detokenize(&mut instance, parse) {
+                warn!(
+                    "skip {} ({} format: {e})",
+                    instance.name,
+                    self.opts.index_format.name()
+                );
+                return None;
+            }
+        }
+        if let Some(dtype) = &self.opts.force_dtype {
+            if instance.dtype != *dtype {
+                // A checksum pinned on the declared dtype's data doesn't apply.
+                instance.expected_checksum = None;
+                instance.dtype = dtype.clone();
+            }
+        }
+        if let Some(dir) = &self.opts.paths_dir {
+            if let Err(e) = apply_sidecar_paths(dir, &mut instance) {
+                warn!("skip {} (paths sidecar: {e})", instance.name);
+                return None;
+            }
+        }
+        if let Some(order) = &self.opts.output_order {
+            if let Err(e) = set_output_order(&mut instance, order) {
+                error!("--output-order: {}: {e}", instance.name);
+                std::process::exit(2);
+            }
+        }
+        Some(instance)
+    }
+}
+
+/// Problems with the selected instances that stop the run before anything
+/// is timed, gathered one instance at a time and reported together.
+#[derive(Default)]
+struct Blockers {
+    /// Instances stored as another dtype than `--accumulate-dtype`.
+    other_dtype: Vec<String>,
+    /// [`BenchmarkInstance::output_rank_mismatch`] descriptions.
+    rank_mismatches: Vec<String>,
+}
+
+impl Blockers {
+    fn add(&mut self, opts: &Options, instance: &BenchmarkInstance) {
+        if opts
+            .accumulate_dtype
+            .as_ref()
+            .is_some_and(|acc| instance.dtype != *acc)
+        {
+            self.other_dtype.push(instance.name.clone());
+        }
+        self.rank_mismatches.extend(instance.output_rank_mismatch());
+    }
+
+    /// Report every problem and exit with status 2, if there are any.
+    fn exit_if_any(self, opts: &Options) {
+        if let (Some(acc), false) = (&opts.accumulate_dtype, self.other_dtype.is_empty()) {
+            // strided-opteinsum accumulates in the operand dtype; there is
+            // no separate accumulator to select.
+            error!(
+                "--accumulate-dtype {acc}: strided-opteinsum accumulates in the operand \
+                 dtype, so {} instance(s) stored as another dtype can't use it ({}); \
+                 see --force-dtype, or --mixed-precision for f32 inputs with f64 accumulation",
+                self.other_dtype.len(),
+                self.other_dtype.join(", ")
+            );
+            std::process::exit(2);
+        }
+        if !self.rank_mismatches.is_empty() {
+            self.rank_mismatches.iter().for_each(|m| error!("{m}"));
+            std::process::exit(2);
+        }
+    }
+}
+
+/// The instances `run_suite` times.
+enum Instances<'a> {
+    /// Parsed up front and held for the whole run.
+    Loaded(&'a [BenchmarkInstance]),
+    /// `--stream`: the files that passed selection, read again (and
+    /// dropped) one at a time for every strategy, and whether any of them
+    /// has a `physical` path.
+    Streamed(&'a [PathBuf], &'a Selection<'a>, bool),
+}
+
+impl Instances<'_> {
+    fn len(&self) -> usize {
+        match self {
+            Instances::Loaded(all) => all.len(),
+            Instances::Streamed(paths, ..) => paths.len(),
+        }
+    }
+
+    /// The strategies worth a table: all but `physical` when no instance
+    /// has that path.
+    fn strategies(&self) -> Vec<PathStrategy> {
+        let physical = match self {
+            Instances::Loaded(all) => all.iter().any(|i| !i.paths.physical.is_missing()),
+            Instances::Streamed(.., physical) => *physical,
+        };
+        STRATEGIES
+            .iter()
+            .filter(|(name, _)| *name != "physical" || physical)
+            .copied()
+            .collect()
+    }
+
+    /// Every instance at once, for the passes that need them together;
+    /// `--stream` is rejected with those flags.
+    fn all(&self) -> &[BenchmarkInstance] {
+        match self {
+            Instances::Loaded(all) => all,
+            Instances::Streamed(..) => {
+                unreachable!("--stream is rejected with whole-dataset passes")
+            }
+        }
+    }
+}
+
+/// Diagnostics go to stderr through `tracing`, filtered by `RUST_LOG`
+/// (default `info`); stdout carries only the header and result tables.
+fn init_logging() {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_env_filter(filter)
+        .with_ansi(std::io::stderr().is_terminal())
+        .with_target(false)
+        .without_time()
+        .init();
+}
+
+fn main() {
+    init_logging();
+    match Options::from_env() {
+        Command::Run(opts) => {
+            let data_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("data/instances");
+            let selection = Selection::new(&opts);
+            if opts.output_order.is_some() && selection.name.is_none() {
+                error!(
+                    "--output-order applies to a single instance; select it with BENCH_INSTANCE"
+                );
+                std::process::exit(2);
+            }
+            let mut blockers = Blockers::default();
+            let mut loaded: Vec<String> = Vec::new();
+            let mut matched = 0;
+            let mut instances = Vec::new();
+            let mut streamed = Vec::new();
+            let mut physical = false;
+            let mut load_skipped = Vec::new();
+            for path in instance_paths(&data_dir) {
+                let instance = match load_instance(&path) {
+                    Ok(instance) => instance,
+                    Err(reason) => {
+                        if selection.could_select(file_stem(&path)) {
+                            load_skipped.push(report::Skipped {
+                                instance: file_stem(&path).to_string(),
+                                strategy: None,
+                                reason,
+                            });
+                        }
+                        continue;
+                    }
+                };
+                loaded.push(instance.name.clone());
+                if !selection.matches_name(&instance) {
+                    continue;
                 }
-                Err(e) => {
-                    println!(
-                        "{:<50} {:>8} {:>10.2} {:>12.2} {:>12} {:>10}",
-                        instance.name,
-                        instance.num_tensors,
-                        path_meta.log10_flops,
-                        path_meta.log2_size,
-                        "SKIP",
-                        "-",
-                    );
-                    eprintln!("  -> {} (backend error: {e})", instance.name);
+                matched += 1;
+                let Some(instance) = selection.apply(instance) else {
+                    continue;
+                };
+                blockers.add(&opts, &instance);
+                if opts.stream {
+                    // Only the path is kept; the instance is read again to run.
+                    physical |= !instance.paths.physical.is_missing();
+                    streamed.push(path);
+                } else {
+                    instances.push(instance);
+                }
+            }
+            if let Some(filter) = selection.name.as_ref().filter(|_| matched == 0) {
+                error!("BENCH_INSTANCE={filter:?}: no matching instance found");
+                std::process::exit(1);
+            }
+            selection.warn_unknown(&loaded);
+            blockers.exit_if_any(&opts);
+            if opts.step_stats {
+                report_step_stats(&instances);
+                return;
+            }
+            if let Some(out) = &opts.dump_intermediates {
+                match dump_intermediates(&instances, out) {
+                    Ok(n) => info!("wrote intermediates of {n} paths to {}", out.display()),
+                    Err(e) => {
+                        error!("--dump-intermediates: {e}");
+                        std::process::exit(2);
+                    }
+                }
+                return;
+            }
+            if let Some(dir) = &opts.dump_operands {
+                match dump_operands(&instances, dir) {
+                    Ok(n) => info!(
+                        "wrote {n} operands, the format string and the result to {}",
+                        dir.display()
+                    ),
+                    Err(e) => {
+                        error!("--dump-operands: {e}");
+                        std::process::exit(2);
+                    }
+                }
+                return;
+            }
+            let instances = if opts.stream {
+                Instances::Streamed(&streamed, &selection, physical)
+            } else {
+                Instances::Loaded(&instances)
+            };
+            run_suite(
+                &opts,
+                instances,
+                &data_dir.display().to_string(),
+                &load_skipped,
+            );
+        }
+        Command::Gen(gen_opts, opts) => {
+            let mut instance = generate::build_instance(&gen_opts).unwrap_or_else(|e| {
+                error!("gen: {e}");
+                std::process::exit(2);
+            });
+            if let Some(order) = &opts.output_order {
+                if let Err(e) = set_output_order(&mut instance, order) {
+                    error!("--output-order: {e}");
+                    std::process::exit(2);
                 }
             }
+            if let Some(out) = &gen_opts.out {
+                let json = serde_json::to_string_pretty(&generate::instance_json(&instance))
+                    .expect("instance JSON serialization failed");
+                std::fs::write(out, json + "\n")
+                    .unwrap_or_else(|e| panic!("failed to write {}: {e}", out.display()));
+                println!("Wrote {} to {}", instance.name, out.display());
+                return;
+            }
+            if let Some(empty) = instance.zero_sized_operand() {
+                warn!("gen: not running {} ({empty})", instance.name);
+                return;
+            }
+            run_suite(
+                &opts,
+                Instances::Loaded(std::slice::from_ref(&instance)),
+                "gen",
+                &[],
+            );
+        }
+        Command::GenChain(chain_opts, opts) => {
+            configure_threads(&opts);
+            chain::run(&opts, &chain_opts);
+        }
+        Command::Diff(a, b) => {
+            if let Err(e) = diff::print(&a, &b) {
+                error!("diff: {e}");
+                std::process::exit(2);
+            }
+        }
+        Command::Validate(data_dir) => {
+            if !data_dir.is_dir() {
+                error!("validate: {} is not a directory", data_dir.display());
+                std::process::exit(2);
+            }
+            if !validate::run(&data_dir) {
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+/// Why `--max-flops`/`--max-size` rule out a path, if they do. Both compare
+/// the dataset's own cost metadata, so the selection is the same on every
+/// machine.
+fn cost_limit(opts: &Options, path_meta: &PathMeta) -> Option<String> {
+    if let Some(max) = opts.max_flops.filter(|&max| path_meta.log10_flops > max) {
+        return Some(format!(
+            "log10FLOPS {:.2} over --max-flops {max}",
+            path_meta.log10_flops
+        ));
+    }
+    if let Some(max) = opts.max_size.filter(|&max| path_meta.log2_size > max) {
+        return Some(format!(
+            "log2SIZE {:.2} over --max-size {max}",
+            path_meta.log2_size
+        ));
+    }
+    None
+}
+
+/// Why a path is not run: a [`cost_limit`], or an estimated peak above the
+/// available memory unless `--force` is given.
+fn skip_reason(
+    opts: &Options,
+    instance: &BenchmarkInstance,
+    path_meta: &PathMeta,
+) -> Option<String> {
+    cost_limit(opts, path_meta).or_else(|| {
+        let need = estimated_peak_bytes(instance, path_meta);
+        let avail = memory::available_bytes().filter(|&a| !opts.force && need > a as f64)?;
+        Some(format!(
+            "needs ~{:.2} GB (operands + largest intermediate), {:.2} GB available; \
+             --force runs it anyway",
+            need / 1e9,
+            avail as f64 / 1e9
+        ))
+    })
+}
+
+/// SIMD features relevant to GEMM kernels that the running CPU supports,
+/// detected at runtime (not the features the binary was compiled for).
+fn cpu_features() -> Vec<&'static str> {
+    #[allow(unused_mut)]
+    let mut features = Vec::new();
+    #[cfg(target_arch = "x86_64")]
+    {
+        for (name, detected) in [
+            ("sse4.2", is_x86_feature_detected!("sse4.2")),
+            ("avx", is_x86_feature_detected!("avx")),
+            ("avx2", is_x86_feature_detected!("avx2")),
+            ("fma", is_x86_feature_detected!("fma")),
+            ("avx512f", is_x86_feature_detected!("avx512f")),
+        ] {
+            if detected {
+                features.push(name);
+            }
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        for (name, detected) in [
+            ("neon", std::arch::is_aarch64_feature_detected!("neon")),
+            ("sve", std::arch::is_aarch64_feature_detected!("sve")),
+        ] {
+            if detected {
+                features.push(name);
+            }
+        }
+    }
+    features
+}
+
+/// Scaling governors of the cores under `cpu_root` (the sysfs
+/// `/sys/devices/system/cpu`), with how many cores use each, most common
+/// first. Empty when cpufreq isn't exposed (containers, some VMs).
+fn cpu_governors(cpu_root: &Path) -> Vec<(String, usize)> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    let Ok(entries) = std::fs::read_dir(cpu_root) else {
+        return counts;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let Some(id) = name.to_str().and_then(|n| n.strip_prefix("cpu")) else {
+            continue;
+        };
+        if id.is_empty() || !id.bytes().all(|b| b.is_ascii_digit()) {
+            continue;
+        }
+        let Ok(governor) = std::fs::read_to_string(entry.path().join("cpufreq/scaling_governor"))
+        else {
+            continue;
+        };
+        let governor = governor.trim().to_string();
+        match counts.iter_mut().find(|(g, _)| *g == governor) {
+            Some((_, n)) => *n += 1,
+            None => counts.push((governor, 1)),
+        }
+    }
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+/// Header line for the scaling governor, warning when any core isn't on
+/// `performance`. Linux only; elsewhere there is no line.
+fn report_cpu_governor() {
+    if !cfg!(target_os = "linux") {
+        return;
+    }
+    let governors = cpu_governors(Path::new("/sys/devices/system/cpu"));
+    if governors.is_empty() {
+        println!("CPU governor: unknown (no cpufreq in sysfs)");
+        return;
+    }
+    let list: Vec<String> = governors
+        .iter()
+        .map(|(g, n)| format!("{g} ({n} cores)"))
+        .collect();
+    println!("CPU governor: {}", list.join(", "));
+    let slow: Vec<&str> = governors
+        .iter()
+        .map(|(g, _)| g.as_str())
+        .filter(|g| *g != "performance")
+        .collect();
+    if !slow.is_empty() {
+        warn!(
+            "CPU scaling governor is {} rather than performance: frequency scaling \
+             can swing timings by tens of percent; for stable numbers run \
+             `sudo cpupower frequency-set -g performance` first",
+            slow.join("/")
+        );
+    }
+}
+
+/// Thread settings actually applied at startup.
+struct ThreadConfig {
+    /// `(count, vendor)` when a BLAS setter was called.
+    blas: Option<(usize, &'static str)>,
+    /// Size of the rayon global pool (parallel builds only).
+    rayon_pool: Option<usize>,
+    /// Cores the process was restricted to by `--cpu-set`.
+    cpu_set: Option<Vec<usize>>,
+}
+
+/// Apply `--cpu-set`, `RAYON_NUM_THREADS`, `OMP_NUM_THREADS`, and
+/// `--blas-threads` before any contraction runs, so the header reports what is used rather
+/// than what each library might pick up lazily.
+fn configure_threads(opts: &Options) -> ThreadConfig {
+    let env_count = |var: &str| -> Option<usize> {
+        let raw = std::env::var(var).ok()?;
+        match raw.parse() {
+            Ok(n) => Some(n),
+            Err(_) => {
+                warn!("ignoring {var}={raw:?} (not a thread count)");
+                None
+            }
+        }
+    };
+
+    // Before any worker thread exists, so every pool inherits the mask.
+    let cpu_set = opts
+        .cpu_set
+        .as_ref()
+        .and_then(|cpus| match affinity::restrict(cpus) {
+            Ok(()) => Some(cpus.clone()),
+            Err(e) => {
+                warn!("--cpu-set ignored: {e}");
+                None
+            }
+        });
+
+    #[cfg(feature = "parallel")]
+    let rayon_pool = {
+        let mut builder = rayon::ThreadPoolBuilder::new();
+        if let Some(n) = env_count("RAYON_NUM_THREADS") {
+            builder = builder.num_threads(n);
+        }
+        if let Err(e) = builder.build_global() {
+            warn!("rayon global pool already initialized ({e})");
+        }
+        Some(rayon::current_num_threads())
+    };
+    #[cfg(not(feature = "parallel"))]
+    let rayon_pool = None;
+
+    let blas = match opts.blas_threads {
+        Some(n) => {
+            let vendor = blas::set_num_threads(n);
+            if vendor.is_none() {
+                warn!(
+                    "this build links no BLAS with a thread setter; \
+                     setting OMP_NUM_THREADS={n} in-process (may be ignored if BLAS is already initialized)"
+                );
+                std::env::set_var("OMP_NUM_THREADS", n.to_string());
+            }
+            vendor.map(|v| (n, v))
         }
+        // Some vendors read other variables (MKL_NUM_THREADS)
+        // or ignore OMP_NUM_THREADS entirely; push the value through the setter.
+        None => env_count("OMP_NUM_THREADS").and_then(|n| blas::set_num_threads(n).map(|v| (n, v))),
+    };
+
+    ThreadConfig {
+        blas,
+        rayon_pool,
+        cpu_set,
+    }
+}
+
+fn run_suite(
+    opts: &Options,
+    instances: Instances<'_>,
+    source: &str,
+    load_skipped: &[report::Skipped],
+) {
+    let threads = configure_threads(opts);
+    let rayon_threads = std::env::var("RAYON_NUM_THREADS").unwrap_or_else(|_| "unset".into());
+    let omp_threads = std::env::var("OMP_NUM_THREADS").unwrap_or_else(|_| "unset".into());
+
+    println!("{BACKEND_NAME} benchmark suite");
+    println!("==================================");
+    println!("Loaded {} instances from {source}", instances.len());
+    if !load_skipped.is_empty() {
+        println!("Skipped at load: {}", load_skipped.len());
+        for skip in load_skipped {
+            match opts.format {
+                RowFormat::Table => println!("  {} [SKIPPED: {}]", skip.instance, skip.reason),
+                RowFormat::Line => println!("{}", untimed_lines(opts, String::new(), skip)[0]),
+            }
+        }
+    }
+    println!("Backend: {BACKEND_NAME}");
+    println!("RAYON_NUM_THREADS={rayon_threads}, OMP_NUM_THREADS={omp_threads}");
+    let features = cpu_features();
+    if features.is_empty() {
+        println!("CPU features: none detected");
+    } else {
+        println!("CPU features: {}", features.join(" "));
+    }
+    report_cpu_governor();
+    if let Some(cpus) = &threads.cpu_set {
+        let list: Vec<String> = cpus.iter().map(ToString::to_string).collect();
+        println!("CPU set: {}", list.join(","));
+    }
+    if let Some(n) = threads.rayon_pool {
+        println!("Rayon pool: {n} threads");
+    }
+    if let Some((n, vendor)) = threads.blas {
+        println!("BLAS threads: {n} (set via {vendor})");
+    }
+    // `--peak-bandwidth auto` is resolved once here, so rows only ever see
+    // a number.
+    let calibrated;
+    let opts = match opts.peak_bandwidth {
+        Some(PeakBandwidth::Auto) => {
+            let gbps =
+                bandwidth::copy_gbps(bandwidth::CALIBRATION_ELEMENTS, bandwidth::CALIBRATION_RUNS);
+            println!(
+                "Peak bandwidth: {gbps:.2} GB/s (calibrated: copy_into of {} MB, best of {})",
+                bandwidth::CALIBRATION_ELEMENTS * 8 / (1 << 20),
+                bandwidth::CALIBRATION_RUNS
+            );
+            calibrated = Options {
+                peak_bandwidth: Some(PeakBandwidth::Given(gbps)),
+                ..opts.clone()
+            };
+            &calibrated
+        }
+        Some(PeakBandwidth::Given(gbps)) => {
+            println!("Peak bandwidth: {gbps:.2} GB/s (--peak-bandwidth)");
+            opts
+        }
+        None => opts,
+    };
+    if opts.once {
+        println!(
+            "Timing: --once, a single run with no warmup (smoke test, not a reliable benchmark)"
+        );
+    } else if opts.min_mode {
+        let warmup = if opts.adaptive_warmup {
+            "adaptive warmup".to_string()
+        } else {
+            format!("{} warmup", opts.warmup)
+        };
+        println!(
+            "Timing: runs until the minimum holds for {} runs or {}s pass ({warmup}); Median and IQR over all of them",
+            opts.min_stable, opts.min_budget
+        );
+    } else if opts.adaptive_warmup {
+        println!(
+            "Timing: median of {} runs (adaptive warmup: until consecutive runs within {:.1}%, max {})",
+            opts.runs,
+            opts.warmup_tolerance * 100.0,
+            opts.max_warmup
+        );
+    } else {
+        println!(
+            "Timing: median of {} runs ({} warmup)",
+            opts.runs, opts.warmup
+        );
+    }
+    if opts.min_mode {
+        println!("  Runs ending in ! hit the time budget before the minimum was stable");
+    }
+    if opts.first_result {
+        println!(
+            "First result: strided-opteinsum returns the whole output at once, \
+             so First (ms) equals the median total"
+        );
+    }
+    if opts.warmup_shared {
+        println!(
+            "  warmup runs for every instance first, then the timed runs with no further warmup"
+        );
+    }
+    for &(strategy_name, _) in STRATEGIES {
+        let (warmup, runs) = opts.counts(strategy_name);
+        if opts.min_mode {
+            // The run count only applies to the extra comparison timings.
+            if warmup != opts.warmup {
+                println!("  {strategy_name}: {warmup} warmup");
+            }
+        } else if (warmup, runs) != (opts.warmup, opts.runs) {
+            if opts.adaptive_warmup {
+                println!("  {strategy_name}: median of {runs} runs");
+            } else {
+                println!("  {strategy_name}: median of {runs} runs ({warmup} warmup)");
+            }
+        }
+    }
+    if let Instances::Streamed(..) = instances {
+        println!("Streaming: one instance in memory at a time, read again for each strategy");
+    }
+    if opts.julia_compatible && !(extra_header(opts).is_empty() && result_header(opts).is_empty()) {
+        error!("--julia-compatible-output: the Julia table has no extra columns; drop the flags that add them");
+        std::process::exit(2);
+    }
+    if opts.format == RowFormat::Line
+        && !(extra_header(opts).is_empty() && result_header(opts).is_empty())
+    {
+        error!("--format line: lines carry the base fields only; drop the flags that add columns");
+        std::process::exit(2);
+    }
+    if opts.prefix_sweep {
+        run_prefix_sweep(opts, instances.all());
+        return;
+    }
+    if opts.bisect {
+        bisect::run(opts, instances.all());
+        return;
+    }
+    if let Some((name, step)) = &opts.isolate_step {
+        run_isolate_step(opts, instances.all(), name, *step);
+        return;
+    }
+    if let Some(baseline) = &opts.rerun_regressions {
+        run_rerun_regressions(opts, instances.all(), baseline);
+        return;
+    }
+    if opts.mixed_precision {
+        println!(
+            "Mixed precision: inputs rounded to f32, stored and accumulated as f64 \
+             (backend has no f32 operands; timings are f64)"
+        );
+    }
+
+    let python = if opts.cross_check {
+        let found = crosscheck::find_python();
+        match found {
+            Some(py) => println!(
+                "Cross-check: {py} + numpy (instances up to {} elements)",
+                crosscheck::MAX_ELEMENTS
+            ),
+            None => warn!("--cross-check needs python3/python with numpy; skipping cross-checks"),
+        }
+        found
+    } else {
+        None
+    };
+
+    match opts.layout {
+        Layout::Col => {}
+        Layout::Row => println!("Layout: row-major operands (same dims, reversed strides)"),
+        Layout::Both => {
+            println!("Layout: column-major timed; Row (ms) repeats the runs on row-major operands")
+        }
+    }
+    if opts.hugepages {
+        let mode = hugepages::thp_mode();
+        println!(
+            "Huge pages: Huge (ms) repeats the runs on MADV_HUGEPAGE operands (THP mode: {})",
+            mode.as_deref().unwrap_or("unavailable")
+        );
+        if matches!(mode.as_deref(), None | Some("never")) {
+            warn!("transparent huge pages are off; Huge (ms) will match the regular median");
+        }
+    }
+    if let Some(dtype) = &opts.force_dtype {
+        println!("Dtype: every instance run as {dtype} (--force-dtype)");
+    }
+    if let Some(acc) = &opts.accumulate_dtype {
+        println!("Accumulation: {acc} (the operand dtype)");
+    }
+    if let Some(secs) = opts.time_budget {
+        println!("Time budget: {secs} s; instances not started by then are tagged SKIPPED");
+    }
+    if opts.verify_checksum && opts.checksum_mode != ChecksumMode::SumReal {
+        println!(
+            "Checksum mode: {} (expected_checksum values must be pinned in this mode)",
+            opts.checksum_mode.name()
+        );
+    }
+    if let Some(n) = opts.determinism_repeats {
+        println!(
+            "Determinism: seeded data contracted {n} times per instance, each result compared \
+             bitwise with the first; a divergence fails the run"
+        );
+    } else if opts.check_determinism {
+        println!(
+            "Determinism: seeded data contracted twice per instance, results compared bitwise"
+        );
+    }
+    if opts.kernel_path {
+        println!(
+            "Kernel path: GEMM counts the steps strided-einsum2 hands to the batched GEMM; \
+             [fallback] rows run at least one step on its element-wise kernel"
+        );
+    }
+    if opts.report_sparsity {
+        println!("Sparsity: Zero % is the share of the result on seeded data that is zero up to rounding");
+    }
+    if opts.cse {
+        println!(
+            "CSE: counting shared subtrees; path trees read each tensor once, \
+             so a tree with none shared is timed unchanged"
+        );
+    }
+
+    if let Some(label) = &opts.label {
+        println!("Label: {label}");
+    }
+    let db = opts.sqlite.as_ref().map(|path| {
+        let run = sqlite::RunInfo {
+            label: opts.label.clone(),
+            backend: BACKEND_NAME,
+            rayon_threads: std::env::var("RAYON_NUM_THREADS").ok(),
+            omp_threads: std::env::var("OMP_NUM_THREADS").ok(),
+        };
+        match sqlite::ResultDb::open(path, run) {
+            Ok(db) => {
+                info!("recording to SQLite: {}", path.display());
+                db
+            }
+            Err(e) => {
+                error!("--sqlite: {e}");
+                std::process::exit(2);
+            }
+        }
+    });
+
+    let mut shuffle = opts.shuffle.map(|seed| {
+        println!("Shuffle: instances run in random order (seed {seed}), printed sorted");
+        fill::SplitMix64::new(seed)
+    });
+
+    let json_outputs: Vec<(&Path, bool)> = [
+        opts.output_json.as_deref().map(|p| (p, false)),
+        opts.output_json_samples.as_deref().map(|p| (p, true)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    let mut records: Vec<report::Record> = Vec::new();
+    // Everything not timed, for the JSON `skipped` array.
+    let mut skipped: Vec<report::Skipped> = load_skipped.to_vec();
+    let keep_records = !json_outputs.is_empty()
+        || opts.output_bincode.is_some()
+        || opts.prometheus_output.is_some();
+
+    // Peak bytes per instance, per strategy, for the `--track-memory` summary.
+    let mut peaks: Vec<Vec<Option<usize>>> = vec![Vec::new(); instances.len()];
+
+    let mut checksum_failures = 0;
+    let mut divergences = 0;
+
+    // Median per instance, per strategy, for the strategy comparison.
+    let mut medians: Vec<[Option<f64>; STRATEGIES.len()]> =
+        vec![[None; STRATEGIES.len()]; instances.len()];
+
+    // One output buffer per instance, shared by both strategies.
+    let mut outputs: Vec<Option<OutputBuffer>> = (0..instances.len()).map(|_| None).collect();
+
+    let started = Instant::now();
+    let over_budget = || {
+        opts.time_budget
+            .is_some_and(|secs| started.elapsed().as_secs_f64() > secs)
+    };
+    let mut budget_skipped = 0;
+
+    let strategies = instances.strategies();
+    if opts.warmup_shared {
+        warm_up_all(opts, instances.all(), &strategies, &mut outputs);
+    }
+    for (s, &(strategy_name, get_path)) in strategies.iter().enumerate() {
+        let shared_warmup = opts.counts(strategy_name).0;
+        let mut opts = opts.for_strategy(strategy_name);
+        if opts.warmup_shared {
+            // Warmed up already, with every other instance.
+            opts.warmup = 0;
+        }
+        let opts = &opts;
+        let extra_header = extra_header(opts);
+        let result_header = result_header(opts);
+        let skip = |name: &str, reason: &str| report::Skipped {
+            instance: name.to_string(),
+            strategy: Some(strategy_name.to_string()),
+            reason: reason.to_string(),
+        };
+        // A row with no path costs: not started, or `n/a`.
+        let blank = extra_header.len() + result_header.len();
+        let blank_row = |name: &str, tensors: &dyn std::fmt::Display, median: &str| {
+            format!(
+                "{name:<50} {tensors:>8} {:>10} {:>12}{:blank$} {median:>12} {:>10}",
+                "-", "-", "", "-",
+            )
+        };
+        println!();
+        println!("Strategy: {strategy_name}");
+        if !opts.summary_only && opts.format == RowFormat::Table {
+            println!(
+                "{:<50} {:>8} {:>10} {:>12}{extra_header}{result_header} {:>12} {:>10}",
+                "Instance", "Tensors", "log10FLOPS", "log2SIZE", "Median (ms)", "IQR (ms)"
+            );
+            println!(
+                "{}",
+                "-".repeat(108 + extra_header.len() + result_header.len())
+            );
+        }
+
+        // Rows held back for `--top` (printed slowest-first) or `--shuffle`
+        // (printed in sorted order) until the strategy finishes, and never
+        // printed with `--summary-only`.
+        let hold = opts.top.is_some() || shuffle.is_some() || opts.summary_only;
+        let mut summary = Summary::default();
+        let mut held: Vec<(usize, Option<f64>, Vec<String>)> = Vec::new();
+        let mut order: Vec<usize> = (0..instances.len()).collect();
+        if let Some(rng) = &mut shuffle {
+            for i in (1..order.len()).rev() {
+                order.swap(i, (rng.next_u64() % (i as u64 + 1)) as usize);
+            }
+        }
+        for idx in order {
+            if over_budget() {
+                summary.over_budget += 1;
+                budget_skipped += 1;
+                peaks[idx].push(None);
+                let reason = "--time-budget exceeded";
+                let (name, row) = match instances {
+                    Instances::Loaded(all) => (
+                        all[idx].name.as_str(),
+                        blank_row(&all[idx].name, &all[idx].num_tensors, "SKIP"),
+                    ),
+                    Instances::Streamed(paths, ..) => {
+                        let name = file_stem(&paths[idx]);
+                        (name, blank_row(name, &"-", "SKIP"))
+                    }
+                };
+                let entry = skip(name, reason);
+                let lines = untimed_lines(opts, row, &entry);
+                skipped.push(entry);
+                if hold {
+                    held.push((idx, None, lines));
+                } else {
+                    lines.iter().for_each(|line| println!("{line}"));
+                }
+                continue;
+            }
+            let read;
+            let instance = match instances {
+                Instances::Loaded(all) => &all[idx],
+                Instances::Streamed(paths, selection, _) => {
+                    // A file changed since selection warns in `load_instance`.
+                    let loaded = load_instance(&paths[idx]).and_then(|i| {
+                        selection
+                            .apply(i)
+                            .ok_or_else(|| "no longer passes selection".to_string())
+                    });
+                    match loaded {
+                        Ok(instance) => {
+                            read = instance;
+                            &read
+                        }
+                        Err(reason) => {
+                            summary.skipped += 1;
+                            peaks[idx].push(None);
+                            let name = file_stem(&paths[idx]);
+                            let entry = skip(name, &reason);
+                            let lines = untimed_lines(opts, blank_row(name, &"-", "SKIP"), &entry);
+                            skipped.push(entry);
+                            if hold {
+                                held.push((idx, None, lines));
+                            } else {
+                                lines.iter().for_each(|line| println!("{line}"));
+                            }
+                            continue;
+                        }
+                    }
+                }
+            };
+            let output = &mut outputs[idx];
+            if instance.skips(strategy_name) {
+                summary.not_applicable += 1;
+                peaks[idx].push(None);
+                let reason = if instance.skip_strategies.iter().any(|s| s == strategy_name) {
+                    "listed in skip_strategies"
+                } else {
+                    "no physical path"
+                };
+                let entry = skip(&instance.name, reason);
+                let mut line = blank_row(&instance.name, &instance.num_tensors, "n/a");
+                if opts.format == RowFormat::Line {
+                    line = logfmt(&[
+                        ("instance", entry.instance.clone()),
+                        ("strategy", strategy_name.to_string()),
+                        ("status", "n/a".into()),
+                        ("reason", entry.reason.clone()),
+                    ]);
+                } else if !opts.julia_compatible {
+                    line += &format!("  [SKIPPED: {reason}]");
+                }
+                skipped.push(entry);
+                if hold {
+                    held.push((idx, None, vec![line]));
+                } else {
+                    println!("{line}");
+                }
+                continue;
+            }
+            let path_meta = get_path(&instance.paths);
+            let extra = extra_cells(opts, instance, path_meta);
+            let limit = cost_limit(opts, path_meta);
+            if let Some(reason) = skip_reason(opts, instance, path_meta) {
+                // Limits were asked for; only the memory guard is a surprise.
+                if limit.is_some() {
+                    info!(instance = %instance.name, strategy = strategy_name, "skip: {reason}");
+                } else {
+                    warn!(instance = %instance.name, strategy = strategy_name, "skip: {reason}");
+                }
+                summary.skipped += 1;
+                peaks[idx].push(None);
+                let line = format!(
+                    "{:<50} {:>8} {:>10.2} {:>12.2}{extra}{} {:>12} {:>10}",
+                    instance.name,
+                    instance.num_tensors,
+                    path_meta.log10_flops,
+                    path_meta.log2_size,
+                    result_cells(opts, None),
+                    "SKIP",
+                    "-",
+                );
+                let entry = skip(&instance.name, &reason);
+                let lines = untimed_lines(opts, line, &entry);
+                skipped.push(entry);
+                if hold {
+                    held.push((idx, None, lines));
+                } else {
+                    lines.iter().for_each(|line| println!("{line}"));
+                }
+                continue;
+            }
+            if (opts.reuse_output || instance.scaling().is_some()) && output.is_none() {
+                match OutputBuffer::new(instance) {
+                    Ok(buf) => *output = Some(buf),
+                    Err(e) => warn!(instance = %instance.name, "no output buffer: {e}"),
+                }
+            }
+            debug!(instance = %instance.name, strategy = strategy_name, "running");
+            let outcome = run_instance(instance, path_meta, opts, output.as_mut());
+            if let Instances::Streamed(..) = instances {
+                *output = None;
+            }
+            peaks[idx].push(outcome.as_ref().ok().and_then(|r| r.peak_bytes));
+            if let Some(r) = outcome.as_ref().ok().filter(|_| opts.reproducibility) {
+                if reproducibility(r.median_ms, r.iqr_ms()) < REPRO_FLAG_BELOW {
+                    summary.low_reproducibility.push(instance.name.clone());
+                }
+            }
+            if let Some(db) = &db {
+                let row = sqlite::Row {
+                    instance: &instance.name,
+                    strategy: strategy_name,
+                    num_tensors: instance.num_tensors,
+                    log10_flops: path_meta.log10_flops,
+                    log2_size: path_meta.log2_size,
+                    timing: outcome
+                        .as_ref()
+                        .ok()
+                        .map(|r| (r.median_ms, r.q1_ms, r.q3_ms)),
+                    error: outcome.as_ref().err().map(ToString::to_string),
+                };
+                if let Err(e) = db.insert(&row) {
+                    warn!(instance = %instance.name, "--sqlite insert failed: {e}");
+                }
+            }
+            if keep_records {
+                let root = plan(instance, path_meta).root;
+                let record = report::Record {
+                    instance: instance.name.clone(),
+                    strategy: strategy_name.to_string(),
+                    label: opts.label.clone(),
+                    num_tensors: instance.num_tensors,
+                    log10_flops: path_meta.log10_flops,
+                    log2_size: path_meta.log2_size,
+                    tree_depth: tree::depth(&root),
+                    tree_width: tree::width(&root),
+                    tree_balance: tree::balance(&root),
+                    median_ms: None,
+                    q1_ms: None,
+                    q3_ms: None,
+                    iqr_ms: None,
+                    mean_ms: None,
+                    min_ms: None,
+                    max_ms: None,
+                    flops_per_second: None,
+                    reproducibility: None,
+                    warmup_runs: outcome.as_ref().ok().map(|r| {
+                        if opts.warmup_shared {
+                            shared_warmup
+                        } else {
+                            r.warmup_runs
+                        }
+                    }),
+                    timed_runs: None,
+                    peak_bytes: outcome.as_ref().ok().and_then(|r| r.peak_bytes),
+                    error: outcome.as_ref().err().map(ToString::to_string),
+                    samples_ns: None,
+                };
+                records.push(match &outcome {
+                    Ok(result) => record.with_samples(result.samples_ns.clone()),
+                    Err(_) => record,
+                });
+            }
+            let (median_ms, lines) = match outcome {
+                Ok(mut result) => {
+                    if let Some(py) = python.filter(|_| crosscheck::is_small(instance, path_meta)) {
+                        result.numpy_err = report_cross_check(py, instance, path_meta);
+                    }
+                    if let Some(check) = result.checksum.as_ref().filter(|c| !c.ok) {
+                        checksum_failures += 1;
+                        error!(
+                            instance = %instance.name,
+                            "checksum mismatch: expected {:e}, got {:e}",
+                            check.expected,
+                            check.actual
+                        );
+                    }
+                    if let Some(Some((k, diff))) = result.rerun_diff {
+                        if opts.determinism_repeats.is_some() {
+                            divergences += 1;
+                            error!(
+                                instance = %instance.name,
+                                "results diverged: repeat {k} differs from repeat 1 by up to {diff:e}"
+                            );
+                        } else {
+                            warn!(
+                                instance = %instance.name,
+                                "not bit-reproducible: reruns differ by up to {diff:e}"
+                            );
+                        }
+                    }
+                    let measured = result_cells(opts, Some(&result));
+                    let mut lines = vec![match opts.format {
+                        RowFormat::Table => format!(
+                            "{:<50} {:>8} {:>10.2} {:>12.2}{extra}{measured} {:>12.3} {:>10.3}",
+                            row_name(opts, instance, path_meta),
+                            instance.num_tensors,
+                            path_meta.log10_flops,
+                            path_meta.log2_size,
+                            result.median_ms,
+                            result.iqr_ms(),
+                        ),
+                        RowFormat::Line => result_line(instance, strategy_name, path_meta, &result),
+                    }];
+                    if opts.trace_steps {
+                        lines.extend(report_step_variance(
+                            opts,
+                            instance,
+                            strategy_name,
+                            path_meta,
+                        ));
+                    }
+                    if opts.profile_tree {
+                        lines.extend(report_tree_levels(opts, instance, path_meta));
+                    }
+                    if opts.show_tree {
+                        let root = plan(instance, path_meta).root;
+                        lines.push(format!("  tree: {}", tree::parenthesized(&root)));
+                    }
+                    (Some(result.median_ms), lines)
+                }
+                Err(e) => {
+                    let measured = result_cells(opts, None);
+                    warn!(instance = %instance.name, "backend error: {e}");
+                    let line = format!(
+                        "{:<50} {:>8} {:>10.2} {:>12.2}{extra}{measured} {:>12} {:>10}",
+                        row_name(opts, instance, path_meta),
+                        instance.num_tensors,
+                        path_meta.log10_flops,
+                        path_meta.log2_size,
+                        "SKIP",
+                        "-",
+                    );
+                    let entry = skip(&instance.name, &e.to_string());
+                    let lines = untimed_lines(opts, line, &entry);
+                    skipped.push(entry);
+                    (None, lines)
+                }
+            };
+            medians[idx][s] = median_ms;
+            match median_ms {
+                Some(ms) => summary.timed.push(Timed {
+                    name: instance.name.clone(),
+                    median_ms: ms,
+                    log10_flops: path_meta.log10_flops,
+                    weight: instance.weight(),
+                }),
+                None => summary.skipped += 1,
+            }
+            if hold {
+                held.push((idx, median_ms, lines));
+            } else {
+                lines.iter().for_each(|line| println!("{line}"));
+            }
+        }
+
+        held.sort_by_key(|&(idx, _, _)| idx);
+        if opts.summary_only {
+            // Rows were only collected; the summary below is the output.
+        } else if let Some(n) = opts.top {
+            let ran = held.len();
+            held.retain(|(_, median, _)| median.is_some());
+            let skipped = ran - held.len();
+            held.sort_by(|a, b| b.1.unwrap().total_cmp(&a.1.unwrap()));
+            held.truncate(n);
+            for (_, _, lines) in &held {
+                lines.iter().for_each(|line| println!("{line}"));
+            }
+            println!(
+                "(top {} of {} by median; {skipped} skipped)",
+                held.len(),
+                ran - skipped
+            );
+        } else {
+            for (_, _, lines) in &held {
+                lines.iter().for_each(|line| println!("{line}"));
+            }
+        }
+        if !opts.julia_compatible {
+            summary.print(strategy_name);
+        }
+    }
+
+    if let Some(wins) = StrategyWins::tally(&medians).filter(|_| !opts.julia_compatible) {
+        wins.print();
+    }
+    if budget_skipped > 0 {
+        warn!(
+            "--time-budget of {} s exceeded: {budget_skipped} of {} instance runs not started",
+            opts.time_budget.unwrap_or_default(),
+            instances.len() * strategies.len()
+        );
+    }
+    if opts.flat && !over_budget() {
+        run_flat(opts, instances.all());
+    }
+    if opts.fused && !over_budget() {
+        run_fused(opts, instances.all(), &medians);
+    }
+    if let Some(n) = opts.seeds.filter(|_| !over_budget()) {
+        run_seeds(opts, instances.all(), n);
+    }
+    if opts.track_memory {
+        report_memory_by_strategy(instances.all(), &peaks);
+    }
+    if opts.multi_output && !over_budget() {
+        run_multi_output(opts, instances.all());
+    }
+    if checksum_failures > 0 {
+        error!("{checksum_failures} checksum mismatch(es)");
+    }
+    if divergences > 0 {
+        error!("{divergences} result(s) diverged across repeats");
+    }
+
+    if keep_records {
+        let run = report::RunInfo {
+            backend: BACKEND_NAME.into(),
+            label: opts.label.clone(),
+            rayon_num_threads: std::env::var("RAYON_NUM_THREADS").ok(),
+            omp_num_threads: std::env::var("OMP_NUM_THREADS").ok(),
+            timed_runs: opts.runs,
+            cpu_features: cpu_features().into_iter().map(String::from).collect(),
+        };
+        for (path, samples) in json_outputs {
+            match report::write(path, &run, &records, &skipped, samples) {
+                Ok(()) => info!("wrote {} results to {}", records.len(), path.display()),
+                Err(e) => error!("JSON output: {e}"),
+            }
+        }
+        if let Some(path) = &opts.output_bincode {
+            match report::write_bincode(path, &run, &records) {
+                Ok(()) => info!("wrote {} results to {}", records.len(), path.display()),
+                Err(e) => error!("bincode output: {e}"),
+            }
+        }
+        if let Some(path) = &opts.prometheus_output {
+            match report::write_prometheus(path, &run, &records) {
+                Ok(()) => info!("wrote {} results to {}", records.len(), path.display()),
+                Err(e) => error!("Prometheus output: {e}"),
+            }
+        }
+    }
+    if checksum_failures > 0 || divergences > 0 {
+        std::process::exit(1);
+    }
+}
+
+/// Median and IQR in milliseconds of [`flat::evaluate`] on zero operands,
+/// with the global warmup and run counts.
+fn time_flat(
+    opts: &Options,
+    instance: &BenchmarkInstance,
+    input_indices: &[Vec<char>],
+    output_indices: &[char],
+    dims: &HashMap<char, usize>,
+) -> Result<(f64, f64), String> {
+    let eval = || {
+        let operands = create_operands(&instance.shapes_colmajor, &instance.dtype);
+        let t0 = Instant::now();
+        let out = flat::evaluate(input_indices, output_indices, dims, &operands);
+        (t0.elapsed(), black_box(out))
+    };
+    if let (_, Err(e)) = eval() {
+        return Err(e.to_string());
+    }
+    // The checked run above counts as the first warmup.
+    for _ in 1..opts.warmup {
+        let _ = eval();
+    }
+    let mut samples: Vec<f64> = (0..opts.runs)
+        .map(|_| eval().0.as_secs_f64() * 1e3)
+        .collect();
+    samples.sort_by(|a, b| a.total_cmp(b));
+    let n = samples.len();
+    Ok((samples[n / 2], samples[3 * n / 4] - samples[n / 4]))
+}
+
+/// `--flat`: time [`flat::evaluate`] as a `flat` pseudo-strategy table.
+/// `log10FLOPS` is the flat loop's iteration count and `log2SIZE` the
+/// output size, since there are no intermediates; instances over
+/// [`flat::MAX_LOG10_ITERATIONS`] print `n/a`.
+fn run_flat(opts: &Options, instances: &[BenchmarkInstance]) {
+    println!();
+    println!("Strategy: flat");
+    println!(
+        "{:<50} {:>8} {:>10} {:>12} {:>12} {:>10}",
+        "Instance", "Tensors", "log10FLOPS", "log2SIZE", "Median (ms)", "IQR (ms)"
+    );
+    println!("{}", "-".repeat(108));
+    for instance in instances {
+        let (input_indices, output_indices) = parse_format_string(&instance.format_string_colmajor);
+        let dims = match path::dim_map(&input_indices, &instance.shapes_colmajor) {
+            Ok(dims) => dims,
+            Err(e) => {
+                warn!(instance = %instance.name, "flat evaluation skipped: {e}");
+                continue;
+            }
+        };
+        let log10_iters = flat::log10_iterations(&input_indices, &output_indices, &dims);
+        let log2_out = path::output_elements(&output_indices, &dims).log2();
+        let row = |median: String, iqr: String| {
+            println!(
+                "{:<50} {:>8} {log10_iters:>10.2} {log2_out:>12.2} {median:>12} {iqr:>10}",
+                instance.name, instance.num_tensors,
+            )
+        };
+        if log10_iters > flat::MAX_LOG10_ITERATIONS {
+            row("n/a".into(), "-".into());
+            continue;
+        }
+        match time_flat(opts, instance, &input_indices, &output_indices, &dims) {
+            Ok((median, iqr)) => row(format!("{median:.3}"), format!("{iqr:.3}")),
+            Err(e) => {
+                warn!(instance = %instance.name, "flat evaluation failed: {e}");
+                row("SKIP".into(), "-".into());
+            }
+        }
+    }
+}
+
+/// How far (in `log10`) the flat loop's iteration count may exceed the
+/// path's `log10_flops` for `--fused` to run it: the dataset stores FLOPs
+/// to four decimals.
+const FUSED_SLACK: f64 = 1e-3;
+
+/// `--fused`: time the single pass of [`flat::evaluate`], which materializes
+/// no intermediate, against this run's `opt_flops` medians. Fusion only
+/// applies where the pass does no more multiply-adds than the path
+/// (`log10` iterations within [`FUSED_SLACK`] of its FLOPs), e.g. a single
+/// step or a Hadamard product of several operands; other instances print
+/// `n/a`.
+fn run_fused(
+    opts: &Options,
+    instances: &[BenchmarkInstance],
+    medians: &[[Option<f64>; STRATEGIES.len()]],
+) {
+    println!();
+    println!("Strategy: fused (one pass, no intermediates; against opt_flops)");
+    println!(
+        "{:<50} {:>8} {:>10} {:>12} {:>10} {:>8} {:>12} {:>10}",
+        "Instance",
+        "Tensors",
+        "log10FLOPS",
+        "log2SIZE",
+        "Tree (ms)",
+        "Speedup",
+        "Median (ms)",
+        "IQR (ms)"
+    );
+    println!("{}", "-".repeat(128));
+    let (mut applied, mut faster) = (0, 0);
+    for (instance, [tree_ms, ..]) in instances.iter().zip(medians) {
+        let (input_indices, output_indices) = parse_format_string(&instance.format_string_colmajor);
+        let dims = match path::dim_map(&input_indices, &instance.shapes_colmajor) {
+            Ok(dims) => dims,
+            Err(e) => {
+                warn!(instance = %instance.name, "fused evaluation skipped: {e}");
+                continue;
+            }
+        };
+        let log10_iters = flat::log10_iterations(&input_indices, &output_indices, &dims);
+        let log2_out = path::output_elements(&output_indices, &dims).log2();
+        let tree = tree_ms.map_or("-".into(), |ms| format!("{ms:.3}"));
+        let row = |speedup: String, median: String, iqr: String| {
+            println!(
+                "{:<50} {:>8} {log10_iters:>10.2} {log2_out:>12.2} {tree:>10} {speedup:>8} {median:>12} {iqr:>10}",
+                instance.name, instance.num_tensors,
+            )
+        };
+        let path_flops = instance.paths.opt_flops.log10_flops;
+        if instance.skips("opt_flops")
+            || log10_iters > path_flops + FUSED_SLACK
+            || log10_iters > flat::MAX_LOG10_ITERATIONS
+        {
+            row("-".into(), "n/a".into(), "-".into());
+            continue;
+        }
+        match time_flat(opts, instance, &input_indices, &output_indices, &dims) {
+            Ok((median, iqr)) => {
+                applied += 1;
+                let speedup = tree_ms.map(|tree| tree / median);
+                faster += usize::from(speedup.is_some_and(|x| x > 1.0));
+                row(
+                    speedup.map_or("-".into(), |x| format!("{x:.2}x")),
+                    format!("{median:.3}"),
+                    format!("{iqr:.3}"),
+                );
+            }
+            Err(e) => {
+                warn!(instance = %instance.name, "fused evaluation failed: {e}");
+                row("-".into(), "SKIP".into(), "-".into());
+            }
+        }
+    }
+    println!();
+    println!("Fused: faster than the tree on {faster} of {applied} instances where it applies");
+}
+
+/// How far the slowest seed's median may be above the fastest one's,
+/// relative to it, before `--seeds` calls the timing data-dependent.
+const SEED_SPREAD_FLAG: f64 = 0.05;
+
+/// Median and IQR in milliseconds of `code` on the operands `operands`
+/// makes, with `opts`'s warmup and run counts.
+fn time_on_data(
+    opts: &Options,
+    instance: &BenchmarkInstance,
+    code: &EinsumCode,
+    operands: impl Fn() -> Vec<EinsumOperand<'static>>,
+) -> Result<(f64, f64), EinsumError> {
+    let transpose_perm = instance.pure_transpose();
+    let eval = || -> Result<_, EinsumError> {
+        let operands = operands();
+        let t0 = Instant::now();
+        let out = evaluate_once(code, transpose_perm.as_deref(), None, operands, None)?;
+        let elapsed = t0.elapsed();
+        black_box(out);
+        Ok(elapsed.as_secs_f64() * 1e3)
+    };
+    for _ in 0..opts.warmup {
+        eval()?;
+    }
+    let mut samples = (0..opts.runs)
+        .map(|_| eval())
+        .collect::<Result<Vec<_>, _>>()?;
+    samples.sort_by(|a, b| a.total_cmp(b));
+    let n = samples.len();
+    Ok((samples[n / 2], samples[3 * n / 4] - samples[n / 4]))
+}
+
+/// The fastest and slowest of `seeds` (median, IQR) timings, and whether
+/// they differ by more than both [`SEED_SPREAD_FLAG`] and the widest IQR,
+/// so that the spread is the data's and not the machine's.
+fn seed_spread(seeds: &[(f64, f64)]) -> (f64, f64, bool) {
+    let min = seeds.iter().map(|s| s.0).fold(f64::INFINITY, f64::min);
+    let max = seeds.iter().map(|s| s.0).fold(0.0, f64::max);
+    let widest_iqr = seeds.iter().map(|s| s.1).fold(0.0, f64::max);
+    let varies = max - min > SEED_SPREAD_FLAG * min && max - min > widest_iqr;
+    (min, max, varies)
+}
+
+/// `--seeds`: time every instance's `opt_flops` path on zero operands and
+/// on `n` seeded inputs ([`fill::random_operands`], seeds counting up from
+/// [`fill::DEFAULT_SEED`]), and flag instances whose time varies with the
+/// data. Every input is a set of independent column-major arrays, without
+/// `alpha`/`beta`, so that only the values differ between columns.
+fn run_seeds(opts: &Options, instances: &[BenchmarkInstance], n: usize) {
+    let opts = opts.for_strategy("opt_flops");
+    println!();
+    println!("Seeds: opt_flops on zero operands and {n} seeded inputs");
+    println!(
+        "{:<50} {:>8} {:>10} {:>12} {:>11} {:>11} {:>11} {:>8} {:>14}",
+        "Instance",
+        "Tensors",
+        "log10FLOPS",
+        "log2SIZE",
+        "Zeros (ms)",
+        "Fastest",
+        "Slowest",
+        "Spread",
+        "Timing"
+    );
+    println!("{}", "-".repeat(143));
+    let (mut compared, mut varying) = (0, 0);
+    for instance in instances {
+        let path_meta = &instance.paths.opt_flops;
+        let row = |zeros: String, fastest: String, slowest: String, spread: String, verdict| {
+            format!(
+                "{:<50} {:>8} {:>10.2} {:>12.2} {zeros:>11} {fastest:>11} {slowest:>11} {spread:>8} {verdict:>14}",
+                instance.name, instance.num_tensors, path_meta.log10_flops, path_meta.log2_size,
+            )
+        };
+        let blank = || row("-".into(), "-".into(), "-".into(), "-".into(), "SKIP");
+        if instance.skips("opt_flops") {
+            let line = row("-".into(), "-".into(), "-".into(), "-".into(), "n/a");
+            println!("{line}");
+            continue;
+        }
+        if let Some(reason) = skip_reason(&opts, instance, path_meta) {
+            skipped_lines(&opts, blank(), &reason)
+                .iter()
+                .for_each(|line| println!("{line}"));
+            continue;
+        }
+        let code = plan(instance, path_meta);
+        let hermitian = instance.hermitian.as_deref().unwrap_or(&[]);
+        let timings = std::iter::once(None)
+            .chain((0..n as u64).map(Some))
+            .map(|seed| {
+                time_on_data(&opts, instance, &code, || match seed {
+                    None => create_operands(&instance.shapes_colmajor, &instance.dtype),
+                    Some(k) => fill::random_operands(
+                        &instance.shapes_colmajor,
+                        &instance.dtype,
+                        hermitian,
+                        fill::DEFAULT_SEED.wrapping_add(k),
+                        |x| x,
+                    ),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>();
+        let timings = match timings {
+            Ok(timings) => timings,
+            Err(e) => {
+                warn!(instance = %instance.name, "--seeds: {e}");
+                skipped_lines(&opts, blank(), &e.to_string())
+                    .iter()
+                    .for_each(|line| println!("{line}"));
+                continue;
+            }
+        };
+        let (fastest, slowest, varies) = seed_spread(&timings[1..]);
+        compared += 1;
+        varying += usize::from(varies);
+        println!(
+            "{}",
+            row(
+                format!("{:.3}", timings[0].0),
+                format!("{fastest:.3}"),
+                format!("{slowest:.3}"),
+                format!("{:.1}%", (slowest / fastest - 1.0) * 100.0),
+                if varies { "data-dependent" } else { "stable" },
+            )
+        );
+    }
+    println!();
+    println!(
+        "Seeds: timing varies with the data on {varying} of {compared} instances \
+         (seed medians over {:.0}% and the widest IQR apart)",
+        SEED_SPREAD_FLAG * 100.0
+    );
+}
+
+/// One timed instance in a [`Summary`].
+struct Timed {
+    name: String,
+    median_ms: f64,
+    log10_flops: f64,
+    weight: f64,
+}
+
+impl Timed {
+    fn gflops(&self) -> f64 {
+        10f64.powf(self.log10_flops) / (self.median_ms * 1e-3) / 1e9
+    }
+}
+
+/// Aggregate of one strategy's table.
+#[derive(Default)]
+struct Summary {
+    timed: Vec<Timed>,
+    /// Backend errors and memory-guard skips.
+    skipped: usize,
+    /// Instances listing the strategy in `skip_strategies`.
+    not_applicable: usize,
+    /// Instances not started because `--time-budget` ran out.
+    over_budget: usize,
+    /// Instances scoring under [`REPRO_FLAG_BELOW`] (`--reproducibility`).
+    low_reproducibility: Vec<String>,
+}
+
+impl Summary {
+    /// Print the block under a strategy's rows. No line ends in two plain
+    /// numbers, so log parsers don't read it as a result row.
+    fn print(&self, strategy_name: &str) {
+        println!();
+        println!(
+            "Summary ({strategy_name}): {} timed, {} skipped, {} n/a",
+            self.timed.len(),
+            self.skipped,
+            self.not_applicable
+        );
+        if self.over_budget > 0 {
+            println!(
+                "  not started:       {} (--time-budget exceeded)",
+                self.over_budget
+            );
+        }
+        if !self.low_reproducibility.is_empty() {
+            println!(
+                "  low reproducibility: {} under {REPRO_FLAG_BELOW} ({})",
+                self.low_reproducibility.len(),
+                self.low_reproducibility.join(", ")
+            );
+        }
+        if self.timed.is_empty() {
+            return;
+        }
+        let total_ms: f64 = self.timed.iter().map(|t| t.median_ms).sum();
+        let total_flops: f64 = self.timed.iter().map(|t| 10f64.powf(t.log10_flops)).sum();
+        let geomean_ms = (self.timed.iter().map(|t| t.median_ms.ln()).sum::<f64>()
+            / self.timed.len() as f64)
+            .exp();
+        let weighted = self.timed.iter().any(|t| t.weight != 1.0);
+        println!("  total median time: {total_ms:.3} ms");
+        println!("  total FLOPs:       10^{:.2}", total_flops.log10());
+        println!(
+            "  achieved:          {:.2} GFLOP/s",
+            total_flops / (total_ms * 1e-3) / 1e9
+        );
+        println!("  geometric mean:    {geomean_ms:.3} ms");
+        println!(
+            "  GFLOP/s geomean:   {:.2}{}",
+            self.gflops_geomean(),
+            if weighted { " (weighted)" } else { "" }
+        );
+        if let Some((best, worst)) = self.efficiency_range() {
+            println!(
+                "  efficiency range:  best {} at {:.2} GFLOP/s, worst {} at {:.2} GFLOP/s ({:.1}x)",
+                best.name,
+                best.gflops(),
+                worst.name,
+                worst.gflops(),
+                best.gflops() / worst.gflops()
+            );
+        }
+    }
+
+    /// The timed instances with the highest and lowest GFLOP/s, when there
+    /// are at least two.
+    fn efficiency_range(&self) -> Option<(&Timed, &Timed)> {
+        if self.timed.len() < 2 {
+            return None;
+        }
+        let by_gflops = |a: &&Timed, b: &&Timed| a.gflops().total_cmp(&b.gflops());
+        Some((
+            self.timed.iter().max_by(by_gflops)?,
+            self.timed.iter().min_by(by_gflops)?,
+        ))
+    }
+
+    /// Geometric mean of the per-instance GFLOP/s, each weighted by its
+    /// instance's `weight`.
+    fn gflops_geomean(&self) -> f64 {
+        let (sum, total_weight) = self.timed.iter().fold((0.0, 0.0), |(sum, total), t| {
+            (sum + t.weight * t.gflops().ln(), total + t.weight)
+        });
+        (sum / total_weight).exp()
+    }
+}
+
+/// Relative difference of two medians under which neither strategy wins.
+const STRATEGY_TIE: f64 = 0.02;
+
+/// Which strategy was faster, over the instances timed with both.
+#[derive(Debug, PartialEq)]
+struct StrategyWins {
+    /// Instances where opt_flops, opt_size, or neither was faster.
+    flops: usize,
+    size: usize,
+    tied: usize,
+    /// Median over those instances of opt_size time / opt_flops time.
+    median_ratio: f64,
+    /// Median physical time / opt_flops time, and over how many instances,
+    /// when some instance was timed with both.
+    physical: Option<(f64, usize)>,
+}
+
+/// Median of a non-empty list.
+fn median_of(mut values: Vec<f64>) -> f64 {
+    values.sort_by(|a, b| a.total_cmp(b));
+    let n = values.len();
+    if n % 2 == 1 {
+        values[n / 2]
+    } else {
+        (values[n / 2 - 1] + values[n / 2]) / 2.0
+    }
+}
+
+impl StrategyWins {
+    /// Pair each instance's medians (in [`STRATEGIES`] order); `None` when
+    /// no instance has both.
+    fn tally(medians: &[[Option<f64>; STRATEGIES.len()]]) -> Option<Self> {
+        let ratios: Vec<f64> = medians
+            .iter()
+            .filter_map(|&[flops, size, _]| Some(size? / flops?))
+            .collect();
+        if ratios.is_empty() {
+            return None;
+        }
+        let faster = |r: &&f64| (**r - 1.0).abs() > STRATEGY_TIE;
+        let flops = ratios.iter().filter(faster).filter(|r| **r > 1.0).count();
+        let size = ratios.iter().filter(faster).filter(|r| **r < 1.0).count();
+        let tied = ratios.len() - flops - size;
+        let physical: Vec<f64> = medians
+            .iter()
+            .filter_map(|&[flops, _, physical]| Some(physical? / flops?))
+            .collect();
+        Some(StrategyWins {
+            flops,
+            size,
+            tied,
+            median_ratio: median_of(ratios),
+            physical: (!physical.is_empty()).then(|| {
+                let n = physical.len();
+                (median_of(physical), n)
+            }),
+        })
+    }
+
+    /// Printed after both strategy tables, in the same no-trailing-numbers
+    /// style as [`Summary::print`].
+    fn print(&self) {
+        let n = self.flops + self.size + self.tied;
+        println!();
+        println!(
+            "Strategies: opt_flops faster on {}/{n} instances, opt_size faster on {}/{n}, \
+             tied on {}/{n} (within {}%)",
+            self.flops,
+            self.size,
+            self.tied,
+            STRATEGY_TIE * 100.0
+        );
+        println!(
+            "  median opt_size/opt_flops time: {:.3}x",
+            self.median_ratio
+        );
+        if let Some((ratio, n)) = self.physical {
+            println!("  median physical/opt_flops time: {ratio:.3}x (over {n} instances with a physical path)");
+        }
+    }
+}
+
+/// `--warmup-shared`: every strategy's warmup runs on every instance it will
+/// time, in suite order, before the first timed run. The output buffers are
+/// kept for the timing pass.
+fn warm_up_all(
+    opts: &Options,
+    instances: &[BenchmarkInstance],
+    strategies: &[PathStrategy],
+    outputs: &mut [Option<OutputBuffer>],
+) {
+    let started = Instant::now();
+    let mut warmed = 0;
+    for &(strategy_name, get_path) in strategies {
+        let opts = &opts.for_strategy(strategy_name);
+        for (instance, output) in instances.iter().zip(outputs.iter_mut()) {
+            let path_meta = get_path(&instance.paths);
+            if instance.skips(strategy_name) || skip_reason(opts, instance, path_meta).is_some() {
+                continue;
+            }
+            if (opts.reuse_output || instance.scaling().is_some()) && output.is_none() {
+                *output = OutputBuffer::new(instance).ok();
+            }
+            // Failures are reported by the timing pass, which hits them again.
+            if warm_up(instance, path_meta, opts, output.as_mut()).is_ok() {
+                warmed += 1;
+            }
+        }
+    }
+    info!(
+        "--warmup-shared: warmed {warmed} instance runs in {:.1} s before timing",
+        started.elapsed().as_secs_f64()
+    );
+}
+
+/// `--rerun-regressions`: quick single-run pass against the baseline, then a
+/// full-precision rerun of the instances that look slower (see
+/// [`regress`]). Exits with status 1 when a regression is confirmed.
+fn run_rerun_regressions(opts: &Options, instances: &[BenchmarkInstance], baseline: &Path) {
+    let baseline = regress::read_baseline(baseline).unwrap_or_else(|e| {
+        error!("--rerun-regressions: {e}");
+        std::process::exit(2);
+    });
+    let threshold = opts.regression_threshold;
+    println!(
+        "Regressions: slower than baseline by more than {:.1}%",
+        threshold * 100.0
+    );
+
+    // Both phases time the main median as the table does (`--layout`,
+    // alpha/beta scaling and pure transposes included) with exactly the
+    // given counts: no adaptive warmup or `--min-mode`, and none of the
+    // extra timings or checks. `--layout both` times the column-major side.
+    let layout = match opts.layout {
+        Layout::Row => Layout::Row,
+        Layout::Col | Layout::Both => Layout::Col,
+    };
+    let median_ms = |instance: &BenchmarkInstance, path_meta: &PathMeta, (warmup, runs)| {
+        let opts = Options {
+            warmup,
+            runs,
+            layout,
+            ..Options::default()
+        };
+        let mut output = instance
+            .scaling()
+            .and_then(|_| OutputBuffer::new(instance).ok());
+        run_instance(instance, path_meta, &opts, output.as_mut()).map(|r| r.median_ms)
+    };
+
+    // Phase 1: one warmup and one timed run per baseline entry.
+    let mut candidates = Vec::new();
+    let mut checked = 0;
+    for &(strategy_name, get_path) in STRATEGIES {
+        for instance in instances {
+            let key = (instance.name.clone(), strategy_name.to_string());
+            let Some(&base_ms) = baseline.get(&key) else {
+                continue;
+            };
+            if instance.skips(strategy_name) {
+                continue;
+            }
+            let path_meta = get_path(&instance.paths);
+            match median_ms(instance, path_meta, (1, 1)) {
+                Ok(quick_ms) => {
+                    checked += 1;
+                    if regress::is_regression(base_ms, quick_ms, threshold) {
+                        candidates.push((instance, strategy_name, path_meta, base_ms, quick_ms));
+                    }
+                }
+                Err(e) => warn!(instance = %instance.name, strategy_name, "backend error: {e}"),
+            }
+        }
+    }
+    println!(
+        "Quick pass: {checked} instance/strategy pairs checked, {} over threshold",
+        candidates.len()
+    );
+
+    // Phase 2: full timing of the candidates only.
+    println!();
+    println!(
+        "{:<50} {:>10} {:>13} {:>10} {:>10} {:>8} {:>10}",
+        "Instance", "Strategy", "Baseline (ms)", "Quick (ms)", "Full (ms)", "Change", "Verdict"
+    );
+    println!("{}", "-".repeat(117));
+    let mut confirmed = 0;
+    for (instance, strategy_name, path_meta, base_ms, quick_ms) in candidates {
+        let full_ms = match median_ms(instance, path_meta, opts.counts(strategy_name)) {
+            Ok(ms) => ms,
+            Err(e) => {
+                warn!(instance = %instance.name, strategy_name, "backend error: {e}");
+                continue;
+            }
+        };
+        let regressed = regress::is_regression(base_ms, full_ms, threshold);
+        confirmed += regressed as usize;
+        println!(
+            "{:<50} {strategy_name:>10} {base_ms:>13.3} {quick_ms:>10.3} {full_ms:>10.3} {:>+7.1}% {:>10}",
+            instance.name,
+            (full_ms - base_ms) / base_ms * 100.0,
+            if regressed { "confirmed" } else { "noise" }
+        );
+    }
+    println!("({confirmed} confirmed)");
+    if confirmed > 0 {
+        std::process::exit(1);
+    }
+}
+
+/// `--prefix-sweep`: cumulative time of each path prefix of one instance,
+/// per strategy, with the increment each step adds.
+fn run_prefix_sweep(opts: &Options, instances: &[BenchmarkInstance]) {
+    let [instance] = instances else {
+        error!(
+            "--prefix-sweep runs a single instance ({} loaded); select one with BENCH_INSTANCE",
+            instances.len()
+        );
+        std::process::exit(2);
+    };
+    for &(strategy_name, get_path) in STRATEGIES {
+        if instance.skips(strategy_name) {
+            continue;
+        }
+        println!();
+        println!("Prefix sweep: {} / {strategy_name}", instance.name);
+        println!(
+            "{:>6} {:>13} {:>13} {:>10}",
+            "Steps", "Cumul. (ms)", "Step (ms)", "Largest"
+        );
+        println!("{}", "-".repeat(45));
+        let (warmup, runs) = opts.counts(strategy_name);
+        let sweep = match prefix::prefix_sweep(instance, get_path(&instance.paths), warmup, runs) {
+            Ok(sweep) => sweep,
+            Err(e) => {
+                warn!(instance = %instance.name, "backend error: {e}");
+                continue;
+            }
+        };
+        let mut prev = 0.0;
+        for p in sweep {
+            let ms = p.median.as_secs_f64() * 1e3;
+            // `Largest` as a power of two keeps the row from ending in two
+            // plain numbers, which log parsers take for a result row.
+            println!(
+                "{:>6} {ms:>13.3} {:>+13.3} {:>10}",
+                p.steps,
+                ms - prev,
+                format!("2^{:.1}", (p.largest.max(1) as f64).log2())
+            );
+            prev = ms;
+        }
+    }
+}
+
+/// `--isolate-step`: time one step of each strategy's path on its own and
+/// split it into operand copies and GEMM (see [`isolate`]).
+fn run_isolate_step(opts: &Options, instances: &[BenchmarkInstance], name: &str, step: usize) {
+    let Some(instance) = instances.iter().find(|i| i.name == name) else {
+        error!("--isolate-step: no loaded instance named {name:?}");
+        std::process::exit(2);
+    };
+    for &(strategy_name, get_path) in STRATEGIES {
+        if instance.skips(strategy_name) {
+            continue;
+        }
+        let path_meta = get_path(&instance.paths);
+        let (warmup, runs) = opts.counts(strategy_name);
+        let isolated = match isolate::isolate_step(instance, path_meta, step, warmup, runs) {
+            Ok(isolated) => isolated,
+            Err(e) => {
+                warn!(instance = %instance.name, strategy = strategy_name, "isolate step: {e}");
+                continue;
+            }
+        };
+        let s = &isolated.step;
+        let labels = |ids: &[char]| ids.iter().collect::<String>();
+        println!();
+        println!(
+            "Isolated step {step}/{}: {} / {strategy_name} ({}{})",
+            path_meta.path.len(),
+            instance.name,
+            s.kind.name(),
+            if s.batched { ", batched" } else { "" }
+        );
+        println!("  left:   {:?} {}", isolated.left_shape, labels(&s.left));
+        println!("  right:  {:?} {}", isolated.right_shape, labels(&s.right));
+        println!(
+            "  output: 2^{:.1} elements {}",
+            s.size.log2(),
+            labels(&s.output)
+        );
+        println!("{:<16} {:>12} {:>7}", "Part", "Median (ms)", "Share");
+        println!("{}", "-".repeat(37));
+        let full = isolated.full.as_secs_f64();
+        // The share column keeps each row's last token non-numeric for log
+        // parsers.
+        let row = |part: &str, d: Option<Duration>| match d {
+            Some(d) => println!(
+                "{part:<16} {:>12.3} {:>6.0}%",
+                d.as_secs_f64() * 1e3,
+                d.as_secs_f64() / full * 100.0
+            ),
+            None => println!("{part:<16} {:>12} {:>7}", "-", "-"),
+        };
+        row("full step", Some(isolated.full));
+        row("  copy left", Some(isolated.copy_left));
+        row("  copy right", Some(isolated.copy_right));
+        row("  GEMM only (~)", isolated.gemm);
+    }
+}
+
+/// `--multi-output`: time each multi-output instance's shared DAG against
+/// recomputing every output separately, per strategy.
+fn run_multi_output(opts: &Options, instances: &[BenchmarkInstance]) {
+    println!();
+    println!("Multi-output: shared intermediates vs per-output recompute");
+    println!(
+        "{:<50} {:>10} {:>8} {:>11} {:>11} {:>8}",
+        "Instance", "Strategy", "Outputs", "Shared ms", "Naive ms", "Speedup"
+    );
+    println!("{}", "-".repeat(103));
+    let mut compared = 0;
+    for instance in instances {
+        let Some(outputs) = &instance.outputs_colmajor else {
+            continue;
+        };
+        for &(strategy_name, get_path) in STRATEGIES {
+            if instance.skips(strategy_name) {
+                continue;
+            }
+            let path_meta = get_path(&instance.paths);
+            let (warmup, runs) = opts.counts(strategy_name);
+            match multi::compare(instance, path_meta, outputs, warmup, runs) {
+                Ok(t) => {
+                    let (shared, naive) = (t.shared.as_secs_f64(), t.naive.as_secs_f64());
+                    println!(
+                        "{:<50} {strategy_name:>10} {:>8} {:>11.3} {:>11.3} {:>8}",
+                        instance.name,
+                        outputs.len(),
+                        shared * 1e3,
+                        naive * 1e3,
+                        format!("{:.2}x", naive / shared)
+                    );
+                    compared += 1;
+                }
+                Err(e) => warn!(instance = %instance.name, strategy_name, "backend error: {e}"),
+            }
+        }
+    }
+    if compared == 0 {
+        println!("(no instance has outputs_colmajor)");
+    }
+}
+
+/// `--dump-intermediates`: write the intermediate of every path step, per
+/// instance and strategy, with its labels and resolved shape. Nothing is
+/// evaluated.
+fn dump_intermediates(instances: &[BenchmarkInstance], out: &Path) -> Result<usize, String> {
+    let mut entries = Vec::new();
+    for instance in instances {
+        let (input_indices, output_indices) = parse_format_string(&instance.format_string_colmajor);
+        let dims = path::dim_map(&input_indices, &instance.shapes_colmajor)
+            .map_err(|e| format!("{}: {e}", instance.name))?;
+        for &(strategy_name, get_path) in STRATEGIES {
+            if instance.skips(strategy_name) {
+                continue;
+            }
+            let steps = path::simulate(
+                &input_indices,
+                &output_indices,
+                &dims,
+                &get_path(&instance.paths).path,
+            )
+            .map_err(|e| format!("{} ({strategy_name}): {e}", instance.name))?;
+            let intermediates: Vec<serde_json::Value> = steps
+                .iter()
+                .map(|step| {
+                    let shape: Vec<usize> = step.output.iter().map(|id| dims[id]).collect();
+                    serde_json::json!({
+                        "labels": step.output.iter().collect::<String>(),
+                        "shape": shape,
+                        "size": shape.iter().product::<usize>(),
+                    })
+                })
+                .collect();
+            entries.push(serde_json::json!({
+                "instance": instance.name,
+                "strategy": strategy_name,
+                "intermediates": intermediates,
+            }));
+        }
+    }
+    let count = entries.len();
+    let json = serde_json::to_string_pretty(&serde_json::json!({ "results": entries }))
+        .map_err(|e| e.to_string())?;
+    std::fs::write(out, json + "\n").map_err(|e| format!("write {}: {e}", out.display()))?;
+    Ok(count)
+}
+
+/// `--dump-operands`: write the single selected instance's seeded operands
+/// (`fill::DEFAULT_SEED`, as `--verify-checksum` and `--cross-check` use)
+/// to `operand_<t>.npy`, its column-major format string to `format.txt`,
+/// and the result along the first strategy it runs to `result.npy`.
+/// Returns the operand count.
+fn dump_operands(instances: &[BenchmarkInstance], dir: &Path) -> Result<usize, String> {
+    let [instance] = instances else {
+        return Err(format!(
+            "runs a single instance ({} loaded); select one with BENCH_INSTANCE",
+            instances.len()
+        ));
+    };
+    std::fs::create_dir_all(dir).map_err(|e| format!("create {}: {e}", dir.display()))?;
+    let operands = fill::random_operands(
+        &instance.shapes_colmajor,
+        &instance.dtype,
+        instance.hermitian.as_deref().unwrap_or(&[]),
+        fill::DEFAULT_SEED,
+        |x| x,
+    );
+    for (t, op) in operands.iter().enumerate() {
+        npy::write_operand(&dir.join(format!("operand_{t}.npy")), op)?;
+    }
+    let format_file = dir.join("format.txt");
+    std::fs::write(
+        &format_file,
+        format!("{}\n", instance.format_string_colmajor),
+    )
+    .map_err(|e| format!("write {}: {e}", format_file.display()))?;
+    let count = operands.len();
+    if let Some(&(_, get_path)) = STRATEGIES.iter().find(|(name, _)| !instance.skips(name)) {
+        let result = plan(instance, get_path(&instance.paths))
+            .evaluate(operands, None)
+            .map_err(|e| format!("{}: {e}", instance.name))?;
+        npy::write_operand(&dir.join("result.npy"), &result)?;
+    }
+    Ok(count)
+}
+
+/// `--step-stats`: classify every step of every path by [`path::StepKind`]
+/// and print per-strategy counts and FLOP shares over the loaded instances.
+/// Nothing is evaluated. Percent cells keep the rows out of log parsers.
+fn report_step_stats(instances: &[BenchmarkInstance]) {
+    for &(strategy_name, get_path) in STRATEGIES {
+        let mut by_kind: BTreeMap<(bool, path::StepKind), (usize, f64)> = BTreeMap::new();
+        let mut analyzed = 0;
+        for instance in instances.iter().filter(|i| !i.skips(strategy_name)) {
+            let (input_indices, output_indices) =
+                parse_format_string(&instance.format_string_colmajor);
+            let steps = path::dim_map(&input_indices, &instance.shapes_colmajor).and_then(|dims| {
+                path::simulate(
+                    &input_indices,
+                    &output_indices,
+                    &dims,
+                    &get_path(&instance.paths).path,
+                )
+            });
+            match steps {
+                Ok(steps) => {
+                    analyzed += 1;
+                    for step in steps {
+                        let entry = by_kind.entry((step.batched, step.kind)).or_default();
+                        entry.0 += 1;
+                        entry.1 += step.flops;
+                    }
+                }
+                Err(e) => warn!(instance = %instance.name, "--step-stats: {e}"),
+            }
+        }
+        let steps: usize = by_kind.values().map(|&(n, _)| n).sum();
+        let flops: f64 = by_kind.values().map(|&(_, f)| f).sum();
+        println!();
+        println!("Step stats ({strategy_name}): {steps} steps in {analyzed} instances");
+        println!(
+            "{:<20} {:>8} {:>9} {:>9}",
+            "Kind", "Steps", "Steps %", "FLOPs %"
+        );
+        println!("{}", "-".repeat(49));
+        for (&(batched, kind), &(n, f)) in &by_kind {
+            let name = if batched {
+                format!("batched {}", kind.name())
+            } else {
+                kind.name().to_string()
+            };
+            println!(
+                "{name:<20} {n:>8} {:>8.1}% {:>8.1}%",
+                100.0 * n as f64 / steps as f64,
+                100.0 * f / flops
+            );
+        }
+    }
+}
+
+/// Compare measured peak memory of `opt_size` against `opt_flops`, flagging
+/// instances where the size-optimized path used more (a cost-model miss).
+/// `peaks[i]` holds one entry per strategy, in suite order.
+fn report_memory_by_strategy(instances: &[BenchmarkInstance], peaks: &[Vec<Option<usize>>]) {
+    println!();
+    println!("Peak memory: opt_size vs opt_flops");
+    println!(
+        "{:<50} {:>13} {:>13} {:>8}",
+        "Instance", "opt_flops MB", "opt_size MB", "Ratio"
+    );
+    println!("{}", "-".repeat(87));
+    let (mut compared, mut worse) = (0, 0);
+    for (instance, p) in instances.iter().zip(peaks) {
+        let (Some(Some(flops)), Some(Some(size))) = (p.first(), p.get(1)) else {
+            continue;
+        };
+        compared += 1;
+        let ratio = *size as f64 / (*flops).max(1) as f64;
+        let flag = if size > flops {
+            worse += 1;
+            "  <- opt_size uses more"
+        } else {
+            ""
+        };
+        println!(
+            "{:<50} {:>13.3} {:>13.3} {:>7.2}x{flag}",
+            instance.name,
+            *flops as f64 / 1e6,
+            *size as f64 / 1e6,
+            ratio
+        );
+    }
+    println!("(opt_size used more peak memory on {worse} of {compared} instances)");
+}
+
+/// Header cells for the optional columns enabled by flags.
+fn extra_header(opts: &Options) -> String {
+    let mut s = String::new();
+    if opts.tree_stats {
+        s += &format!(" {:>5} {:>5} {:>7}", "Depth", "Width", "Balance");
+    }
+    if opts.fingerprint {
+        s += &format!(" {:>16}", "Fingerprint");
+    }
+    if opts.flops_per_element {
+        s += &format!(" {:>10}", "FLOP/elem");
+    }
+    if opts.kernel_path {
+        s += &format!(" {:>9}", "GEMM");
+    }
+    if opts.throughput {
+        s += &format!(" {:>10}", "Min MB");
+    }
+    s
+}
+
+/// Header cells for optional columns derived from the timing result.
+fn result_header(opts: &Options) -> String {
+    let mut s = String::new();
+    if opts.adaptive_warmup {
+        s += &format!(" {:>7}", "Warmup");
+    }
+    if opts.min_mode {
+        s += &format!(" {:>10} {:>6}", "Min (ms)", "Runs");
+    }
+    if opts.first_result {
+        s += &format!(" {:>10}", "First (ms)");
+    }
+    if opts.profile_planning {
+        s += &format!(" {:>10} {:>7} {:>10}", "Plan (us)", "Plan %", "Tree (us)");
+    }
+    if opts.mixed_precision {
+        s += &format!(" {:>10}", "f32 err");
+    }
+    if opts.profile_copy {
+        s += &format!(" {:>7}", "Copy %");
+    }
+    if opts.cross_check {
+        s += &format!(" {:>10}", "NumPy err");
+    }
+    if opts.verify_checksum {
+        s += &format!(" {:>8}", "Checksum");
+    }
+    if opts.check_determinism {
+        s += &format!(" {:>10}", "Rerun diff");
+    }
+    if opts.report_sparsity {
+        s += &format!(" {:>7}", "Zero %");
+    }
+    if opts.cse {
+        s += &format!(" {:>7}", "Shared");
+    }
+    if opts.track_memory {
+        s += &format!(" {:>10}", "Peak MB");
+    }
+    if opts.reuse_output {
+        s += &format!(" {:>11} {:>8}", "Reuse (ms)", "Delta");
+    }
+    if opts.layout == Layout::Both {
+        s += &format!(" {:>11} {:>8}", "Row (ms)", "Row/Col");
+    }
+    if opts.pool {
+        s += &format!(" {:>11} {:>8}", "Pool (ms)", "Saved");
+    }
+    if opts.hugepages {
+        s += &format!(" {:>11} {:>8}", "Huge (ms)", "Delta");
+    }
+    if opts.include_load {
+        s += &format!(" {:>11} {:>7}", "E2E (ms)", "Load %");
+    }
+    if opts.throughput {
+        s += &format!(" {:>9} {:>8}", "FLOP/s", "GB/s");
+    }
+    if opts.peak_bandwidth.is_some() {
+        s += &format!(" {:>7}", "% peak");
+    }
+    if opts.gflops_spread {
+        s += &format!(" {:>10} {:>10} {:>7}", "FLOP/s min", "FLOP/s max", "Spread");
+    }
+    if opts.reproducibility {
+        s += &format!(" {:>6}", "Repro");
+    }
+    s
+}
+
+/// Row cells matching [`result_header`]; `None` for skipped instances.
+fn result_cells(opts: &Options, result: Option<&BenchResult>) -> String {
+    let mut s = String::new();
+    if opts.adaptive_warmup {
+        match result {
+            Some(r) => s += &format!(" {:>7}", r.warmup_runs),
+            None => s += &format!(" {:>7}", "-"),
+        }
+    }
+    if opts.min_mode {
+        // A trailing `!` marks a minimum cut off by the budget, not stable.
+        match result.and_then(|r| r.min_runs) {
+            Some((min_ms, runs, stable)) => {
+                let runs = format!("{runs}{}", if stable { "" } else { "!" });
+                s += &format!(" {min_ms:>10.3} {runs:>6}");
+            }
+            None => s += &format!(" {:>10} {:>6}", "-", "-"),
+        }
+    }
+    if opts.first_result {
+        // `evaluate` hands back the whole output at once, so its first
+        // element arrives with the last.
+        match result {
+            Some(r) => s += &format!(" {:>10.3}", r.median_ms),
+            None => s += &format!(" {:>10}", "-"),
+        }
+    }
+    if opts.profile_planning {
+        match result.and_then(|r| Some((r.plan_us?, r.median_ms))) {
+            Some(((plan_us, tree_us), median_ms)) => {
+                let pct = plan_us / (median_ms * 1e3) * 100.0;
+                s += &format!(" {plan_us:>10.1} {pct:>6.2}% {tree_us:>10.1}");
+            }
+            None => s += &format!(" {:>10} {:>7} {:>10}", "-", "-", "-"),
+        }
+    }
+    if opts.mixed_precision {
+        match result.and_then(|r| r.mixed_err) {
+            Some(err) => s += &format!(" {err:>10.2e}"),
+            None => s += &format!(" {:>10}", "-"),
+        }
+    }
+    if opts.profile_copy {
+        match result.and_then(|r| r.copy_tax) {
+            Some(tax) => s += &format!(" {:>6.1}%", tax * 100.0),
+            None => s += &format!(" {:>7}", "-"),
+        }
+    }
+    if opts.cross_check {
+        match result.and_then(|r| r.numpy_err) {
+            Some(err) => s += &format!(" {err:>10.2e}"),
+            None => s += &format!(" {:>10}", "-"),
+        }
+    }
+    if opts.verify_checksum {
+        match result.and_then(|r| r.checksum.as_ref()) {
+            Some(c) => s += &format!(" {:>8}", if c.ok { "ok" } else { "MISMATCH" }),
+            None => s += &format!(" {:>8}", "-"),
+        }
+    }
+    if opts.check_determinism {
+        match result.and_then(|r| r.rerun_diff) {
+            Some(None) => s += &format!(" {:>10}", "identical"),
+            Some(Some((_, diff))) => s += &format!(" {diff:>10.2e}"),
+            None => s += &format!(" {:>10}", "-"),
+        }
+    }
+    if opts.report_sparsity {
+        match result.and_then(|r| r.sparsity) {
+            Some(share) => s += &format!(" {:>6.1}%", share * 100.0),
+            None => s += &format!(" {:>7}", "-"),
+        }
+    }
+    if opts.cse {
+        match result.and_then(|r| r.cse_shared) {
+            Some(n) => s += &format!(" {n:>7}"),
+            None => s += &format!(" {:>7}", "-"),
+        }
+    }
+    if opts.track_memory {
+        match result.and_then(|r| r.peak_bytes) {
+            Some(b) => s += &format!(" {:>10.3}", b as f64 / 1e6),
+            None => s += &format!(" {:>10}", "-"),
+        }
+    }
+    if opts.reuse_output {
+        match result.and_then(|r| Some((r.reuse_ms?, r.median_ms))) {
+            Some((reuse_ms, median_ms)) => {
+                let delta = (reuse_ms - median_ms) / median_ms * 100.0;
+                s += &format!(" {reuse_ms:>11.3} {delta:>+7.1}%");
+            }
+            None => s += &format!(" {:>11} {:>8}", "-", "-"),
+        }
+    }
+    if opts.layout == Layout::Both {
+        match result.and_then(|r| Some((r.row_ms?, r.median_ms))) {
+            Some((row_ms, median_ms)) => {
+                s += &format!(
+                    " {row_ms:>11.3} {:>8}",
+                    format!("{:.2}x", row_ms / median_ms)
+                )
+            }
+            None => s += &format!(" {:>11} {:>8}", "-", "-"),
+        }
+    }
+    if opts.pool {
+        match result.and_then(|r| Some((r.pool_ms?, r.median_ms))) {
+            Some((pool_ms, median_ms)) => {
+                let saved = (median_ms - pool_ms) / median_ms * 100.0;
+                s += &format!(" {pool_ms:>11.3} {saved:>+7.1}%");
+            }
+            None => s += &format!(" {:>11} {:>8}", "-", "-"),
+        }
+    }
+    if opts.hugepages {
+        match result.and_then(|r| Some((r.huge_ms?, r.median_ms))) {
+            Some((huge_ms, median_ms)) => {
+                let delta = (huge_ms - median_ms) / median_ms * 100.0;
+                s += &format!(" {huge_ms:>11.3} {delta:>+7.1}%");
+            }
+            None => s += &format!(" {:>11} {:>8}", "-", "-"),
+        }
+    }
+    if opts.include_load {
+        match result.and_then(|r| r.load_ms) {
+            Some((e2e_ms, share)) => {
+                s += &format!(" {e2e_ms:>11.3} {:>7}", format!("{:.1}%", 100.0 * share))
+            }
+            None => s += &format!(" {:>11} {:>7}", "-", "-"),
+        }
+    }
+    if opts.throughput {
+        match result.and_then(|r| r.throughput) {
+            Some((flops, gbps)) => {
+                s += &format!(" {:>9} {gbps:>8.2}", fmt_flops(flops, opts.flops_unit))
+            }
+            None => s += &format!(" {:>9} {:>8}", "-", "-"),
+        }
+    }
+    if let Some(PeakBandwidth::Given(peak)) = opts.peak_bandwidth {
+        match result.and_then(|r| r.throughput) {
+            Some((_, gbps)) if gbps.is_finite() => {
+                s += &format!(" {:>7}", format!("{:.1}%", 100.0 * gbps / peak))
+            }
+            _ => s += &format!(" {:>7}", "-"),
+        }
+    }
+    if opts.gflops_spread {
+        match result.and_then(|r| r.gflops_spread) {
+            Some((lo, median, hi)) => {
+                let spread = format!("{:.1}%", 100.0 * (hi - lo) / median);
+                let (lo, hi) = (
+                    fmt_flops(lo, opts.flops_unit),
+                    fmt_flops(hi, opts.flops_unit),
+                );
+                s += &format!(" {lo:>10} {hi:>10} {spread:>7}");
+            }
+            None => s += &format!(" {:>10} {:>10} {:>7}", "-", "-", "-"),
+        }
+    }
+    if opts.reproducibility {
+        match result {
+            Some(r) => {
+                let score = reproducibility(r.median_ms, r.iqr_ms());
+                let flag = if score < REPRO_FLAG_BELOW { "!" } else { "" };
+                s += &format!(" {:>6}", format!("{score:.2}{flag}"));
+            }
+            None => s += &format!(" {:>6}", "-"),
+        }
+    }
+    s
+}
+
+/// A FLOP/s cell: the value in `unit` with its prefix letter, e.g. `45.6G`.
+/// `Auto` picks the largest of M, G, T that keeps the value at or above 1
+/// and shows three significant digits; a fixed unit shows two decimals.
+fn fmt_flops(flops_per_s: f64, unit: FlopsUnit) -> String {
+    let (scale, letter) = match unit {
+        FlopsUnit::Mega => (1e6, 'M'),
+        FlopsUnit::Giga => (1e9, 'G'),
+        FlopsUnit::Tera => (1e12, 'T'),
+        FlopsUnit::Auto => {
+            let (scale, letter) = if flops_per_s >= 1e12 {
+                (1e12, 'T')
+            } else if flops_per_s >= 1e9 {
+                (1e9, 'G')
+            } else {
+                (1e6, 'M')
+            };
+            let v = flops_per_s / scale;
+            let decimals = if v >= 100.0 {
+                0
+            } else if v >= 10.0 {
+                1
+            } else {
+                2
+            };
+            return format!("{v:.decimals$}{letter}");
+        }
+    };
+    format!("{:.2}{letter}", flops_per_s / scale)
+}
+
+/// A row that wasn't timed, tagged `[SKIPPED: reason]`; in the Julia layout,
+/// the row as it was and the reason on an indented line below it.
+fn skipped_lines(opts: &Options, row: String, reason: &str) -> Vec<String> {
+    if opts.julia_compatible {
+        vec![row, format!("  reason: {reason}")]
+    } else {
+        vec![format!("{row}  [SKIPPED: {reason}]")]
+    }
+}
+
+/// `key=value` pairs on one line, logfmt style (`--format line`): values
+/// that are empty or hold spaces, `=` or quotes are quoted and escaped.
+fn logfmt(fields: &[(&str, String)]) -> String {
+    let pairs: Vec<String> = fields
+        .iter()
+        .map(|(key, value)| {
+            if value.is_empty() || value.contains([' ', '=', '"', '\\']) {
+                format!("{key}={value:?}")
+            } else {
+                format!("{key}={value}")
+            }
+        })
+        .collect();
+    pairs.join(" ")
+}
+
+/// A timed row as a [`logfmt`] line.
+fn result_line(
+    instance: &BenchmarkInstance,
+    strategy: &str,
+    path_meta: &PathMeta,
+    result: &BenchResult,
+) -> String {
+    let gflops = 10f64.powf(path_meta.log10_flops) / (result.median_ms * 1e-3) / 1e9;
+    logfmt(&[
+        ("instance", instance.name.clone()),
+        ("strategy", strategy.to_string()),
+        ("status", "ok".into()),
+        ("tensors", instance.num_tensors.to_string()),
+        ("log10_flops", format!("{:.2}", path_meta.log10_flops)),
+        ("log2_size", format!("{:.2}", path_meta.log2_size)),
+        ("median_ms", format!("{:.3}", result.median_ms)),
+        ("iqr_ms", format!("{:.3}", result.iqr_ms())),
+        ("gflops", format!("{gflops:.2}")),
+    ])
+}
+
+/// A row that wasn't timed, as [`skipped_lines`] prints it, or as a
+/// [`logfmt`] line with `status=skipped` under `--format line`.
+fn untimed_lines(opts: &Options, row: String, skip: &report::Skipped) -> Vec<String> {
+    match opts.format {
+        RowFormat::Table => skipped_lines(opts, row, &skip.reason),
+        RowFormat::Line => {
+            let mut fields = vec![("instance", skip.instance.clone())];
+            fields.extend(skip.strategy.clone().map(|s| ("strategy", s)));
+            fields.push(("status", "skipped".into()));
+            fields.push(("reason", skip.reason.clone()));
+            vec![logfmt(&fields)]
+        }
+    }
+}
+
+/// Instance cell of a result row: the name, tagged `[transpose]` when the
+/// instance was timed as a pure permutation (not in the Julia layout).
+fn row_name(opts: &Options, instance: &BenchmarkInstance, path_meta: &PathMeta) -> String {
+    if opts.julia_compatible {
+        instance.name.clone()
+    } else if instance.pure_transpose().is_some() {
+        format!("{} [transpose]", instance.name)
+    } else if opts.kernel_path
+        && gemm_steps(instance, path_meta).is_some_and(|(gemm, steps)| gemm < steps)
+    {
+        format!("{} [fallback]", instance.name)
+    } else {
+        instance.name.clone()
+    }
+}
+
+/// `(GEMM steps, steps)` along the path (see [`path::PathStep::uses_gemm`]);
+/// `None` without binary steps or when the path doesn't replay.
+fn gemm_steps(instance: &BenchmarkInstance, path_meta: &PathMeta) -> Option<(usize, usize)> {
+    if path_meta.path.is_empty() {
+        return None;
+    }
+    let (input_indices, output_indices) = parse_format_string(&instance.format_string_colmajor);
+    let steps = path::dim_map(&input_indices, &instance.shapes_colmajor)
+        .and_then(|dims| path::simulate(&input_indices, &output_indices, &dims, &path_meta.path))
+        .ok()?;
+    Some((steps.iter().filter(|s| s.uses_gemm()).count(), steps.len()))
+}
+
+/// Row cells matching [`extra_header`].
+fn extra_cells(opts: &Options, instance: &BenchmarkInstance, path_meta: &PathMeta) -> String {
+    let mut s = String::new();
+    if opts.tree_stats {
+        let root = plan(instance, path_meta).root;
+        let balance = tree::balance(&root).map_or("-".into(), |b| format!("{b:.2}"));
+        s += &format!(
+            " {:>5} {:>5} {balance:>7}",
+            tree::depth(&root),
+            tree::width(&root)
+        );
+    }
+    if opts.fingerprint {
+        let (input_indices, output_indices) = parse_format_string(&instance.format_string_colmajor);
+        let fp = fingerprint::fingerprint(&input_indices, &output_indices);
+        s += &format!(" {fp:>016x}");
+    }
+    if opts.flops_per_element {
+        match flops_per_output_element(instance, path_meta) {
+            Some(ratio) => s += &format!(" {ratio:>10.3e}"),
+            None => s += &format!(" {:>10}", "-"),
+        }
+    }
+    if opts.kernel_path {
+        match gemm_steps(instance, path_meta) {
+            Some((gemm, steps)) => s += &format!(" {:>9}", format!("{gemm}/{steps}")),
+            None => s += &format!(" {:>9}", "-"),
+        }
+    }
+    if opts.throughput {
+        match min_traffic_bytes(instance) {
+            Some(bytes) => s += &format!(" {:>10.2}", bytes / 1e6),
+            None => s += &format!(" {:>10}", "-"),
+        }
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strided_opteinsum::EinsumNode;
+
+    fn leaf_indices(node: &EinsumNode, out: &mut Vec<usize>) {
+        match node {
+            EinsumNode::Leaf { tensor_index, .. } => out.push(*tensor_index),
+            EinsumNode::Contract { args } => args.iter().for_each(|a| leaf_indices(a, out)),
+        }
+    }
+
+    #[test]
+    fn strategy_counts_fall_back_to_global() {
+        let parse = |args: &[&str]| Options::parse(args.iter().map(|a| a.to_string()));
+        let opts = parse(&["--runs", "opt_flops=10,opt_size=3", "--warmup", "1"]).unwrap();
+        assert_eq!(opts.counts("opt_flops"), (1, 10));
+        assert_eq!(opts.counts("opt_size"), (1, 3));
+        let opts = parse(&["--runs", "7,opt_size=2"]).unwrap();
+        assert_eq!(opts.counts("opt_flops"), (NUM_WARMUP, 7));
+        assert_eq!(opts.for_strategy("opt_size").runs, 2);
+        assert!(parse(&["--runs", "opt_speed=3"]).is_err());
+        assert!(parse(&["--runs", "opt_size=0"]).is_err());
+        assert!(parse(&["--runs", "opt_size=2,opt_size=3"]).is_err());
+        assert!(parse(&["--runs", "5", "--once"]).is_err());
+    }
+
+    #[test]
+    fn governors_are_counted_per_core() {
+        let root = std::env::temp_dir().join(format!("strided-bench-cpu-{}", std::process::id()));
+        for (cpu, governor) in [
+            ("cpu0", Some("powersave")),
+            ("cpu1", Some("performance")),
+            ("cpu2", Some("powersave\n")),
+            ("cpu3", None),
+            ("cpufreq", Some("performance")),
+        ] {
+            let dir = root.join(cpu).join("cpufreq");
+            std::fs::create_dir_all(&dir).unwrap();
+            if let Some(g) = governor {
+                std::fs::write(dir.join("scaling_governor"), g).unwrap();
+            }
+        }
+        let governors = cpu_governors(&root);
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(
+            governors,
+            vec![("powersave".to_string(), 2), ("performance".to_string(), 1)]
+        );
+        assert!(cpu_governors(&root).is_empty());
+    }
+
+    #[test]
+    fn flops_cells_scale_to_three_digits() {
+        assert_eq!(fmt_flops(4.56e10, FlopsUnit::Auto), "45.6G");
+        assert_eq!(fmt_flops(1.234e12, FlopsUnit::Auto), "1.23T");
+        assert_eq!(fmt_flops(8.5e8, FlopsUnit::Auto), "850M");
+        assert_eq!(fmt_flops(1.2e4, FlopsUnit::Auto), "0.01M");
+        assert_eq!(fmt_flops(4.56e10, FlopsUnit::Giga), "45.60G");
+        assert_eq!(fmt_flops(4.56e10, FlopsUnit::Tera), "0.05T");
+    }
+
+    #[test]
+    fn strategy_wins_pair_instances_timed_with_both() {
+        let medians = [
+            [Some(1.0), Some(2.0), Some(3.0)],
+            [Some(2.0), Some(1.0), None],
+            [Some(1.0), Some(1.01), Some(1.5)],
+            [Some(1.0), None, None],
+            [None, None, None],
+        ];
+        let wins = StrategyWins::tally(&medians).unwrap();
+        assert_eq!((wins.flops, wins.size, wins.tied), (1, 1, 1));
+        assert_eq!(wins.median_ratio, 1.01);
+        assert_eq!(wins.physical, Some((2.25, 2)));
+        assert_eq!(StrategyWins::tally(&medians[1..2]).unwrap().physical, None);
+        assert_eq!(StrategyWins::tally(&medians[3..]), None);
+    }
+
+    fn timed(name: &str, median_ms: f64, log10_flops: f64) -> Timed {
+        Timed {
+            name: name.into(),
+            median_ms,
+            log10_flops,
+            weight: 1.0,
+        }
+    }
+
+    #[test]
+    fn result_cells_line_up_with_result_header() {
+        let opts = Options {
+            adaptive_warmup: true,
+            min_mode: true,
+            first_result: true,
+            profile_planning: true,
+            mixed_precision: true,
+            profile_copy: true,
+            cross_check: true,
+            verify_checksum: true,
+            check_determinism: true,
+            report_sparsity: true,
+            cse: true,
+            track_memory: true,
+            reuse_output: true,
+            layout: Layout::Both,
+            pool: true,
+            hugepages: true,
+            include_load: true,
+            throughput: true,
+            peak_bandwidth: Some(PeakBandwidth::Given(6.0)),
+            gflops_spread: true,
+            reproducibility: true,
+            ..Options::default()
+        };
+        let result = BenchResult {
+            median_ms: 10.0,
+            q1_ms: 9.0,
+            q3_ms: 11.0,
+            warmup_runs: 7,
+            min_runs: Some((8.5, 12, false)),
+            plan_us: Some((100.0, 40.0)),
+            mixed_err: Some(1e-7),
+            copy_tax: Some(0.25),
+            numpy_err: Some(2e-15),
+            reuse_ms: Some(9.0),
+            row_ms: Some(20.0),
+            huge_ms: Some(12.0),
+            pool_ms: Some(5.0),
+            load_ms: Some((15.0, 0.2)),
+            throughput: Some((1e9, 3.0)),
+            gflops_spread: Some((5e8, 1e9, 2e9)),
+            checksum: Some(ChecksumCheck {
+                actual: 1.0,
+                expected: 1.0,
+                ok: true,
+            }),
+            rerun_diff: Some(Some((2, 3e-16))),
+            sparsity: Some(0.5),
+            peak_bytes: Some(2_000_000),
+            cse_shared: Some(4),
+            samples_ns: Vec::new(),
+        };
+        let header = result_header(&opts);
+        let cells = result_cells(&opts, Some(&result));
+        assert_eq!(header.len(), cells.len(), "{header}\n{cells}");
+        assert_eq!(result_cells(&opts, None).len(), header.len());
+        // Each cell is right-aligned under its header, every column in order.
+        let fmt = |flops| fmt_flops(flops, opts.flops_unit);
+        let columns = [
+            ("Warmup", "7".to_string()),
+            ("Min (ms)", "8.500".into()),
+            ("Runs", "12!".into()),
+            ("First (ms)", "10.000".into()),
+            ("Plan (us)", "100.0".into()),
+            ("Plan %", "1.00%".into()),
+            ("Tree (us)", "40.0".into()),
+            ("f32 err", "1.00e-7".into()),
+            ("Copy %", "25.0%".into()),
+            ("NumPy err", "2.00e-15".into()),
+            ("Checksum", "ok".into()),
+            ("Rerun diff", "3.00e-16".into()),
+            ("Zero %", "50.0%".into()),
+            ("Shared", "4".into()),
+            ("Peak MB", "2.000".into()),
+            ("Reuse (ms)", "9.000".into()),
+            ("Delta", "-10.0%".into()),
+            ("Row (ms)", "20.000".into()),
+            ("Row/Col", "2.00x".into()),
+            ("Pool (ms)", "5.000".into()),
+            ("Saved", "+50.0%".into()),
+            ("Huge (ms)", "12.000".into()),
+            ("Delta", "+20.0%".into()),
+            ("E2E (ms)", "15.000".into()),
+            ("Load %", "20.0%".into()),
+            ("FLOP/s", fmt(1e9)),
+            ("GB/s", "3.00".into()),
+            ("% peak", "50.0%".into()),
+            ("FLOP/s min", fmt(5e8)),
+            ("FLOP/s max", fmt(2e9)),
+            ("Spread", "150.0%".into()),
+            ("Repro", "0.20!".into()),
+        ];
+        let mut from = 0;
+        for (label, value) in columns {
+            let end = from
+                + header[from..]
+                    .find(&format!(" {label}"))
+                    .unwrap_or_else(|| panic!("{label} missing after column {from}"))
+                + 1
+                + label.len();
+            assert!(
+                cells[..end].ends_with(&format!(" {value}")),
+                "{label}: expected {value:?} under it\n{header}\n{cells}"
+            );
+            from = end;
+        }
+        assert_eq!(from, header.len());
+    }
+
+    #[test]
+    fn skipped_rows_carry_their_reason() {
+        let row = || "x SKIP -".to_string();
+        assert_eq!(
+            skipped_lines(&Options::default(), row(), "over --max-flops 7"),
+            ["x SKIP -  [SKIPPED: over --max-flops 7]"]
+        );
+        let julia = Options {
+            julia_compatible: true,
+            ..Options::default()
+        };
+        assert_eq!(
+            skipped_lines(&julia, row(), "over --max-flops 7"),
+            ["x SKIP -", "  reason: over --max-flops 7"]
+        );
+    }
+
+    #[test]
+    fn logfmt_quotes_only_values_that_need_it() {
+        let line = logfmt(&[
+            ("instance", "bin_matmul_256".into()),
+            ("median_ms", "1.234".into()),
+            ("reason", "log10FLOPS 9.75 over --max-flops 7.5".into()),
+            ("label", "a=\"b\"".into()),
+            ("empty", String::new()),
+        ]);
+        assert_eq!(
+            line,
+            r#"instance=bin_matmul_256 median_ms=1.234 reason="log10FLOPS 9.75 over --max-flops 7.5" label="a=\"b\"" empty="""#
+        );
+        let skip = report::Skipped {
+            instance: "broken".into(),
+            strategy: None,
+            reason: "parse failed".into(),
+        };
+        let opts = Options {
+            format: RowFormat::Line,
+            ..Options::default()
+        };
+        assert_eq!(
+            untimed_lines(&opts, String::new(), &skip),
+            [r#"instance=broken status=skipped reason="parse failed""#]
+        );
+    }
+
+    #[test]
+    fn seed_spread_flags_only_gaps_wider_than_the_noise() {
+        // 10% apart, each seed's IQR well under that.
+        assert_eq!(
+            seed_spread(&[(1.0, 0.01), (1.1, 0.02), (1.05, 0.01)]),
+            (1.0, 1.1, true)
+        );
+        // The same gap inside one seed's IQR is noise.
+        assert!(!seed_spread(&[(1.0, 0.2), (1.1, 0.02)]).2);
+        // Under SEED_SPREAD_FLAG.
+        assert!(!seed_spread(&[(1.0, 0.0), (1.04, 0.0)]).2);
+    }
+
+    #[test]
+    fn gflops_geomean_weights_instances() {
+        // 10^9 FLOPs in 1 s and in 10 ms: 1 and 100 GFLOP/s.
+        let mut summary = Summary {
+            timed: vec![timed("slow", 1000.0, 9.0), timed("fast", 10.0, 9.0)],
+            ..Default::default()
+        };
+        assert!((summary.gflops_geomean() - 10.0).abs() < 1e-9);
+        summary.timed[1].weight = 3.0;
+        // (1^1 * 100^3)^(1/4)
+        assert!((summary.gflops_geomean() - 100f64.powf(0.75)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn efficiency_range_names_best_and_worst_gflops() {
+        let mut summary = Summary {
+            timed: vec![timed("only", 1.0, 9.0)],
+            ..Default::default()
+        };
+        assert!(summary.efficiency_range().is_none());
+        // 1000, 10 and 100 GFLOP/s; the fastest median is not the best.
+        summary.timed.push(timed("small", 0.1, 6.0));
+        summary.timed.push(timed("big", 100.0, 10.0));
+        let (best, worst) = summary.efficiency_range().unwrap();
+        assert_eq!((best.name.as_str(), worst.name.as_str()), ("only", "small"));
+    }
+
+    #[test]
+    fn tokenized_format_maps_labels_consistently() {
+        let mut labels = HashMap::new();
+        let (inputs, output) =
+            parse_format_string_tokenized("[i0 bond_1],[bond_1, 42] -> [i0 42]", &mut labels)
+                .unwrap();
+        assert_eq!(labels.len(), 3);
+        assert_eq!(inputs.len(), 2);
+        assert_eq!(inputs[0][1], inputs[1][0]);
+        assert_eq!(output, vec![inputs[0][0], inputs[1][1]]);
+
+        // Scalars and more labels than single ASCII letters can name.
+        let many: Vec<String> = (0..100).map(|k| format!("x{k}")).collect();
+        let fmt = format!("[{}],[]->[]", many.join(" "));
+        let (inputs, output) = parse_format_string_tokenized(&fmt, &mut HashMap::new()).unwrap();
+        assert_eq!(inputs[0].len(), 100);
+        assert!(inputs[1].is_empty() && output.is_empty());
+
+        assert!(parse_format_string_tokenized("[a b,[b]->[a]", &mut HashMap::new()).is_err());
+        assert!(parse_format_string_tokenized("a b->a", &mut HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn numeric_suffixes_make_distinct_labels() {
+        let mut labels = HashMap::new();
+        let (inputs, output) =
+            parse_format_string_numeric_suffix("a1b,ba2a10->a1a2a10", &mut labels).unwrap();
+        assert_eq!(labels.len(), 4);
+        assert_eq!(inputs[0].len(), 2);
+        assert_eq!(inputs[1].len(), 3);
+        assert_eq!(inputs[0][1], inputs[1][0]);
+        assert_eq!(output, vec![inputs[0][0], inputs[1][1], inputs[1][2]]);
+        assert_ne!(inputs[1][1], inputs[1][2]);
+
+        // A bare letter and its suffixed forms are different labels.
+        let (inputs, _) =
+            parse_format_string_numeric_suffix("aa1,a1->", &mut HashMap::new()).unwrap();
+        assert_ne!(inputs[0][0], inputs[0][1]);
+        assert_eq!(inputs[0][1], inputs[1][0]);
+
+        for bad in ["1a,b->", "a01,b->a01", "a b->", "ab->a-"] {
+            assert!(
+                parse_format_string_numeric_suffix(bad, &mut HashMap::new()).is_err(),
+                "{bad}"
+            );
+        }
+    }
+
+    #[test]
+    fn zero_sized_dimension_is_detected() {
+        let json = r#"{
+            "name": "empty", "format_string_colmajor": "ab,bc->ac",
+            "shapes_colmajor": [[2, 3], [3, 0]], "dtype": "float64", "num_tensors": 2,
+            "paths": {"opt_flops": {"path": [[0, 1]], "log2_size": 0, "log10_flops": 0},
+                      "opt_size": {"path": [[0, 1]], "log2_size": 0, "log10_flops": 0}}
+        }"#;
+        let mut instance: BenchmarkInstance = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            instance.zero_sized_operand().as_deref(),
+            Some("[empty] tensor 1 has shape [3, 0]")
+        );
+        instance.shapes_colmajor[1] = vec![3, 4];
+        assert_eq!(instance.zero_sized_operand(), None);
+    }
+
+    #[test]
+    fn repeated_operand_gets_its_own_leaf_and_buffer() {
+        let (inputs, _) = parse_format_string("ab,ab->");
+        let tree = build_contraction_tree(&inputs, &[[0, 1]]);
+        let mut leaves = Vec::new();
+        leaf_indices(&tree, &mut leaves);
+        assert_eq!(leaves, vec![0, 1]);
+
+        let operands = create_operands(&[vec![3, 4], vec![3, 4]], "float64");
+        assert_eq!(operands.len(), 2);
+        let ptr = |op: &EinsumOperand| match op {
+            EinsumOperand::F64(data) => data.as_view().ptr(),
+            EinsumOperand::C64(_) => unreachable!(),
+        };
+        assert_ne!(ptr(&operands[0]), ptr(&operands[1]));
+    }
+
+    #[test]
+    fn self_contraction_evaluates() {
+        // ab,ab-> on identical data is the squared Frobenius norm.
+        let data = || {
+            let arr = StridedArray::<f64>::from_fn_col_major(&[2, 3], |i| (i[0] + 2 * i[1]) as f64);
+            EinsumOperand::from(arr)
+        };
+        let (inputs, output) = parse_format_string("ab,ab->");
+        let code = EinsumCode {
+            root: build_contraction_tree(&inputs, &[[0, 1]]),
+            output_ids: output,
+        };
+        let result = compare::col_major_values(&code.evaluate(vec![data(), data()], None).unwrap());
+        let expected: f64 = (0..6).map(|x| (x * x) as f64).sum();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].re, expected);
+    }
+
+    #[test]
+    fn repeated_label_within_operand_is_reported_not_panicking() {
+        // aa,ab->b: strided-opteinsum's binary contraction rejects duplicate
+        // axes, so the runner must surface this as a SKIP row.
+        let diag = StridedArray::<f64>::col_major(&[2, 2]);
+        let ones = StridedArray::<f64>::col_major(&[2, 3]);
+        let (inputs, output) = parse_format_string("aa,ab->b");
+        let code = EinsumCode {
+            root: build_contraction_tree(&inputs, &[[0, 1]]),
+            output_ids: output,
+        };
+        assert!(matches!(
+            code.evaluate(vec![diag.into(), ones.into()], None),
+            Err(EinsumError::Einsum2(_))
+        ));
+    }
+
+    #[test]
+    fn gzipped_instance_round_trips() {
+        use std::io::Write;
+
+        let json = r#"{"name": "gz", "format_string_colmajor": "ij,jk->ik",
+            "shapes_colmajor": [[2, 3], [3, 4]], "dtype": "float64", "num_tensors": 2,
+            "paths": {"opt_size": {"path": [[0, 1]], "log2_size": 3.0, "log10_flops": 1.4},
+                      "opt_flops": {"path": [[0, 1]], "log2_size": 3.0, "log10_flops": 1.4}}}"#;
+        let dir = std::env::temp_dir().join(format!("strided-bench-gz-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("gz.json.gz");
+        let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        enc.write_all(json.as_bytes()).unwrap();
+        std::fs::write(&path, enc.finish().unwrap()).unwrap();
+
+        assert!(is_instance_file(&path));
+        assert!(!is_instance_file(&dir.join("notes.gz")));
+        let read = read_instance_file(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(read, json);
+        let instance: BenchmarkInstance = serde_json::from_str(&read).unwrap();
+        assert_eq!(instance.shapes_colmajor, vec![vec![2, 3], vec![3, 4]]);
+    }
+
+    #[test]
+    fn output_rank_is_checked_against_format_string() {
+        let mut instance = generate::build_instance(&generate::GenOptions {
+            format: "ij,jk->ik".into(),
+            dims: [('i', 2), ('j', 3), ('k', 4)].into_iter().collect(),
+            dtype: "float64".into(),
+            ..generate::GenOptions::default()
+        })
+        .unwrap();
+        assert_eq!(instance.output_rank_mismatch(), None);
+        instance.expected_output_rank = Some(2);
+        assert_eq!(instance.output_rank_mismatch(), None);
+        instance.expected_output_rank = Some(0);
+        let msg = instance.output_rank_mismatch().unwrap();
+        assert!(msg.contains("'ik' has rank 2"), "{msg}");
+    }
+
+    #[test]
+    fn row_major_operands_keep_dims_and_reverse_strides() {
+        let shapes = [vec![2, 3, 4]];
+        let view_of = |ops: &[EinsumOperand]| match &ops[0] {
+            EinsumOperand::F64(data) => {
+                let v = data.as_view();
+                (v.dims().to_vec(), v.strides().to_vec())
+            }
+            EinsumOperand::C64(_) => unreachable!(),
+        };
+        let col = create_operands_in(&shapes, "float64", false);
+        let row = create_operands_in(&shapes, "float64", true);
+        assert_eq!(view_of(&col), (vec![2, 3, 4], vec![1, 2, 6]));
+        assert_eq!(view_of(&row), (vec![2, 3, 4], vec![12, 4, 1]));
+    }
+
+    #[test]
+    fn output_order_must_permute_the_output() {
+        let mut instance = generate::build_instance(&generate::GenOptions {
+            format: "ij,jk->ik".into(),
+            dims: [('i', 2), ('j', 3), ('k', 4)].into(),
+            ..Default::default()
+        })
+        .unwrap();
+        instance.format_string = Some("ji,kj->ki".into());
+        instance.expected_checksum = Some(1.0);
+        for bad in ["kk", "ij", "ikj", "i"] {
+            assert!(set_output_order(&mut instance, bad).is_err(), "{bad}");
+        }
+        set_output_order(&mut instance, "ki").unwrap();
+        assert_eq!(instance.format_string_colmajor, "ij,jk->ki");
+        assert_eq!(instance.format_string.as_deref(), Some("ji,kj->ik"));
+        assert_eq!(instance.expected_checksum, None);
+    }
+
+    #[test]
+    fn skipped_strategy_may_omit_its_path() {
+        let json = r#"{"name": "x", "format_string_colmajor": "ij,jk->ik",
+            "shapes_colmajor": [[2, 3], [3, 4]], "dtype": "float64", "num_tensors": 2,
+            "skip_strategies": ["opt_size"],
+            "paths": {"opt_flops": {"path": [[0, 1]], "log2_size": 3.0, "log10_flops": 1.4}}}"#;
+        let instance: BenchmarkInstance = serde_json::from_str(json).unwrap();
+        assert!(instance.skips("opt_size") && !instance.skips("opt_flops"));
+        assert!(instance.paths.opt_size.is_missing());
+        assert!(!instance.paths.opt_flops.is_missing());
+    }
+
+    #[test]
+    fn shared_buffer_views_are_validated_and_timed() {
+        let json = |buffer: usize| {
+            format!(
+                r#"{{"name": "views", "format_string_colmajor": "ij,jk->ik",
+                "shapes_colmajor": [[2, 3], [3, 4]], "dtype": "float64", "num_tensors": 2,
+                "buffer_size": {buffer},
+                "views": [{{"offset": 0, "strides": [1, 2]}}, {{"offset": 6, "strides": [1, 3]}}],
+                "paths": {{"opt_size": {{"path": [[0, 1]], "log2_size": 3.0, "log10_flops": 1.4}},
+                          "opt_flops": {{"path": [[0, 1]], "log2_size": 3.0, "log10_flops": 1.4}}}}}}"#
+            )
+        };
+        let dir = std::env::temp_dir().join(format!("strided-bench-views-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("views.json");
+        std::fs::write(&path, json(17)).unwrap();
+        let too_small = load_instance(&path);
+        std::fs::write(&path, json(18)).unwrap();
+        let instance = load_instance(&path);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(too_small.is_err_and(|e| e.starts_with("invalid shared buffer")));
+        let instance = instance.unwrap();
+
+        let buffer = instance.shared_buffer().unwrap();
+        let operands = instance.timed_operands(Some(&buffer), false);
+        assert!(matches!(
+            &operands[1],
+            EinsumOperand::F64(strided_opteinsum::StridedData::View(v)) if v.dims() == [3, 4]
+        ));
+        let opts = Options {
+            warmup: 1,
+            runs: 3,
+            ..Options::default()
+        };
+        let result = run_instance(&instance, &instance.paths.opt_flops, &opts, None).unwrap();
+        assert_eq!(result.samples_ns.len(), 3);
+    }
+
+    #[test]
+    fn gflops_spread_goes_from_slowest_to_fastest_run() {
+        let instance = generate::build_instance(&generate::GenOptions {
+            format: "ab,bc->ac".into(),
+            dims: [('a', 16), ('b', 16), ('c', 16)].into_iter().collect(),
+            dtype: "float64".into(),
+            ..generate::GenOptions::default()
+        })
+        .unwrap();
+        let path_meta = &instance.paths.opt_flops;
+        let opts = Options {
+            warmup: 1,
+            runs: 5,
+            gflops_spread: true,
+            ..Options::default()
+        };
+        let result = run_instance(&instance, path_meta, &opts, None).unwrap();
+        let (lo, median, hi) = result.gflops_spread.unwrap();
+        let flops = |ns: u64| 10f64.powf(path_meta.log10_flops) / (ns as f64 * 1e-9);
+        let close = |a: f64, b: f64| (a - b).abs() <= 1e-9 * b;
+        assert!(close(lo, flops(*result.samples_ns.iter().max().unwrap())));
+        assert!(close(hi, flops(*result.samples_ns.iter().min().unwrap())));
+        assert!(close(
+            median,
+            10f64.powf(path_meta.log10_flops) / (result.median_ms / 1e3)
+        ));
+        assert!(lo <= median && median <= hi);
+    }
+
+    #[test]
+    fn physical_strategy_runs_only_where_listed() {
+        let json = |physical: &str| {
+            format!(
+                r#"{{"name": "x", "format_string_colmajor": "ij,jk->ik",
+                "shapes_colmajor": [[2, 3], [3, 4]], "dtype": "float64", "num_tensors": 2,
+                "paths": {{"opt_size": {{"path": [[0, 1]], "log2_size": 3.0, "log10_flops": 1.4}},
+                "opt_flops": {{"path": [[0, 1]], "log2_size": 3.0, "log10_flops": 1.4}}{physical}}}}}"#
+            )
+        };
+        let without: BenchmarkInstance = serde_json::from_str(&json("")).unwrap();
+        assert!(without.skips("physical") && !without.skips("opt_size"));
+        let names = |all: &[BenchmarkInstance]| -> Vec<&str> {
+            Instances::Loaded(all)
+                .strategies()
+                .iter()
+                .map(|(name, _)| *name)
+                .collect()
+        };
+        assert_eq!(
+            names(std::slice::from_ref(&without)),
+            ["opt_flops", "opt_size"]
+        );
+        let with: BenchmarkInstance = serde_json::from_str(&json(
+            r#", "physical": {"path": [[1, 0]], "log2_size": 3.0, "log10_flops": 1.4}"#,
+        ))
+        .unwrap();
+        assert!(!with.skips("physical"));
+        assert_eq!(with.paths.physical.path, vec![[1, 0]]);
+        assert_eq!(
+            names(&[without, with]),
+            ["opt_flops", "opt_size", "physical"]
+        );
+    }
+
+    #[test]
+    fn pure_transpose_copies_the_permuted_tensor() {
+        let instance = |format: &str| {
+            generate::build_instance(&generate::GenOptions {
+                format: format.into(),
+                dims: [('a', 2), ('b', 3), ('c', 4)]
+                    .into_iter()
+                    .filter(|(id, _)| format.contains(*id))
+                    .collect(),
+                dtype: "complex128".into(),
+                ..generate::GenOptions::default()
+            })
+            .unwrap()
+        };
+        for format in ["abc->ab", "aab->ab", "ab,bc->ac", "abc->aab"] {
+            assert_eq!(instance(format).pure_transpose(), None, "{format}");
+        }
+        let t = instance("abc->cab");
+        let perm = t.pure_transpose().unwrap();
+        assert_eq!(perm, vec![2, 0, 1]);
+        let seeded = || fill::random_operands(&t.shapes_colmajor, "complex128", &[], 5, |x| x);
+        let copied = transpose(seeded().remove(0), &perm).unwrap();
+        assert_eq!(copied.dims(), &[4, 2, 3]);
+        let general = plan(&t, &t.paths.opt_flops)
+            .evaluate(seeded(), None)
+            .unwrap();
+        assert_eq!(
+            compare::col_major_values(&copied),
+            compare::col_major_values(&general)
+        );
+    }
+
+    #[test]
+    fn flops_per_element_divides_by_output_size() {
+        let instance = generate::build_instance(&generate::GenOptions {
+            format: "ij,jk->ik".into(),
+            dims: [('i', 2), ('j', 3), ('k', 4)].into_iter().collect(),
+            dtype: "float64".into(),
+            ..generate::GenOptions::default()
+        })
+        .unwrap();
+        // 2 * 3 * 4 = 24 FLOPs over an 8-element output: j = 3 per element.
+        let ratio = flops_per_output_element(&instance, &instance.paths.opt_flops).unwrap();
+        assert!((ratio - 3.0).abs() < 1e-9, "{ratio}");
+    }
+
+    #[test]
+    fn peak_estimate_counts_operands_and_largest_intermediate() {
+        let instance = generate::build_instance(&generate::GenOptions {
+            format: "ij,jk->ik".into(),
+            dims: [('i', 2), ('j', 3), ('k', 4)].into_iter().collect(),
+            dtype: "complex128".into(),
+            ..generate::GenOptions::default()
+        })
+        .unwrap();
+        // (6 + 12 input elements + 2^3 for the output) * 16 bytes.
+        let bytes = estimated_peak_bytes(&instance, &instance.paths.opt_flops);
+        assert!((bytes - 26.0 * 16.0).abs() < 1e-9, "{bytes}");
+    }
+
+    #[test]
+    fn scaled_evaluation_accumulates_into_buffer() {
+        let data = || {
+            let arr = StridedArray::<f64>::from_fn_col_major(&[2, 3], |i| (i[0] + 2 * i[1]) as f64);
+            EinsumOperand::from(arr)
+        };
+        let (inputs, output) = parse_format_string("ab,ab->");
+        let code = EinsumCode {
+            root: build_contraction_tree(&inputs, &[[0, 1]]),
+            output_ids: output,
+        };
+        let mut buf = OutputBuffer::F64(StridedArray::col_major(&[]));
+        // 2 * r, then 2 * r + 0.5 * (2 * r) = 3 * r.
+        buf.evaluate_into(&code, vec![data(), data()], (2.0, 0.5))
+            .unwrap();
+        buf.evaluate_into(&code, vec![data(), data()], (2.0, 0.5))
+            .unwrap();
+        let OutputBuffer::F64(arr) = buf else {
+            unreachable!()
+        };
+        let expected: f64 = (0..6).map(|x| (x * x) as f64).sum();
+        assert_eq!(arr.data()[0], 3.0 * expected);
+    }
+
+    #[test]
+    fn reproducibility_halves_at_the_reference_spread() {
+        assert_eq!(reproducibility(2.0, 0.0), 1.0);
+        assert!((reproducibility(2.0, 0.1) - 0.5).abs() < 1e-12);
+        assert!(reproducibility(2.0, 0.4) < REPRO_FLAG_BELOW);
+        assert!(reproducibility(2.0, 0.02) > 0.8);
+        assert_eq!(reproducibility(0.0, 0.0), 1.0);
+    }
+
+    #[test]
+    fn min_is_stable_after_k_runs_without_a_new_low() {
+        let ms = Duration::from_millis;
+        let mut tracker = MinTracker::new(2);
+        assert!(!tracker.push(ms(5)));
+        assert!(!tracker.push(ms(4)));
+        assert!(
+            !tracker.push(ms(4)),
+            "a tie is not a new minimum, but one run is not enough"
+        );
+        assert!(!tracker.push(ms(3)), "a new minimum restarts the count");
+        assert!(!tracker.push(ms(6)));
+        assert!(tracker.push(ms(3)));
+        assert_eq!(tracker.best, ms(3));
+    }
+
+    #[test]
+    fn sidecar_paths_override_listed_strategies() {
+        let mut instance = generate::build_instance(&generate::GenOptions {
+            format: "ab,bc,cd->ad".into(),
+            dims: [('a', 2), ('b', 3), ('c', 4), ('d', 5)]
+                .into_iter()
+                .collect(),
+            dtype: "float64".into(),
+            ..generate::GenOptions::default()
+        })
+        .unwrap();
+        instance.name = "chain".into();
+        let dir = std::env::temp_dir().join(format!("strided-bench-paths-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        assert!(!apply_sidecar_paths(&dir, &mut instance).unwrap());
+
+        // Only opt_size is listed, without costs: (T1 T2) first, then T0.
+        let sidecar = dir.join("chain.path.json");
+        std::fs::write(&sidecar, r#"{"opt_size": {"path": [[1, 2], [0, 1]]}}"#).unwrap();
+        let embedded = instance.paths.opt_flops.path.clone();
+        assert!(apply_sidecar_paths(&dir, &mut instance).unwrap());
+        assert_eq!(instance.paths.opt_size.path, vec![[1, 2], [0, 1]]);
+        // 3*4*5 + 2*3*5 multiplies; the largest tensor is the 3x5 intermediate.
+        assert!((instance.paths.opt_size.log10_flops - 90f64.log10()).abs() < 1e-12);
+        assert!((instance.paths.opt_size.log2_size - 15f64.log2()).abs() < 1e-12);
+        assert_eq!(instance.paths.opt_flops.path, embedded);
+
+        std::fs::write(&sidecar, r#"{"opt_flops": {"path": [[0, 3]]}}"#).unwrap();
+        let err = apply_sidecar_paths(&dir, &mut instance).unwrap_err();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(err.contains("invalid pair"), "{err}");
     }
 }
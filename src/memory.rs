@@ -0,0 +1,87 @@
+//! Peak heap measurement for `--track-memory`, and the machine's available
+//! memory for the pre-run guard.
+//!
+//! [`TrackingAlloc`] wraps the system allocator. Outside [`measure`] it only
+//! checks one relaxed flag per call, so ordinary timed runs are unaffected.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicBool, AtomicIsize, Ordering};
+
+pub struct TrackingAlloc;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+/// Bytes allocated minus bytes freed since `measure` started. Can go
+/// negative when memory allocated before the measurement is freed.
+static CURRENT: AtomicIsize = AtomicIsize::new(0);
+static PEAK: AtomicIsize = AtomicIsize::new(0);
+
+fn record(delta: isize) {
+    if ENABLED.load(Ordering::Relaxed) {
+        let now = CURRENT.fetch_add(delta, Ordering::Relaxed) + delta;
+        PEAK.fetch_max(now, Ordering::Relaxed);
+    }
+}
+
+// SAFETY: every call is forwarded unchanged to `System`; the bookkeeping
+// never touches the returned memory.
+unsafe impl GlobalAlloc for TrackingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            record(layout.size() as isize);
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            record(layout.size() as isize);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        record(-(layout.size() as isize));
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new = System.realloc(ptr, layout, new_size);
+        if !new.is_null() {
+            record(new_size as isize - layout.size() as isize);
+        }
+        new
+    }
+}
+
+/// Run `f` and return its result with the peak heap growth (bytes above
+/// what was live when `f` started) reached while it ran, including whatever
+/// `f` returns. Allocations on other threads during `f` are counted too.
+pub fn measure<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    CURRENT.store(0, Ordering::Relaxed);
+    PEAK.store(0, Ordering::Relaxed);
+    ENABLED.store(true, Ordering::SeqCst);
+    let result = f();
+    ENABLED.store(false, Ordering::SeqCst);
+    (result, PEAK.load(Ordering::Relaxed).max(0) as usize)
+}
+
+/// Physical memory currently available to new allocations, in bytes, or
+/// `None` where the platform doesn't report it.
+pub fn available_bytes() -> Option<u64> {
+    let mut sys = sysinfo::System::new();
+    sys.refresh_memory();
+    Some(sys.available_memory()).filter(|&b| b > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measure_counts_live_result() {
+        let (buf, peak) = measure(|| vec![1u8; 64 << 20]);
+        assert!(peak >= buf.len());
+    }
+}
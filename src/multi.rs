@@ -0,0 +1,224 @@
+//! `--multi-output`: instances that need several outputs from one network.
+//!
+//! `EinsumCode` is a tree with a single output, so the shared evaluation is
+//! a two-level DAG built from it: the instance's path is contracted once
+//! down to the union of every requested output's labels, and each output is
+//! then a single-operand reduction of that shared intermediate. The naive
+//! alternative contracts the whole path again for every output. Both use the
+//! same path, so the difference is what sharing the intermediates saves (or,
+//! when the union is much larger than any one output, costs).
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use strided_opteinsum::{EinsumCode, EinsumError, EinsumNode, EinsumOperand};
+
+use crate::{create_operands, parse_format_string, BenchmarkInstance, PathMeta};
+
+/// Medians of the shared and naive evaluations of one instance.
+pub struct MultiTime {
+    pub shared: Duration,
+    pub naive: Duration,
+}
+
+/// Labels of every output, in order of first appearance.
+fn union_labels(outputs: &[Vec<char>]) -> Vec<char> {
+    let mut union = Vec::new();
+    for ids in outputs {
+        for &id in ids {
+            if !union.contains(&id) {
+                union.push(id);
+            }
+        }
+    }
+    union
+}
+
+/// Check that each output only names input labels, none of them twice.
+pub fn validate_outputs(input_indices: &[Vec<char>], outputs: &[String]) -> Result<(), String> {
+    if outputs.is_empty() {
+        return Err("outputs_colmajor is empty".into());
+    }
+    for out in outputs {
+        let ids: Vec<char> = out.chars().collect();
+        for (k, id) in ids.iter().enumerate() {
+            if ids[..k].contains(id) {
+                return Err(format!("output {out:?}: label '{id}' repeated"));
+            }
+            if !input_indices.iter().any(|t| t.contains(id)) {
+                return Err(format!("output {out:?}: label '{id}' not in any input"));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Borrow an evaluated operand as the input of another contraction.
+fn borrow<'a>(op: &'a EinsumOperand<'_>) -> EinsumOperand<'a> {
+    match op {
+        EinsumOperand::F64(data) => EinsumOperand::from_view(&data.as_view()),
+        EinsumOperand::C64(data) => EinsumOperand::from_view(&data.as_view()),
+    }
+}
+
+/// Materialize an output that may still view the shared intermediate (a
+/// reduction that only permutes), so both evaluations return owned data.
+fn owned(op: EinsumOperand<'_>) -> EinsumOperand<'static> {
+    match op {
+        EinsumOperand::F64(data) => data.into_array().into(),
+        EinsumOperand::C64(data) => data.into_array().into(),
+    }
+}
+
+/// The contraction plans behind both evaluations.
+struct Plans {
+    /// Path tree down to the union of the output labels.
+    shared: EinsumCode,
+    /// One single-leaf reduction of the shared intermediate per output.
+    reductions: Vec<EinsumCode>,
+    /// The whole path tree once per output.
+    naive: Vec<EinsumCode>,
+}
+
+fn plans(outputs: &[String], root: &EinsumNode) -> Plans {
+    let outputs: Vec<Vec<char>> = outputs.iter().map(|o| o.chars().collect()).collect();
+    let union = union_labels(&outputs);
+    Plans {
+        shared: EinsumCode {
+            root: root.clone(),
+            output_ids: union.clone(),
+        },
+        reductions: outputs
+            .iter()
+            .map(|ids| EinsumCode {
+                root: EinsumNode::Leaf {
+                    ids: union.clone(),
+                    tensor_index: 0,
+                },
+                output_ids: ids.clone(),
+            })
+            .collect(),
+        naive: outputs
+            .iter()
+            .map(|ids| EinsumCode {
+                root: root.clone(),
+                output_ids: ids.clone(),
+            })
+            .collect(),
+    }
+}
+
+impl Plans {
+    /// Every output via the shared intermediate.
+    fn eval_shared(
+        &self,
+        operands: Vec<EinsumOperand<'static>>,
+    ) -> Result<Vec<EinsumOperand<'static>>, EinsumError> {
+        let union = self.shared.evaluate(operands, None)?;
+        self.reductions
+            .iter()
+            .map(|code| code.evaluate(vec![borrow(&union)], None).map(owned))
+            .collect()
+    }
+
+    /// Every output from scratch, each from its own set of operands.
+    fn eval_naive(
+        &self,
+        operand_sets: Vec<Vec<EinsumOperand<'static>>>,
+    ) -> Result<Vec<EinsumOperand<'static>>, EinsumError> {
+        self.naive
+            .iter()
+            .zip(operand_sets)
+            .map(|(code, operands)| code.evaluate(operands, None))
+            .collect()
+    }
+}
+
+/// Time the shared DAG against recomputing each output (`warmup` untimed
+/// runs, then the median of `runs`). Operand allocation is outside the
+/// timed region for both.
+pub fn compare(
+    instance: &BenchmarkInstance,
+    path_meta: &PathMeta,
+    outputs: &[String],
+    warmup: usize,
+    runs: usize,
+) -> Result<MultiTime, EinsumError> {
+    let (input_indices, _) = parse_format_string(&instance.format_string_colmajor);
+    let root = crate::build_contraction_tree(&input_indices, &path_meta.path);
+    let plans = plans(outputs, &root);
+    let operands = || create_operands(&instance.shapes_colmajor, &instance.dtype);
+
+    let median = |mut samples: Vec<Duration>| {
+        samples.sort();
+        samples[samples.len() / 2]
+    };
+    let mut shared = Vec::with_capacity(runs);
+    let mut naive = Vec::with_capacity(runs);
+    for r in 0..warmup + runs {
+        let ops = operands();
+        let t0 = Instant::now();
+        black_box(plans.eval_shared(ops)?);
+        let t_shared = t0.elapsed();
+
+        let sets = (0..plans.naive.len()).map(|_| operands()).collect();
+        let t0 = Instant::now();
+        black_box(plans.eval_naive(sets)?);
+        let t_naive = t0.elapsed();
+
+        if r >= warmup {
+            shared.push(t_shared);
+            naive.push(t_naive);
+        }
+    }
+    Ok(MultiTime {
+        shared: median(shared),
+        naive: median(naive),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compare::{col_major_values, max_rel_error};
+    use crate::fill;
+
+    #[test]
+    fn union_keeps_first_appearance_order() {
+        let outputs = vec![vec!['a', 'd'], vec!['c', 'a'], vec![]];
+        assert_eq!(union_labels(&outputs), vec!['a', 'd', 'c']);
+    }
+
+    #[test]
+    fn shared_outputs_match_recomputation() {
+        let (inputs, _) = parse_format_string("ab,bc,cd->ad");
+        let shapes = vec![vec![2, 3], vec![3, 4], vec![4, 5]];
+        let root = crate::build_contraction_tree(&inputs, &[[0, 1], [0, 1]]);
+        let outputs = ["ad".to_string(), "c".to_string(), String::new()];
+        validate_outputs(&inputs, &outputs).unwrap();
+        let plans = plans(&outputs, &root);
+        let seeded = || fill::random_operands(&shapes, "float64", &[], 7, |x| x);
+        let shared = plans.eval_shared(seeded()).unwrap();
+        let naive = plans
+            .eval_naive(vec![seeded(), seeded(), seeded()])
+            .unwrap();
+        for (s, n) in shared.iter().zip(&naive) {
+            // A full reduction of a single operand comes back as shape [1]
+            // rather than [], so compare element counts rather than shapes.
+            assert_eq!(
+                s.dims().iter().product::<usize>(),
+                n.dims().iter().product::<usize>()
+            );
+            assert!(max_rel_error(&col_major_values(n), &col_major_values(s)) < 1e-12);
+        }
+    }
+
+    #[test]
+    fn outputs_must_name_input_labels_once() {
+        let (inputs, _) = parse_format_string("ab,bc->ac");
+        assert!(validate_outputs(&inputs, &["ac".into(), "b".into()]).is_ok());
+        assert!(validate_outputs(&inputs, &["ax".into()]).is_err());
+        assert!(validate_outputs(&inputs, &["aa".into()]).is_err());
+        assert!(validate_outputs(&inputs, &[]).is_err());
+    }
+}
@@ -0,0 +1,108 @@
+//! Minimal `.npy` (format version 1.0) writer for `--dump-operands`.
+//!
+//! Arrays are written in Fortran order, the dataset's column-major layout,
+//! so `np.load` gives back the same logical tensor with no transpose.
+
+use std::path::Path;
+
+use num_complex::Complex64;
+use strided_opteinsum::EinsumOperand;
+
+use crate::compare;
+
+/// An element type with a little-endian NumPy `descr`.
+pub trait Element: Copy {
+    const DESCR: &'static str;
+    fn put(self, out: &mut Vec<u8>);
+}
+
+impl Element for f64 {
+    const DESCR: &'static str = "<f8";
+    fn put(self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+}
+
+impl Element for Complex64 {
+    const DESCR: &'static str = "<c16";
+    fn put(self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.re.to_le_bytes());
+        out.extend_from_slice(&self.im.to_le_bytes());
+    }
+}
+
+/// The `.npy` bytes of `data`, the column-major elements of `shape`.
+pub fn encode<T: Element>(shape: &[usize], data: &[T]) -> Vec<u8> {
+    assert_eq!(
+        data.len(),
+        shape.iter().product::<usize>(),
+        "data does not match the shape"
+    );
+    let dims = match shape {
+        [n] => format!("{n},"),
+        _ => shape
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", "),
+    };
+    let mut header = format!(
+        "{{'descr': '{}', 'fortran_order': True, 'shape': ({dims}), }}",
+        T::DESCR
+    );
+    // Magic (6), version (2) and header length (2), then the header padded
+    // with spaces and ended by a newline so the data is 64-byte aligned.
+    let unpadded = 10 + header.len() + 1;
+    header.extend(std::iter::repeat_n(
+        ' ',
+        unpadded.next_multiple_of(64) - unpadded,
+    ));
+    header.push('\n');
+    let mut out = Vec::with_capacity(10 + header.len() + std::mem::size_of_val(data));
+    out.extend_from_slice(b"\x93NUMPY\x01\x00");
+    out.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    out.extend_from_slice(header.as_bytes());
+    for &x in data {
+        x.put(&mut out);
+    }
+    out
+}
+
+/// Write `op` to `path` in its own dtype.
+pub fn write_operand(path: &Path, op: &EinsumOperand) -> Result<(), String> {
+    let bytes = match op {
+        EinsumOperand::F64(data) => encode(data.as_view().dims(), &compare::dense_col_major(data)),
+        EinsumOperand::C64(data) => encode(data.as_view().dims(), &compare::dense_col_major(data)),
+    };
+    std::fs::write(path, bytes).map_err(|e| format!("write {}: {e}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The header text and where the data starts.
+    fn header(bytes: &[u8]) -> (&str, usize) {
+        let len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+        (std::str::from_utf8(&bytes[10..10 + len]).unwrap(), 10 + len)
+    }
+
+    #[test]
+    fn header_is_aligned_and_data_follows_in_fortran_order() {
+        let bytes = encode(&[2, 3], &[1.0f64, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        assert_eq!(&bytes[..8], b"\x93NUMPY\x01\x00");
+        let (text, start) = header(&bytes);
+        assert_eq!(start % 64, 0);
+        assert!(text.starts_with("{'descr': '<f8', 'fortran_order': True, 'shape': (2, 3), }"));
+        assert!(text.ends_with(" \n"));
+        assert_eq!(bytes.len() - start, 6 * 8);
+        assert_eq!(bytes[start + 8..start + 16], 2.0f64.to_le_bytes());
+
+        let vector = encode(&[1], &[Complex64::new(1.0, -1.0)]);
+        let (text, start) = header(&vector);
+        assert!(text.contains("'descr': '<c16'") && text.contains("'shape': (1,)"));
+        assert_eq!((start % 64, vector.len() - start), (0, 16));
+        let scalar = encode::<f64>(&[], &[7.0]);
+        assert!(header(&scalar).0.contains("'shape': ()"));
+    }
+}
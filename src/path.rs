@@ -0,0 +1,314 @@
+//! Static analysis of a contraction path (no tensor data involved).
+//!
+//! Replays a path on index labels alone, mirroring what `strided-opteinsum`
+//! does for each binary `Contract` node: an intermediate keeps the labels
+//! still needed by the final output or by a remaining tensor, in canonical
+//! `[lo, ro, batch]` order.
+
+use std::collections::HashMap;
+
+/// One pairwise contraction step of a path.
+#[derive(Debug, Clone)]
+pub struct PathStep {
+    /// Product of the sizes of every label touched by this step.
+    pub flops: f64,
+    /// Number of elements in the step's output.
+    pub size: f64,
+    /// Labels of the two operands, as the step receives them.
+    pub left: Vec<char>,
+    pub right: Vec<char>,
+    /// Labels of the step's output, in canonical order.
+    pub output: Vec<char>,
+    /// Which BLAS-style kernel the step's index structure maps to.
+    pub kind: StepKind,
+    /// Whether labels shared by both operands are kept (a batch dimension).
+    pub batched: bool,
+}
+
+impl PathStep {
+    /// Whether strided-einsum2 hands the step to the backend's batched GEMM.
+    /// It takes its element-wise kernel instead when no label is free on
+    /// either side and none is contracted, whatever the sizes; labels summed
+    /// within one operand are reduced before either. This mirrors
+    /// `einsum2_into`'s dispatch, since the backend doesn't report which
+    /// kernel ran.
+    pub fn uses_gemm(&self) -> bool {
+        let (left, right, output) = (&self.left, &self.right, &self.output);
+        left.iter()
+            .any(|id| !right.contains(id) && output.contains(id))
+            || right
+                .iter()
+                .any(|id| !left.contains(id) && output.contains(id))
+            || left
+                .iter()
+                .any(|id| right.contains(id) && !output.contains(id))
+    }
+}
+
+/// Shape of a binary contraction, from which of its label groups have more
+/// than one element: free labels of each side (`lo`, `ro`), contracted
+/// labels shared by both, and batch labels shared by both and kept.
+///
+/// Labels summed out of a single operand (a trace before the step) don't
+/// change the kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum StepKind {
+    /// Contracted labels with free labels on both sides: matrix-matrix.
+    Gemm,
+    /// Contracted labels with free labels on one side: matrix-vector.
+    Gemv,
+    /// Contracted labels only: an inner product.
+    Dot,
+    /// Free labels on both sides, nothing contracted.
+    Outer,
+    /// Nothing contracted and at most one side free: a scaling or an
+    /// elementwise product (with batch labels).
+    Elementwise,
+}
+
+impl StepKind {
+    /// Classify a step with `left`/`right` operand labels producing
+    /// `output`, and report whether it is batched (shared labels kept).
+    pub fn classify(
+        left: &[char],
+        right: &[char],
+        output: &[char],
+        dims: &HashMap<char, usize>,
+    ) -> (StepKind, bool) {
+        let size = |ids: &[char], keep: &dyn Fn(&char) -> bool| -> usize {
+            ids.iter()
+                .filter(|id| keep(id))
+                .map(|id| dims[id])
+                .product()
+        };
+        let lo = size(left, &|id| !right.contains(id) && output.contains(id));
+        let ro = size(right, &|id| !left.contains(id) && output.contains(id));
+        let contracted = size(left, &|id| right.contains(id) && !output.contains(id));
+        let batch = size(left, &|id| right.contains(id) && output.contains(id));
+        let kind = match (contracted > 1, lo > 1, ro > 1) {
+            (true, true, true) => StepKind::Gemm,
+            (true, false, false) => StepKind::Dot,
+            (true, _, _) => StepKind::Gemv,
+            (false, true, true) => StepKind::Outer,
+            (false, _, _) => StepKind::Elementwise,
+        };
+        (kind, batch > 1)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            StepKind::Gemm => "GEMM",
+            StepKind::Gemv => "GEMV",
+            StepKind::Dot => "dot",
+            StepKind::Outer => "outer",
+            StepKind::Elementwise => "elementwise",
+        }
+    }
+}
+
+/// Map each index label to its dimension, checking consistency across operands.
+pub fn dim_map(
+    input_indices: &[Vec<char>],
+    shapes: &[Vec<usize>],
+) -> Result<HashMap<char, usize>, String> {
+    if input_indices.len() != shapes.len() {
+        return Err(format!(
+            "{} operands in format string but {} shapes",
+            input_indices.len(),
+            shapes.len()
+        ));
+    }
+    let mut dims = HashMap::new();
+    for (t, (ids, shape)) in input_indices.iter().zip(shapes).enumerate() {
+        if ids.len() != shape.len() {
+            return Err(format!(
+                "tensor {t}: {} labels but rank-{} shape",
+                ids.len(),
+                shape.len()
+            ));
+        }
+        for (&id, &d) in ids.iter().zip(shape) {
+            if let Some(&prev) = dims.get(&id) {
+                if prev != d {
+                    return Err(format!("label '{id}': dimension {prev} vs {d}"));
+                }
+            } else {
+                dims.insert(id, d);
+            }
+        }
+    }
+    Ok(dims)
+}
+
+/// Replay `path` over the input labels and return every step in order.
+pub fn simulate(
+    input_indices: &[Vec<char>],
+    output_indices: &[char],
+    dims: &HashMap<char, usize>,
+    path: &[[usize; 2]],
+) -> Result<Vec<PathStep>, String> {
+    let mut current: Vec<Vec<char>> = input_indices.to_vec();
+    let mut steps = Vec::with_capacity(path.len());
+
+    for (s, &pair) in path.iter().enumerate() {
+        let (i, j) = if pair[0] < pair[1] {
+            (pair[0], pair[1])
+        } else {
+            (pair[1], pair[0])
+        };
+        if i == j || j >= current.len() {
+            return Err(format!(
+                "step {s}: invalid pair {pair:?} for {} tensors",
+                current.len()
+            ));
+        }
+        let right = current.remove(j);
+        let left = current.remove(i);
+
+        let mut needed: Vec<char> = output_indices.to_vec();
+        for ids in &current {
+            needed.extend_from_slice(ids);
+        }
+        let output = binary_output_ids(&left, &right, &needed);
+
+        let mut touched: Vec<char> = left.clone();
+        for &id in &right {
+            if !touched.contains(&id) {
+                touched.push(id);
+            }
+        }
+        let flops = touched.iter().map(|id| dims[id] as f64).product();
+        let size = output.iter().map(|id| dims[id] as f64).product();
+        let (kind, batched) = StepKind::classify(&left, &right, &output, dims);
+
+        current.push(output.clone());
+        steps.push(PathStep {
+            flops,
+            size,
+            left,
+            right,
+            output,
+            kind,
+            batched,
+        });
+    }
+
+    if current.len() != 1 {
+        return Err(format!(
+            "path leaves {} tensors instead of one",
+            current.len()
+        ));
+    }
+    Ok(steps)
+}
+
+/// `(log10_flops, log2_size)` of a simulated path, in the dataset's convention.
+pub fn cost(steps: &[PathStep]) -> (f64, f64) {
+    let flops: f64 = steps.iter().map(|s| s.flops).sum();
+    let size = steps.iter().map(|s| s.size).fold(1.0, f64::max);
+    (flops.max(1.0).log10(), size.log2())
+}
+
+/// Elements any evaluation must move at least once: every input read and
+/// the output written (intermediates and repeated passes excluded).
+pub fn min_traffic_elements(
+    shapes: &[Vec<usize>],
+    output_indices: &[char],
+    dims: &HashMap<char, usize>,
+) -> f64 {
+    let inputs: f64 = shapes
+        .iter()
+        .map(|s| s.iter().map(|&d| d as f64).product::<f64>())
+        .sum();
+    inputs + output_elements(output_indices, dims)
+}
+
+/// Number of elements in the final output (1 for a scalar).
+pub fn output_elements(output_indices: &[char], dims: &HashMap<char, usize>) -> f64 {
+    output_indices.iter().map(|id| dims[id] as f64).product()
+}
+
+/// Canonical `[lo, ro, batch]` output labels of a binary contraction.
+pub fn binary_output_ids(left: &[char], right: &[char], needed: &[char]) -> Vec<char> {
+    let mut out = Vec::new();
+    for &id in left {
+        if needed.contains(&id) && !right.contains(&id) && !out.contains(&id) {
+            out.push(id);
+        }
+    }
+    for &id in right {
+        if needed.contains(&id) && !left.contains(&id) && !out.contains(&id) {
+            out.push(id);
+        }
+    }
+    for &id in left {
+        if needed.contains(&id) && right.contains(&id) && !out.contains(&id) {
+            out.push(id);
+        }
+    }
+    out
+}
+
+/// Sequential left-to-right path: `((T0 T1) T2) T3 ...`.
+pub fn linear_path(num_tensors: usize) -> Vec<[usize; 2]> {
+    (0..num_tensors.saturating_sub(1))
+        .map(|step| {
+            let remaining = num_tensors - step;
+            if step == 0 {
+                [0, 1]
+            } else {
+                [0, remaining - 1]
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_classify_by_label_groups() {
+        let dims: HashMap<char, usize> =
+            "abcdz".chars().map(|c| (c, 4)).chain([('u', 1)]).collect();
+        let kind = |l: &str, r: &str, o: &str| {
+            let ids = |s: &str| s.chars().collect::<Vec<_>>();
+            StepKind::classify(&ids(l), &ids(r), &ids(o), &dims)
+        };
+        assert_eq!(kind("ab", "bc", "ac"), (StepKind::Gemm, false));
+        assert_eq!(kind("zab", "zbc", "acz"), (StepKind::Gemm, true));
+        assert_eq!(kind("ab", "b", "a"), (StepKind::Gemv, false));
+        assert_eq!(kind("ab", "ab", ""), (StepKind::Dot, false));
+        assert_eq!(kind("a", "b", "ab"), (StepKind::Outer, false));
+        assert_eq!(kind("ab", "ab", "ab"), (StepKind::Elementwise, true));
+        // A size-1 free label doesn't make a vector a matrix.
+        assert_eq!(kind("ab", "bu", "au"), (StepKind::Gemv, false));
+    }
+
+    #[test]
+    fn only_steps_without_free_or_contracted_labels_skip_gemm() {
+        let ids = |s: &str| s.chars().collect::<Vec<_>>();
+        let uses_gemm = |l: &str, r: &str, o: &str| {
+            PathStep {
+                flops: 0.0,
+                size: 0.0,
+                left: ids(l),
+                right: ids(r),
+                output: ids(o),
+                kind: StepKind::Gemm,
+                batched: false,
+            }
+            .uses_gemm()
+        };
+        assert!(uses_gemm("ab", "bc", "ac"));
+        assert!(uses_gemm("ab", "ab", ""));
+        assert!(uses_gemm("a", "b", "ab"));
+        assert!(
+            uses_gemm("ab", "b", "ab"),
+            "a free label, even with b batched"
+        );
+        assert!(!uses_gemm("ab", "ab", "ab"));
+        // `c` is traced out of the left operand before the kernel is chosen.
+        assert!(!uses_gemm("abc", "ab", "ab"));
+    }
+}
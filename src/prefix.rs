@@ -0,0 +1,181 @@
+//! `--prefix-sweep`: time the first `k` steps of a path, for every `k`.
+//!
+//! After `k` steps the path has built a forest: one contraction subtree per
+//! intermediate still alive, next to the untouched inputs. Each subtree is
+//! evaluated as its own `EinsumCode` down to its intermediate (labels in the
+//! tree evaluator's canonical order), so prefix `k` costs what the full run
+//! spends before step `k + 1`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use strided_opteinsum::{EinsumCode, EinsumError, EinsumNode, EinsumOperand};
+
+use crate::path::binary_output_ids;
+use crate::{create_operands, parse_format_string, BenchmarkInstance, PathMeta};
+
+/// Timing of one path prefix.
+pub struct PrefixTime {
+    /// Number of path steps evaluated.
+    pub steps: usize,
+    /// Median over the timed runs.
+    pub median: Duration,
+    /// Elements in the largest intermediate alive after the prefix.
+    pub largest: usize,
+}
+
+/// Subtrees built by the first `k` steps, each with its intermediate's
+/// labels and the original operand indices it reads, leaves renumbered
+/// `0..` in that order.
+fn prefix_forest(
+    input_indices: &[Vec<char>],
+    output_indices: &[char],
+    path: &[[usize; 2]],
+    k: usize,
+) -> Vec<(EinsumCode, Vec<usize>)> {
+    // (node, labels, original operand indices)
+    let mut current: Vec<(EinsumNode, Vec<char>, Vec<usize>)> = input_indices
+        .iter()
+        .enumerate()
+        .map(|(t, ids)| {
+            let leaf = EinsumNode::Leaf {
+                ids: ids.clone(),
+                tensor_index: t,
+            };
+            (leaf, ids.clone(), vec![t])
+        })
+        .collect();
+    for &pair in &path[..k] {
+        let (i, j) = if pair[0] < pair[1] {
+            (pair[0], pair[1])
+        } else {
+            (pair[1], pair[0])
+        };
+        let (rnode, right, rtensors) = current.remove(j);
+        let (lnode, left, mut tensors) = current.remove(i);
+        let mut needed = output_indices.to_vec();
+        for (_, ids, _) in &current {
+            needed.extend_from_slice(ids);
+        }
+        let output = binary_output_ids(&left, &right, &needed);
+        tensors.extend(rtensors);
+        let node = EinsumNode::Contract {
+            args: vec![lnode, rnode],
+        };
+        current.push((node, output, tensors));
+    }
+    current
+        .into_iter()
+        .filter(|(node, _, _)| matches!(node, EinsumNode::Contract { .. }))
+        .map(|(mut root, ids, tensors)| {
+            renumber(&mut root, &tensors);
+            let code = EinsumCode {
+                root,
+                output_ids: ids,
+            };
+            (code, tensors)
+        })
+        .collect()
+}
+
+/// Point each leaf at its position in `tensors`.
+fn renumber(node: &mut EinsumNode, tensors: &[usize]) {
+    match node {
+        EinsumNode::Leaf { tensor_index, .. } => {
+            *tensor_index = tensors.iter().position(|t| t == tensor_index).unwrap();
+        }
+        EinsumNode::Contract { args } => args.iter_mut().for_each(|a| renumber(a, tensors)),
+    }
+}
+
+fn num_elements(op: &EinsumOperand) -> usize {
+    match op {
+        EinsumOperand::F64(data) => data.as_view().dims().iter().product(),
+        EinsumOperand::C64(data) => data.as_view().dims().iter().product(),
+    }
+}
+
+/// Time every prefix of `path_meta`'s path (`warmup` untimed runs, then the
+/// median of `runs`), shortest first.
+pub fn prefix_sweep(
+    instance: &BenchmarkInstance,
+    path_meta: &PathMeta,
+    warmup: usize,
+    runs: usize,
+) -> Result<Vec<PrefixTime>, EinsumError> {
+    let (input_indices, output_indices) = parse_format_string(&instance.format_string_colmajor);
+    let mut sweep = Vec::with_capacity(path_meta.path.len());
+    for k in 1..=path_meta.path.len() {
+        let forest = prefix_forest(&input_indices, &output_indices, &path_meta.path, k);
+        let run = || -> Result<(Duration, usize), EinsumError> {
+            let mut operands: Vec<Option<EinsumOperand<'static>>> =
+                create_operands(&instance.shapes_colmajor, &instance.dtype)
+                    .into_iter()
+                    .map(Some)
+                    .collect();
+            let inputs: Vec<Vec<EinsumOperand<'static>>> = forest
+                .iter()
+                .map(|(_, tensors)| {
+                    tensors
+                        .iter()
+                        .map(|&t| operands[t].take().unwrap())
+                        .collect()
+                })
+                .collect();
+            let t0 = Instant::now();
+            let mut results = Vec::with_capacity(forest.len());
+            for ((code, _), ops) in forest.iter().zip(inputs) {
+                results.push(code.evaluate(ops, None)?);
+            }
+            let elapsed = t0.elapsed();
+            let largest = black_box(&results)
+                .iter()
+                .map(num_elements)
+                .max()
+                .unwrap_or(0);
+            Ok((elapsed, largest))
+        };
+        for _ in 0..warmup {
+            run()?;
+        }
+        let mut samples = Vec::with_capacity(runs);
+        let mut largest = 0;
+        for _ in 0..runs {
+            let (d, l) = run()?;
+            samples.push(d);
+            largest = l;
+        }
+        samples.sort();
+        sweep.push(PrefixTime {
+            steps: k,
+            median: samples[samples.len() / 2],
+            largest,
+        });
+    }
+    Ok(sweep)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forest_keeps_only_built_subtrees() {
+        let (inputs, output) = parse_format_string("ab,bc,cd,de->ae");
+        let path = [[0, 1], [0, 1], [0, 1]];
+        // After one step: (T0 T1) over operands 0 and 1, labels a, c.
+        let forest = prefix_forest(&inputs, &output, &path, 1);
+        assert_eq!(forest.len(), 1);
+        assert_eq!(forest[0].1, vec![0, 1]);
+        assert_eq!(forest[0].0.output_ids, vec!['a', 'c']);
+        // The second [0, 1] contracts T2 with T3: two subtrees side by side.
+        let forest = prefix_forest(&inputs, &output, &path, 2);
+        assert_eq!(forest.len(), 2);
+        assert_eq!(forest[1].1, vec![2, 3]);
+        // The full path is one tree over every operand, renumbered.
+        let forest = prefix_forest(&inputs, &output, &path, 3);
+        assert_eq!(forest.len(), 1);
+        assert_eq!(forest[0].0.output_ids, vec!['a', 'e']);
+        assert_eq!(forest[0].1, vec![0, 1, 2, 3]);
+    }
+}
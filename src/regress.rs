@@ -0,0 +1,91 @@
+//! `--rerun-regressions`: compare against an `--output-json` baseline in two
+//! phases, so the full timing budget goes only to likely regressions.
+//!
+//! Phase 1 times every baseline instance once (one warmup, one run) and
+//! flags those slower than the baseline by more than the threshold. Phase 2
+//! re-times only the flagged ones with the regular warmup and run counts; a
+//! regression is confirmed when the full median is still over the threshold,
+//! and put down to noise otherwise.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::report;
+
+#[derive(Deserialize)]
+struct BaselineFile {
+    results: Vec<BaselineRecord>,
+}
+
+#[derive(Deserialize)]
+struct BaselineRecord {
+    instance: String,
+    strategy: String,
+    median_ms: Option<f64>,
+}
+
+/// Baseline medians keyed by `(instance, strategy)`, from an
+/// `--output-json` or `--output-bincode` file; skipped entries (no median)
+/// are left out.
+pub fn read_baseline(path: &Path) -> Result<HashMap<(String, String), f64>, String> {
+    let in_file = |e: String| format!("{}: {e}", path.display());
+    let bytes = std::fs::read(path).map_err(|e| in_file(e.to_string()))?;
+    let results: Vec<BaselineRecord> = if bytes.starts_with(report::BINCODE_MAGIC) {
+        let file = report::read_bincode(&bytes).map_err(in_file)?;
+        file.results
+            .into_iter()
+            .map(|r| BaselineRecord {
+                instance: r.instance,
+                strategy: r.strategy,
+                median_ms: r.median_ms,
+            })
+            .collect()
+    } else {
+        let file: BaselineFile =
+            serde_json::from_slice(&bytes).map_err(|e| in_file(e.to_string()))?;
+        file.results
+    };
+    Ok(results
+        .into_iter()
+        .filter_map(|r| Some(((r.instance, r.strategy), r.median_ms?)))
+        .collect())
+}
+
+/// Whether `current_ms` is slower than `baseline_ms` by more than
+/// `threshold` (a fraction: 0.1 is 10%).
+pub fn is_regression(baseline_ms: f64, current_ms: f64, threshold: f64) -> bool {
+    current_ms > baseline_ms * (1.0 + threshold)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn baseline_reads_output_json_and_drops_skips() {
+        let dir = std::env::temp_dir().join(format!("strided-bench-base-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("baseline.json");
+        std::fs::write(
+            &path,
+            r#"{"run": {"backend": "x"}, "results": [
+                {"instance": "a", "strategy": "opt_flops", "median_ms": 2.5, "q1_ms": 2.0},
+                {"instance": "b", "strategy": "opt_flops", "median_ms": null, "error": "e"}
+            ]}"#,
+        )
+        .unwrap();
+        let baseline = read_baseline(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(baseline.len(), 1);
+        assert_eq!(baseline[&("a".into(), "opt_flops".into())], 2.5);
+    }
+
+    #[test]
+    fn threshold_is_relative_to_baseline() {
+        assert!(!is_regression(10.0, 10.9, 0.1));
+        assert!(is_regression(10.0, 11.1, 0.1));
+        assert!(!is_regression(10.0, 5.0, 0.1));
+    }
+}
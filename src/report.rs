@@ -0,0 +1,379 @@
+//! JSON result files: `--output-json` (one summary object per instance and
+//! strategy) and `--output-json-samples` (the same, plus every timed run).
+//! `--output-bincode` writes the same records, samples included, in
+//! `bincode` (the `bincode` feature). `--prometheus-output` writes their
+//! timing stats in the Prometheus text format, for a node_exporter textfile
+//! collector.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// First bytes of an `--output-bincode` file, so readers can tell it from
+/// JSON.
+pub const BINCODE_MAGIC: &[u8; 8] = b"SRBRES\x00\x01";
+
+/// Run-wide context written once at the top of the file.
+#[derive(Serialize, Deserialize)]
+pub struct RunInfo {
+    pub backend: String,
+    pub label: Option<String>,
+    pub rayon_num_threads: Option<String>,
+    pub omp_num_threads: Option<String>,
+    pub timed_runs: usize,
+    /// SIMD features detected at runtime (see [`crate::cpu_features`]).
+    pub cpu_features: Vec<String>,
+}
+
+/// One (instance, strategy) result.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Record {
+    pub instance: String,
+    pub strategy: String,
+    /// The run's `--label` again, so records pooled from several files keep
+    /// their run.
+    pub label: Option<String>,
+    pub num_tensors: usize,
+    pub log10_flops: f64,
+    pub log2_size: f64,
+    /// See [`crate::tree::depth`], [`crate::tree::width`] and
+    /// [`crate::tree::balance`].
+    pub tree_depth: usize,
+    pub tree_width: usize,
+    pub tree_balance: Option<f64>,
+    /// Timing stats in milliseconds; `None` when the instance was skipped.
+    pub median_ms: Option<f64>,
+    pub q1_ms: Option<f64>,
+    pub q3_ms: Option<f64>,
+    pub iqr_ms: Option<f64>,
+    pub mean_ms: Option<f64>,
+    pub min_ms: Option<f64>,
+    pub max_ms: Option<f64>,
+    /// `10^log10_flops` over the median, unscaled.
+    pub flops_per_second: Option<f64>,
+    /// See [`crate::reproducibility`].
+    pub reproducibility: Option<f64>,
+    pub warmup_runs: Option<usize>,
+    /// Timed runs behind the stats (a per-strategy `--runs` may differ from
+    /// the run block's `timed_runs`).
+    pub timed_runs: Option<usize>,
+    /// Peak heap growth of one evaluation (`--track-memory`).
+    pub peak_bytes: Option<usize>,
+    /// Backend error for skipped instances.
+    pub error: Option<String>,
+    /// Every timed run in execution order, in nanoseconds. Only written by
+    /// `--output-json-samples` and `--output-bincode`; the latter always
+    /// writes `Some`, since bincode can't mark a field as left out.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub samples_ns: Option<Vec<u64>>,
+}
+
+impl Record {
+    /// Fill the derived stats from `samples_ns` (median and quartiles use
+    /// the same indices as the printed table).
+    pub fn with_samples(mut self, samples_ns: Vec<u64>) -> Self {
+        let mut sorted = samples_ns.clone();
+        sorted.sort_unstable();
+        if let (Some(&min), Some(&max)) = (sorted.first(), sorted.last()) {
+            let ms = |ns: u64| ns as f64 / 1e6;
+            let n = sorted.len();
+            let (q1, q3) = (ms(sorted[n / 4]), ms(sorted[3 * n / 4]));
+            self.median_ms = Some(ms(sorted[n / 2]));
+            self.flops_per_second = Some(10f64.powf(self.log10_flops) / (ms(sorted[n / 2]) * 1e-3));
+            self.q1_ms = Some(q1);
+            self.q3_ms = Some(q3);
+            self.iqr_ms = Some(q3 - q1);
+            self.reproducibility = Some(crate::reproducibility(ms(sorted[n / 2]), q3 - q1));
+            self.mean_ms = Some(sorted.iter().sum::<u64>() as f64 / n as f64 / 1e6);
+            self.min_ms = Some(ms(min));
+            self.max_ms = Some(ms(max));
+        }
+        self.timed_runs = Some(samples_ns.len());
+        self.samples_ns = Some(samples_ns);
+        self
+    }
+}
+
+/// An instance, or one strategy of it, that wasn't timed, and why (the
+/// reason its row is tagged `[SKIPPED: ...]` with).
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Skipped {
+    pub instance: String,
+    /// `None` for a file skipped at load time, before any strategy.
+    pub strategy: Option<String>,
+    pub reason: String,
+}
+
+#[derive(Serialize)]
+struct File<'a> {
+    run: &'a RunInfo,
+    results: Vec<Record>,
+    /// Only in the JSON outputs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    skipped: Option<&'a [Skipped]>,
+}
+
+/// [`File`] as read back. The readers so far only look at `results`.
+#[allow(dead_code)]
+#[derive(Deserialize)]
+pub struct OwnedFile {
+    pub run: RunInfo,
+    pub results: Vec<Record>,
+}
+
+/// Write `records` and `skipped` under `run`; `samples` keeps the per-run
+/// `samples_ns`.
+pub fn write(
+    path: &Path,
+    run: &RunInfo,
+    records: &[Record],
+    skipped: &[Skipped],
+    samples: bool,
+) -> Result<(), String> {
+    let results: Vec<Record> = records
+        .iter()
+        .map(|r| Record {
+            samples_ns: if samples { r.samples_ns.clone() } else { None },
+            ..r.clone()
+        })
+        .collect();
+    let file = File {
+        run,
+        results,
+        skipped: Some(skipped),
+    };
+    let json = serde_json::to_string_pretty(&file).map_err(|e| e.to_string())?;
+    std::fs::write(path, json + "\n").map_err(|e| format!("write {}: {e}", path.display()))
+}
+
+/// Write `records` under `run` as [`BINCODE_MAGIC`] followed by the bincode
+/// encoding of the same file structure, samples included.
+#[cfg(feature = "bincode")]
+pub fn write_bincode(path: &Path, run: &RunInfo, records: &[Record]) -> Result<(), String> {
+    let results: Vec<Record> = records
+        .iter()
+        .map(|r| Record {
+            samples_ns: Some(r.samples_ns.clone().unwrap_or_default()),
+            ..r.clone()
+        })
+        .collect();
+    let mut bytes = BINCODE_MAGIC.to_vec();
+    let file = File {
+        run,
+        results,
+        skipped: None,
+    };
+    bincode::serialize_into(&mut bytes, &file).map_err(|e| e.to_string())?;
+    std::fs::write(path, bytes).map_err(|e| format!("write {}: {e}", path.display()))
+}
+
+#[cfg(not(feature = "bincode"))]
+pub fn write_bincode(_path: &Path, _run: &RunInfo, _records: &[Record]) -> Result<(), String> {
+    Err(NO_BINCODE.into())
+}
+
+/// Decode an `--output-bincode` file's contents, magic included.
+#[cfg(feature = "bincode")]
+pub fn read_bincode(bytes: &[u8]) -> Result<OwnedFile, String> {
+    let payload = bytes
+        .strip_prefix(BINCODE_MAGIC)
+        .ok_or("not an --output-bincode file")?;
+    bincode::deserialize(payload).map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "bincode"))]
+pub fn read_bincode(_bytes: &[u8]) -> Result<OwnedFile, String> {
+    Err(NO_BINCODE.into())
+}
+
+#[cfg(not(feature = "bincode"))]
+const NO_BINCODE: &str = "built without bincode support (rebuild with --features bincode)";
+
+/// A Prometheus metric: name, help text and the record field it reads.
+type Metric = (&'static str, &'static str, fn(&Record) -> Option<f64>);
+
+/// The metrics written by [`write_prometheus`].
+const METRICS: &[Metric] = &[
+    (
+        "einsum_median_ms",
+        "Median time of one contraction in milliseconds.",
+        |r| r.median_ms,
+    ),
+    (
+        "einsum_iqr_ms",
+        "Interquartile range of the timed runs in milliseconds.",
+        |r| r.iqr_ms,
+    ),
+    ("einsum_min_ms", "Fastest timed run in milliseconds.", |r| {
+        r.min_ms
+    }),
+    (
+        "einsum_log10_flops",
+        "log10 of the contraction path's FLOP count.",
+        |r| Some(r.log10_flops),
+    ),
+];
+
+/// A label value with `\`, `"` and newlines escaped.
+fn label_value(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// `records` in the Prometheus text exposition format, one gauge sample per
+/// metric and timed record; instances that were skipped are left out.
+pub fn prometheus_text(run: &RunInfo, records: &[Record]) -> String {
+    let mut out = String::new();
+    for (name, help, value) in METRICS {
+        out += &format!("# HELP {name} {help}\n# TYPE {name} gauge\n");
+        for r in records.iter().filter(|r| r.median_ms.is_some()) {
+            let Some(v) = value(r) else { continue };
+            let mut labels = format!(
+                "instance=\"{}\",strategy=\"{}\",backend=\"{}\"",
+                label_value(&r.instance),
+                label_value(&r.strategy),
+                label_value(&run.backend)
+            );
+            if let Some(label) = &r.label {
+                labels += &format!(",label=\"{}\"", label_value(label));
+            }
+            out += &format!("{name}{{{labels}}} {v}\n");
+        }
+    }
+    out
+}
+
+/// Write [`prometheus_text`] to `path` through a temporary file renamed into
+/// place, so a collector never scrapes a half-written file.
+pub fn write_prometheus(path: &Path, run: &RunInfo, records: &[Record]) -> Result<(), String> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    std::fs::write(&tmp, prometheus_text(run, records))
+        .and_then(|()| std::fs::rename(&tmp, path))
+        .map_err(|e| format!("write {}: {e}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record() -> Record {
+        Record {
+            instance: "x".into(),
+            strategy: "opt_flops".into(),
+            label: None,
+            num_tensors: 2,
+            log10_flops: 3.0,
+            log2_size: 4.0,
+            tree_depth: 1,
+            tree_width: 1,
+            tree_balance: Some(1.0),
+            median_ms: None,
+            q1_ms: None,
+            q3_ms: None,
+            iqr_ms: None,
+            mean_ms: None,
+            min_ms: None,
+            max_ms: None,
+            flops_per_second: None,
+            reproducibility: None,
+            warmup_runs: None,
+            timed_runs: None,
+            peak_bytes: None,
+            error: None,
+            samples_ns: None,
+        }
+    }
+
+    #[test]
+    fn stats_derive_from_samples() {
+        let r = record().with_samples(vec![4_000_000, 1_000_000, 3_000_000, 2_000_000]);
+        assert_eq!(r.median_ms, Some(3.0));
+        assert_eq!(r.q1_ms, Some(2.0));
+        assert_eq!(r.q3_ms, Some(4.0));
+        assert_eq!(r.mean_ms, Some(2.5));
+        assert_eq!(r.min_ms, Some(1.0));
+        assert_eq!(r.timed_runs, Some(4));
+        // 10^3 FLOPs in 3 ms.
+        assert!((r.flops_per_second.unwrap() - 1e3 / 3e-3).abs() < 1e-6);
+        // Samples stay in run order.
+        assert_eq!(
+            r.samples_ns.as_deref(),
+            Some(&[4_000_000, 1_000_000, 3_000_000, 2_000_000][..])
+        );
+    }
+
+    #[test]
+    fn samples_are_omitted_from_flat_output() {
+        let r = record().with_samples(vec![1, 2, 3]);
+        let flat = serde_json::to_value(Record {
+            samples_ns: None,
+            ..r.clone()
+        })
+        .unwrap();
+        assert!(flat.get("samples_ns").is_none());
+        let nested = serde_json::to_value(r).unwrap();
+        assert_eq!(nested["samples_ns"], serde_json::json!([1, 2, 3]));
+    }
+
+    #[test]
+    fn prometheus_text_has_one_sample_per_timed_record() {
+        let run = RunInfo {
+            backend: "strided-opteinsum(faer)".into(),
+            label: None,
+            rayon_num_threads: None,
+            omp_num_threads: None,
+            timed_runs: 3,
+            cpu_features: Vec::new(),
+        };
+        let timed = Record {
+            instance: "a\"b".into(),
+            label: Some("abc".into()),
+            ..record()
+        }
+        .with_samples(vec![1_000_000, 2_000_000, 4_000_000]);
+        let text = prometheus_text(&run, &[timed, record()]);
+        assert!(text.starts_with("# HELP einsum_median_ms "));
+        assert!(text.contains("# TYPE einsum_median_ms gauge\n"));
+        assert!(text.contains(
+            "einsum_median_ms{instance=\"a\\\"b\",strategy=\"opt_flops\",\
+             backend=\"strided-opteinsum(faer)\",label=\"abc\"} 2\n"
+        ));
+        assert!(text.contains("label=\"abc\"} 1\n"), "{text}");
+        // The skipped record has no samples at all.
+        assert_eq!(
+            text.lines().filter(|l| !l.starts_with('#')).count(),
+            METRICS.len()
+        );
+    }
+
+    #[cfg(feature = "bincode")]
+    #[test]
+    fn bincode_round_trips_skipped_and_timed_records() {
+        let run = RunInfo {
+            backend: "faer".into(),
+            label: Some("abc".into()),
+            rayon_num_threads: None,
+            omp_num_threads: Some("1".into()),
+            timed_runs: 3,
+            cpu_features: vec!["avx2".into()],
+        };
+        let skipped = Record {
+            error: Some("e".into()),
+            ..record()
+        };
+        let records = [record().with_samples(vec![1, 2, 3]), skipped];
+        let path = std::env::temp_dir().join(format!("strided-bench-{}.bin", std::process::id()));
+        write_bincode(&path, &run, &records).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let file = read_bincode(&bytes).unwrap();
+        assert_eq!(file.run.label.as_deref(), Some("abc"));
+        assert_eq!(file.results.len(), 2);
+        assert_eq!(file.results[0].median_ms, Some(2e-6));
+        assert_eq!(file.results[0].samples_ns.as_deref(), Some(&[1, 2, 3][..]));
+        assert_eq!(file.results[1].error.as_deref(), Some("e"));
+        assert!(read_bincode(b"{}").is_err());
+    }
+}
@@ -0,0 +1,179 @@
+//! Operands stored as views into one shared buffer.
+//!
+//! In real networks several tensors are often slices of one large array. An
+//! instance with `buffer_size` and `views` allocates one zeroed buffer of
+//! `buffer_size` elements per [`crate::run_instance`] call, and each run
+//! views its operands into it instead of allocating every operand on its
+//! own. Each view gives the element offset of the operand's first element
+//! and the strides of its column-major dims (in elements; negative strides
+//! are allowed).
+
+use num_complex::Complex64;
+use serde::Deserialize;
+use strided_opteinsum::{EinsumOperand, StridedData};
+use strided_view::StridedView;
+
+/// Where one operand sits in the shared buffer.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ViewLayout {
+    pub offset: usize,
+    pub strides: Vec<isize>,
+}
+
+/// Lowest and highest element a view of `dims` touches.
+fn extent(dims: &[usize], view: &ViewLayout) -> (isize, isize) {
+    let (mut lo, mut hi) = (view.offset as isize, view.offset as isize);
+    for (&d, &s) in dims.iter().zip(&view.strides) {
+        let reach = (d as isize - 1) * s;
+        if reach < 0 {
+            lo += reach;
+        } else {
+            hi += reach;
+        }
+    }
+    (lo, hi)
+}
+
+/// Every element a view of `dims` touches, in column-major order.
+fn elements(dims: &[usize], view: &ViewLayout) -> Vec<isize> {
+    let mut out = vec![view.offset as isize];
+    for (&d, &s) in dims.iter().zip(&view.strides) {
+        out = (0..d as isize)
+            .flat_map(|i| out.iter().map(move |&e| e + i * s))
+            .collect();
+    }
+    out
+}
+
+/// Check that there is one view per operand, of the operand's rank, inside
+/// `0..buffer_size`, and that no two views share an element. A view may
+/// reach one element more than once (a zero stride).
+pub fn validate(
+    buffer_size: usize,
+    views: &[ViewLayout],
+    shapes: &[Vec<usize>],
+) -> Result<(), String> {
+    if views.len() != shapes.len() {
+        return Err(format!(
+            "{} views for {} operands",
+            views.len(),
+            shapes.len()
+        ));
+    }
+    let mut spans = Vec::with_capacity(views.len());
+    for (t, (view, dims)) in views.iter().zip(shapes).enumerate() {
+        if view.strides.len() != dims.len() {
+            return Err(format!(
+                "view {t} has {} strides for a rank-{} operand",
+                view.strides.len(),
+                dims.len()
+            ));
+        }
+        let (lo, hi) = extent(dims, view);
+        if lo < 0 || hi >= buffer_size as isize {
+            return Err(format!(
+                "view {t} spans elements {lo}..={hi}, outside the buffer of {buffer_size}"
+            ));
+        }
+        spans.push((lo, hi, t));
+    }
+    // Views over disjoint ranges can't overlap. Only when some ranges do
+    // (e.g. interleaved views) are the elements themselves compared.
+    spans.sort_unstable();
+    if spans.windows(2).all(|w| w[0].1 < w[1].0) {
+        return Ok(());
+    }
+    let mut owner: Vec<Option<usize>> = vec![None; buffer_size];
+    for (t, (view, dims)) in views.iter().zip(shapes).enumerate() {
+        for e in elements(dims, view) {
+            match owner[e as usize].replace(t) {
+                Some(other) if other != t => {
+                    return Err(format!("views {other} and {t} share element {e}"));
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The zeroed buffer every view points into.
+pub enum Buffer {
+    F64(Vec<f64>),
+    C64(Vec<Complex64>),
+}
+
+impl Buffer {
+    pub fn zeroed(size: usize, dtype: &str) -> Self {
+        match dtype {
+            "float64" => Buffer::F64(vec![0.0; size]),
+            "complex128" => Buffer::C64(vec![Complex64::default(); size]),
+            other => panic!("unsupported dtype: {other}"),
+        }
+    }
+
+    /// One operand per view; `views` must have passed [`validate`].
+    pub fn operands(&self, views: &[ViewLayout], shapes: &[Vec<usize>]) -> Vec<EinsumOperand<'_>> {
+        fn view<'a, T>(data: &'a [T], dims: &[usize], layout: &ViewLayout) -> StridedView<'a, T> {
+            StridedView::new(data, dims, &layout.strides, layout.offset as isize)
+                .expect("views are validated when the instance is loaded")
+        }
+        views
+            .iter()
+            .zip(shapes)
+            .map(|(layout, dims)| match self {
+                Buffer::F64(data) => {
+                    EinsumOperand::F64(StridedData::View(view(data, dims, layout)))
+                }
+                Buffer::C64(data) => {
+                    EinsumOperand::C64(StridedData::View(view(data, dims, layout)))
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compare;
+
+    fn layout(offset: usize, strides: &[isize]) -> ViewLayout {
+        ViewLayout {
+            offset,
+            strides: strides.to_vec(),
+        }
+    }
+
+    #[test]
+    fn views_must_fit_the_buffer_and_not_overlap() {
+        let shapes = vec![vec![2, 3], vec![3]];
+        validate(9, &[layout(0, &[1, 2]), layout(6, &[1])], &shapes).unwrap();
+        // Interleaved columns: overlapping ranges, disjoint elements.
+        let pair = vec![vec![2, 2], vec![2, 2]];
+        validate(8, &[layout(0, &[1, 4]), layout(2, &[1, 4])], &pair).unwrap();
+        // A negative stride walks back from the offset.
+        validate(3, &[layout(2, &[-1])], &[vec![3]]).unwrap();
+
+        let err = validate(8, &[layout(0, &[1, 2]), layout(6, &[1])], &shapes).unwrap_err();
+        assert!(err.contains("outside the buffer of 8"), "{err}");
+        let err = validate(3, &[layout(1, &[-1])], &[vec![3]]).unwrap_err();
+        assert!(err.contains("-1..=1"), "{err}");
+        let err = validate(9, &[layout(0, &[1, 2]), layout(5, &[1])], &shapes).unwrap_err();
+        assert_eq!(err, "views 0 and 1 share element 5");
+        assert!(validate(9, &[layout(0, &[1])], &shapes).is_err());
+        assert!(validate(9, &[layout(0, &[1]), layout(6, &[1])], &shapes).is_err());
+    }
+
+    #[test]
+    fn operands_read_their_slice_of_the_buffer() {
+        let buffer = Buffer::F64((0..8).map(f64::from).collect());
+        let shapes = vec![vec![2, 2], vec![2, 2]];
+        let views = [layout(0, &[1, 4]), layout(2, &[1, 4])];
+        let operands = buffer.operands(&views, &shapes);
+        let EinsumOperand::F64(second) = &operands[1] else {
+            unreachable!()
+        };
+        assert_eq!(compare::dense_col_major(second), vec![2.0, 3.0, 6.0, 7.0]);
+    }
+}
@@ -0,0 +1,137 @@
+//! `--sqlite <PATH>`: append every result to a SQLite table for tracking
+//! performance across commits.
+//!
+//! `rusqlite` is only linked with the `sqlite` feature; without it,
+//! [`ResultDb::open`] fails with a hint to rebuild.
+
+pub use imp::ResultDb;
+
+#[cfg(feature = "sqlite")]
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS results (
+    id          INTEGER PRIMARY KEY,
+    timestamp   INTEGER NOT NULL,  -- unix seconds
+    label       TEXT,              -- --label, e.g. a git hash
+    backend     TEXT NOT NULL,
+    instance    TEXT NOT NULL,
+    strategy    TEXT NOT NULL,
+    num_tensors INTEGER NOT NULL,
+    log10_flops REAL NOT NULL,
+    log2_size   REAL NOT NULL,
+    median_ms   REAL,              -- NULL when skipped
+    q1_ms       REAL,
+    q3_ms       REAL,
+    rayon_threads TEXT,
+    omp_threads TEXT,
+    error       TEXT               -- backend error for skipped rows
+);
+CREATE INDEX IF NOT EXISTS results_instance ON results (instance, strategy, timestamp);
+";
+
+/// One row of the `results` table.
+#[cfg_attr(not(feature = "sqlite"), allow(dead_code))]
+pub struct Row<'a> {
+    pub instance: &'a str,
+    pub strategy: &'a str,
+    pub num_tensors: usize,
+    pub log10_flops: f64,
+    pub log2_size: f64,
+    /// `(median, q1, q3)` in milliseconds, `None` for skipped instances.
+    pub timing: Option<(f64, f64, f64)>,
+    pub error: Option<String>,
+}
+
+/// Values shared by every row of one run.
+#[cfg_attr(not(feature = "sqlite"), allow(dead_code))]
+pub struct RunInfo {
+    pub label: Option<String>,
+    pub backend: &'static str,
+    pub rayon_threads: Option<String>,
+    pub omp_threads: Option<String>,
+}
+
+#[cfg(feature = "sqlite")]
+mod imp {
+    use std::path::Path;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use rusqlite::{params, Connection};
+
+    use super::{Row, RunInfo, SCHEMA};
+
+    pub struct ResultDb {
+        conn: Connection,
+        run: RunInfo,
+        timestamp: i64,
+    }
+
+    impl ResultDb {
+        /// Open (or create) the database at `path` and ensure the table exists.
+        pub fn open(path: &Path, run: RunInfo) -> Result<Self, String> {
+            let conn = Connection::open(path).map_err(|e| format!("{}: {e}", path.display()))?;
+            conn.execute_batch(SCHEMA)
+                .map_err(|e| format!("{}: create table: {e}", path.display()))?;
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or(0);
+            Ok(ResultDb {
+                conn,
+                run,
+                timestamp,
+            })
+        }
+
+        /// Insert one result. Each insert commits on its own, so an interrupted
+        /// run keeps the rows it already produced.
+        pub fn insert(&self, row: &Row) -> Result<(), String> {
+            let (median, q1, q3) = match row.timing {
+                Some((m, a, b)) => (Some(m), Some(a), Some(b)),
+                None => (None, None, None),
+            };
+            self.conn
+            .execute(
+                "INSERT INTO results (timestamp, label, backend, instance, strategy, num_tensors,
+                     log10_flops, log2_size, median_ms, q1_ms, q3_ms, rayon_threads, omp_threads, error)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+                params![
+                    self.timestamp,
+                    self.run.label,
+                    self.run.backend,
+                    row.instance,
+                    row.strategy,
+                    row.num_tensors as i64,
+                    row.log10_flops,
+                    row.log2_size,
+                    median,
+                    q1,
+                    q3,
+                    self.run.rayon_threads,
+                    self.run.omp_threads,
+                    row.error,
+                ],
+            )
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+        }
+    }
+}
+
+#[cfg(not(feature = "sqlite"))]
+mod imp {
+    use std::path::Path;
+
+    use super::{Row, RunInfo};
+
+    pub enum ResultDb {}
+
+    impl ResultDb {
+        pub fn open(_path: &Path, _run: RunInfo) -> Result<Self, String> {
+            Err("built without SQLite support (rebuild with --features sqlite)".into())
+        }
+
+        pub fn insert(&self, _row: &Row) -> Result<(), String> {
+            match *self {}
+        }
+    }
+}
@@ -0,0 +1,213 @@
+//! Per-step traced evaluation.
+//!
+//! Instead of one `EinsumCode::evaluate` over the whole tree, each path step
+//! is evaluated as its own binary contraction so it can be timed separately.
+//! Intermediates use the same label order as the tree evaluator, so the
+//! kernels and copies are the ones the full run performs.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use strided_einsum2::contiguous::prepare_input_owned;
+use strided_einsum2::{Einsum2Plan, ScalarBase};
+use strided_opteinsum::{EinsumCode, EinsumError, EinsumNode, EinsumOperand, StridedData};
+
+use crate::path::binary_output_ids;
+use crate::{create_operands, parse_format_string, BenchmarkInstance, PathMeta};
+
+/// Evaluate `path_meta` step by step once, returning one duration per step.
+pub fn run_steps(
+    instance: &BenchmarkInstance,
+    path_meta: &PathMeta,
+) -> Result<Vec<Duration>, EinsumError> {
+    walk_steps(instance, path_meta, |_, _, _, _, _| {})
+}
+
+/// Evaluate `path_meta` step by step, calling `probe` with each step's
+/// operands, labels, and output labels before it is timed.
+fn walk_steps(
+    instance: &BenchmarkInstance,
+    path_meta: &PathMeta,
+    mut probe: impl FnMut(&EinsumOperand, &[char], &EinsumOperand, &[char], &[char]),
+) -> Result<Vec<Duration>, EinsumError> {
+    let (input_indices, output_indices) = parse_format_string(&instance.format_string_colmajor);
+    let operands = create_operands(&instance.shapes_colmajor, &instance.dtype);
+    let mut current: Vec<(EinsumOperand<'static>, Vec<char>)> =
+        operands.into_iter().zip(input_indices).collect();
+
+    let mut durations = Vec::with_capacity(path_meta.path.len());
+    for &pair in &path_meta.path {
+        let (i, j) = if pair[0] < pair[1] {
+            (pair[0], pair[1])
+        } else {
+            (pair[1], pair[0])
+        };
+        let (rhs, right) = current.remove(j);
+        let (lhs, left) = current.remove(i);
+        let mut needed = output_indices.clone();
+        for (_, ids) in &current {
+            needed.extend_from_slice(ids);
+        }
+        let output = binary_output_ids(&left, &right, &needed);
+        probe(&lhs, &left, &rhs, &right, &output);
+        let code = EinsumCode {
+            root: EinsumNode::Contract {
+                args: vec![
+                    EinsumNode::Leaf {
+                        ids: left,
+                        tensor_index: 0,
+                    },
+                    EinsumNode::Leaf {
+                        ids: right,
+                        tensor_index: 1,
+                    },
+                ],
+            },
+            output_ids: output.clone(),
+        };
+
+        let t0 = Instant::now();
+        let result = code.evaluate(vec![lhs, rhs], None)?;
+        durations.push(t0.elapsed());
+        current.push((black_box(result), output));
+    }
+    Ok(durations)
+}
+
+/// Fraction of one traced run spent making step inputs GEMM-contiguous.
+///
+/// For each step the operands are permuted to the `[lo, sum, batch]` /
+/// `[sum, ro, batch]` order einsum2 uses and passed through
+/// `prepare_input_owned`, the routine the contraction itself calls, so only
+/// inputs it would actually copy are charged. A single traced pass keeps
+/// this cheap enough for whole-dataset runs.
+pub fn copy_tax(instance: &BenchmarkInstance, path_meta: &PathMeta) -> Result<f64, EinsumError> {
+    let mut copy = Duration::ZERO;
+    let steps = walk_steps(instance, path_meta, |lhs, left, rhs, right, output| {
+        copy += match (lhs, rhs) {
+            (EinsumOperand::F64(a), EinsumOperand::F64(b)) => {
+                prepare_time(a, left, b, right, output)
+            }
+            (EinsumOperand::C64(a), EinsumOperand::C64(b)) => {
+                prepare_time(a, left, b, right, output)
+            }
+            // Mixed dtypes are promoted first; that's not a permutation copy.
+            _ => Duration::ZERO,
+        };
+    })?;
+    let total: Duration = steps.iter().sum();
+    Ok(if total.is_zero() {
+        0.0
+    } else {
+        copy.as_secs_f64() / total.as_secs_f64()
+    })
+}
+
+/// Time spent preparing both operands of one step for GEMM.
+fn prepare_time<T: ScalarBase + 'static>(
+    a: &StridedData<T>,
+    left: &[char],
+    b: &StridedData<T>,
+    right: &[char],
+    output: &[char],
+) -> Duration {
+    let Ok(plan) = Einsum2Plan::new(left, right, output) else {
+        return Duration::ZERO;
+    };
+    prepare_operand_time(a, &plan.left_perm, plan.lo.len(), plan.sum.len())
+        + prepare_operand_time(b, &plan.right_perm, plan.sum.len(), plan.ro.len())
+}
+
+/// Time `prepare_input_owned` spends on one operand permuted by `perm`,
+/// whose first `g1` and next `g2` axes are fused into the GEMM's rows and
+/// columns; zero when it needs no copy.
+pub fn prepare_operand_time<T: ScalarBase + 'static>(
+    data: &StridedData<T>,
+    perm: &[usize],
+    g1: usize,
+    g2: usize,
+) -> Duration {
+    // Operands with labels summed away before the GEMM (traces, or axes
+    // only one side carries) are reduced first and never copied as-is.
+    let StridedData::Owned(arr) = data else {
+        return Duration::ZERO;
+    };
+    if perm.len() != arr.ndim() {
+        return Duration::ZERO;
+    }
+    let Ok(arr) = arr.clone().permuted(perm) else {
+        return Duration::ZERO;
+    };
+    let t0 = Instant::now();
+    black_box(prepare_input_owned(arr, g1, g2, false, REQUIRES_UNIT_STRIDE, false, None).ok());
+    t0.elapsed()
+}
+
+/// Whether the GEMM backend needs a unit stride in one fused dimension.
+const REQUIRES_UNIT_STRIDE: bool = cfg!(feature = "blas");
+
+/// Per-step timing samples collected across repeated traced runs.
+pub struct StepSamples {
+    /// `ms[step][run]`
+    pub ms: Vec<Vec<f64>>,
+}
+
+impl StepSamples {
+    pub fn collect(
+        instance: &BenchmarkInstance,
+        path_meta: &PathMeta,
+        warmup: usize,
+        runs: usize,
+    ) -> Result<Self, EinsumError> {
+        for _ in 0..warmup {
+            run_steps(instance, path_meta)?;
+        }
+        let mut ms = vec![Vec::with_capacity(runs); path_meta.path.len()];
+        for _ in 0..runs {
+            for (step, d) in run_steps(instance, path_meta)?.into_iter().enumerate() {
+                ms[step].push(d.as_secs_f64() * 1e3);
+            }
+        }
+        Ok(StepSamples { ms })
+    }
+
+    /// `(mean, variance)` of each step's samples.
+    fn moments(&self) -> Vec<(f64, f64)> {
+        self.ms
+            .iter()
+            .map(|samples| {
+                let n = samples.len() as f64;
+                let mean = samples.iter().sum::<f64>() / n;
+                let var = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
+                (mean, var)
+            })
+            .collect()
+    }
+
+    /// The step contributing most to run-to-run variance, as
+    /// `(step, coefficient of variation, share of summed step variance)`.
+    ///
+    /// Ranking by variance rather than CV keeps a jittery but negligible
+    /// step from hiding the one that actually moves the total.
+    pub fn noisiest(&self) -> Option<(usize, f64, f64)> {
+        let moments = self.moments();
+        let total_var: f64 = moments.iter().map(|&(_, v)| v).sum();
+        let (step, &(mean, var)) = moments
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1 .1.total_cmp(&b.1 .1))?;
+        let cv = if mean > 0.0 { var.sqrt() / mean } else { 0.0 };
+        let share = if total_var > 0.0 {
+            var / total_var
+        } else {
+            0.0
+        };
+        Some((step, cv, share))
+    }
+
+    pub fn median(&self, step: usize) -> f64 {
+        let mut s = self.ms[step].clone();
+        s.sort_by(|a, b| a.total_cmp(b));
+        s[s.len() / 2]
+    }
+}
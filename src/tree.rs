@@ -0,0 +1,385 @@
+//! Building an `EinsumNode` contraction tree from a path, and structural
+//! analysis of the result.
+
+use std::collections::HashMap;
+
+use strided_opteinsum::EinsumNode;
+
+/// Convert a flat contraction path (list of index pairs) into a nested EinsumNode tree.
+///
+/// Path convention (opt_einsum / cotengra):
+/// - Each step [i, j] refers to the current list of tensors
+/// - Remove higher index first, then lower; contract; append result to end
+///
+/// Each `Contract` gets the tensor at the lower position of the current
+/// list as `args[0]`, the left operand of the binary einsum, whichever way
+/// round the pair is written: `[2, 0]` builds the same node as `[0, 2]`.
+/// Inputs keep their relative order and intermediates join at the end, so
+/// an input is always left of any intermediate, and of two intermediates
+/// the older one is left. `path::simulate` replays steps the same
+/// way. Which operand is left decides the permutations the GEMM needs, so
+/// swapping them can change a step's copy pattern and its time.
+///
+/// Nodes stay in fixed slots (inputs first, then one per step) and removal
+/// only marks a slot dead, so no step shifts the list. A [`LiveSlots`] count
+/// maps a position in the current list to its slot in O(log n).
+pub fn build_contraction_tree(input_indices: &[Vec<char>], path: &[[usize; 2]]) -> EinsumNode {
+    let mut slots: Vec<Option<EinsumNode>> = Vec::with_capacity(input_indices.len() + path.len());
+    slots.extend(input_indices.iter().enumerate().map(|(i, ids)| {
+        Some(EinsumNode::Leaf {
+            ids: ids.clone(),
+            tensor_index: i,
+        })
+    }));
+    let mut live = LiveSlots::new(input_indices.len() + path.len(), input_indices.len());
+    let mut count = input_indices.len();
+
+    for &pair in path {
+        let (i, j) = if pair[0] < pair[1] {
+            (pair[0], pair[1])
+        } else {
+            (pair[1], pair[0])
+        };
+        assert!(
+            i != j && j < count,
+            "path step {pair:?} invalid for {count} tensors"
+        );
+        // Both positions are read before either removal: i < j, so removing
+        // j first leaves i where it was.
+        let (slot_i, slot_j) = (live.find(i), live.find(j));
+        live.remove(slot_j);
+        live.remove(slot_i);
+        let node_j = slots[slot_j].take().unwrap();
+        let node_i = slots[slot_i].take().unwrap();
+        live.insert(slots.len());
+        slots.push(Some(EinsumNode::Contract {
+            args: vec![node_i, node_j],
+        }));
+        count -= 1;
+    }
+
+    assert_eq!(count, 1, "contraction path should reduce to a single node");
+    slots.into_iter().flatten().next().unwrap()
+}
+
+/// Fenwick tree over node slots, each counting 1 while its node is in the
+/// current list: the `k`-th entry of the list is the slot where the prefix
+/// count reaches `k + 1`.
+struct LiveSlots {
+    /// 1-based partial sums.
+    tree: Vec<usize>,
+}
+
+impl LiveSlots {
+    /// `len` slots, the first `live` of them in the list.
+    fn new(len: usize, live: usize) -> Self {
+        let mut tree = vec![0; len + 1];
+        tree[1..=live].fill(1);
+        for i in 1..=len {
+            let parent = i + (i & i.wrapping_neg());
+            if parent <= len {
+                tree[parent] += tree[i];
+            }
+        }
+        LiveSlots { tree }
+    }
+
+    fn insert(&mut self, slot: usize) {
+        let mut i = slot + 1;
+        while i < self.tree.len() {
+            self.tree[i] += 1;
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    fn remove(&mut self, slot: usize) {
+        let mut i = slot + 1;
+        while i < self.tree.len() {
+            self.tree[i] -= 1;
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    /// Slot of the `k`-th (0-based) live entry.
+    fn find(&self, k: usize) -> usize {
+        let len = self.tree.len() - 1;
+        let mut pos = 0;
+        let mut rem = k + 1;
+        let mut step = if len == 0 { 0 } else { 1 << len.ilog2() };
+        while step > 0 {
+            if pos + step <= len && self.tree[pos + step] < rem {
+                pos += step;
+                rem -= self.tree[pos];
+            }
+            step >>= 1;
+        }
+        pos
+    }
+}
+
+/// Level of each path step's contraction in the tree built from `path`:
+/// 0 for the root (the last step), 1 for the steps feeding it, and so on.
+/// Same list bookkeeping as [`build_contraction_tree`], without the nodes.
+pub fn step_levels(num_inputs: usize, path: &[[usize; 2]]) -> Vec<usize> {
+    // Slot contents: `Some(step)` for an intermediate, `None` for an input.
+    let mut slots: Vec<Option<usize>> = vec![None; num_inputs];
+    let mut live = LiveSlots::new(num_inputs + path.len(), num_inputs);
+    let mut parent = vec![usize::MAX; path.len()];
+    for (step, &pair) in path.iter().enumerate() {
+        let (i, j) = (pair[0].min(pair[1]), pair[0].max(pair[1]));
+        let (slot_i, slot_j) = (live.find(i), live.find(j));
+        live.remove(slot_j);
+        live.remove(slot_i);
+        for child in [slots[slot_i], slots[slot_j]].into_iter().flatten() {
+            parent[child] = step;
+        }
+        live.insert(slots.len());
+        slots.push(Some(step));
+    }
+    // A parent always comes later in the path, so walking backwards sees it
+    // first.
+    let mut levels = vec![0; path.len()];
+    for step in (0..path.len()).rev() {
+        if parent[step] != usize::MAX {
+            levels[step] = levels[parent[step]] + 1;
+        }
+    }
+    levels
+}
+
+/// Number of `Contract` subtrees that repeat an identical subtree elsewhere
+/// in the tree: same shape, same labels, same `tensor_index` leaves. These
+/// are what common subexpression elimination (`--cse`) would compute once.
+///
+/// A tree built from a contraction path uses every tensor exactly once, so
+/// two such subtrees can only coincide by reading the same operand twice;
+/// for the dataset this is always 0.
+pub fn shared_subtrees(root: &EinsumNode) -> usize {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    key(root, &mut seen);
+    seen.values().map(|&n| n - 1).sum()
+}
+
+/// Longest root-to-leaf path, counted in `Contract` nodes (a lone leaf is
+/// depth 0, one pairwise contraction depth 1).
+pub fn depth(node: &EinsumNode) -> usize {
+    match node {
+        EinsumNode::Leaf { .. } => 0,
+        EinsumNode::Contract { args } => 1 + args.iter().map(depth).max().unwrap_or(0),
+    }
+}
+
+/// Depth over the least depth any binary tree on the same leaves could
+/// have, `ceil(log2(leaves))`: 1 for a perfectly balanced tree, up to
+/// `(n - 1) / ceil(log2(n))` for a chain of `n` tensors. `None` for a lone
+/// leaf, which has no contraction to balance.
+pub fn balance(node: &EinsumNode) -> Option<f64> {
+    fn leaves(node: &EinsumNode) -> usize {
+        match node {
+            EinsumNode::Leaf { .. } => 1,
+            EinsumNode::Contract { args } => args.iter().map(leaves).sum(),
+        }
+    }
+    let min_depth = leaves(node).next_power_of_two().trailing_zeros();
+    (min_depth > 0).then(|| depth(node) as f64 / min_depth as f64)
+}
+
+/// The tree as nested parentheses over input tensor indices, e.g.
+/// `((T0 T1) (T2 T3))`, children in argument (left, right) order.
+pub fn parenthesized(node: &EinsumNode) -> String {
+    match node {
+        EinsumNode::Leaf { tensor_index, .. } => format!("T{tensor_index}"),
+        EinsumNode::Contract { args } => {
+            let parts: Vec<String> = args.iter().map(parenthesized).collect();
+            format!("({})", parts.join(" "))
+        }
+    }
+}
+
+/// Most intermediates alive at once when the tree is evaluated depth-first,
+/// children left to right: finished sibling results are held while later
+/// siblings are evaluated. Input tensors are not counted.
+pub fn width(node: &EinsumNode) -> usize {
+    match node {
+        EinsumNode::Leaf { .. } => 0,
+        EinsumNode::Contract { args } => {
+            let mut held = 0;
+            let mut peak = 1;
+            for arg in args {
+                peak = peak.max(held + width(arg));
+                if matches!(arg, EinsumNode::Contract { .. }) {
+                    held += 1;
+                }
+            }
+            peak
+        }
+    }
+}
+
+/// Canonical string for `node`, recording each `Contract` key in `seen`.
+fn key(node: &EinsumNode, seen: &mut HashMap<String, usize>) -> String {
+    match node {
+        EinsumNode::Leaf { ids, tensor_index } => {
+            format!("{tensor_index}:{}", ids.iter().collect::<String>())
+        }
+        EinsumNode::Contract { args } => {
+            let parts: Vec<String> = args.iter().map(|a| key(a, seen)).collect();
+            let k = format!("({})", parts.join(" "));
+            *seen.entry(k.clone()).or_insert(0) += 1;
+            k
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaf(ids: &str, tensor_index: usize) -> EinsumNode {
+        EinsumNode::Leaf {
+            ids: ids.chars().collect(),
+            tensor_index,
+        }
+    }
+
+    fn contract(args: Vec<EinsumNode>) -> EinsumNode {
+        EinsumNode::Contract { args }
+    }
+
+    /// The original `Vec::remove` implementation, kept as the reference for
+    /// the slot-based one.
+    fn build_by_removal(input_indices: &[Vec<char>], path: &[[usize; 2]]) -> EinsumNode {
+        let mut nodes: Vec<EinsumNode> = input_indices
+            .iter()
+            .enumerate()
+            .map(|(i, ids)| leaf(&ids.iter().collect::<String>(), i))
+            .collect();
+        for &pair in path {
+            let (i, j) = (pair[0].min(pair[1]), pair[0].max(pair[1]));
+            let node_j = nodes.remove(j);
+            let node_i = nodes.remove(i);
+            nodes.push(contract(vec![node_i, node_j]));
+        }
+        assert_eq!(nodes.len(), 1);
+        nodes.pop().unwrap()
+    }
+
+    #[test]
+    fn lower_list_position_is_the_left_operand() {
+        let inputs: Vec<Vec<char>> = ["ab", "bc", "cd"]
+            .iter()
+            .map(|s| s.chars().collect())
+            .collect();
+        assert_eq!(
+            build_contraction_tree(&inputs, &[[2, 0], [1, 0]]),
+            build_contraction_tree(&inputs, &[[0, 2], [0, 1]]),
+        );
+        // T1 stays in front of the (T0 T2) intermediate appended after it.
+        let expected = contract(vec![
+            leaf("bc", 1),
+            contract(vec![leaf("ab", 0), leaf("cd", 2)]),
+        ]);
+        assert_eq!(build_contraction_tree(&inputs, &[[2, 0], [1, 0]]), expected);
+    }
+
+    #[test]
+    fn parentheses_follow_the_pairing() {
+        let inputs: Vec<Vec<char>> = ["ab", "bc", "cd", "de"]
+            .iter()
+            .map(|s| s.chars().collect())
+            .collect();
+        let balanced = build_contraction_tree(&inputs, &[[0, 1], [0, 1], [0, 1]]);
+        assert_eq!(parenthesized(&balanced), "((T0 T1) (T2 T3))");
+        let chain = build_contraction_tree(&inputs, &[[0, 1], [0, 2], [0, 1]]);
+        assert_eq!(parenthesized(&chain), "(T3 (T2 (T0 T1)))");
+        assert_eq!(parenthesized(&leaf("ab", 0)), "T0");
+    }
+
+    #[test]
+    fn step_levels_count_from_the_root() {
+        // (T0 T1) and (T2 T3) both feed the root.
+        assert_eq!(step_levels(4, &[[0, 1], [0, 1], [0, 1]]), vec![1, 1, 0]);
+        // ((T0 T1) T2) T3: a chain, deepest first.
+        assert_eq!(step_levels(4, &[[0, 1], [0, 2], [0, 1]]), vec![2, 1, 0]);
+        assert!(step_levels(1, &[]).is_empty());
+    }
+
+    #[test]
+    fn slot_tree_matches_removal_tree() {
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = |bound: usize| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % bound as u64) as usize
+        };
+        for n in [1, 2, 3, 5, 8, 33, 100, 257] {
+            let inputs: Vec<Vec<char>> = (0..n)
+                .map(|k| vec![char::from_u32(0x100 + k as u32).unwrap()])
+                .collect();
+            let mut paths: Vec<Vec<[usize; 2]>> = vec![
+                vec![[0, 1]; n - 1],
+                (0..n - 1).map(|s| [n - s - 2, n - s - 1]).collect(),
+                (0..n - 1).map(|s| [n - s - 1, 0]).collect(),
+            ];
+            for _ in 0..5 {
+                paths.push(
+                    (0..n - 1)
+                        .map(|s| {
+                            let count = n - s;
+                            let i = next(count);
+                            let j = (i + 1 + next(count - 1)) % count;
+                            [i, j]
+                        })
+                        .collect(),
+                );
+            }
+            for path in &paths {
+                assert_eq!(
+                    build_contraction_tree(&inputs, path),
+                    build_by_removal(&inputs, path),
+                    "n = {n}, path = {path:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn path_trees_share_nothing() {
+        let tree = contract(vec![
+            contract(vec![leaf("ab", 0), leaf("bc", 1)]),
+            contract(vec![leaf("ab", 2), leaf("bc", 3)]),
+        ]);
+        assert_eq!(shared_subtrees(&tree), 0);
+    }
+
+    #[test]
+    fn depth_and_width_of_chain_and_balanced_trees() {
+        // ((T0 T1) T2) T3: deep and narrow.
+        let chain = contract(vec![
+            contract(vec![
+                contract(vec![leaf("a", 0), leaf("a", 1)]),
+                leaf("a", 2),
+            ]),
+            leaf("a", 3),
+        ]);
+        assert_eq!((depth(&chain), width(&chain)), (3, 1));
+        // (T0 T1) (T2 T3): the left pair is held while the right is built.
+        let balanced = contract(vec![
+            contract(vec![leaf("a", 0), leaf("a", 1)]),
+            contract(vec![leaf("a", 2), leaf("a", 3)]),
+        ]);
+        assert_eq!((depth(&balanced), width(&balanced)), (2, 2));
+        assert_eq!((depth(&leaf("a", 0)), width(&leaf("a", 0))), (0, 0));
+        assert_eq!(balance(&chain), Some(1.5));
+        assert_eq!(balance(&balanced), Some(1.0));
+        assert_eq!(balance(&leaf("a", 0)), None);
+    }
+
+    #[test]
+    fn repeated_subtree_is_counted_once_per_repeat() {
+        let pair = || contract(vec![leaf("ab", 0), leaf("bc", 1)]);
+        let tree = contract(vec![pair(), pair(), pair()]);
+        assert_eq!(shared_subtrees(&tree), 2);
+    }
+}
@@ -0,0 +1,323 @@
+//! `validate`: check every instance of a dataset without timing anything.
+//!
+//! Each file gets the checks that would skip it at load time (see
+//! [`crate::load_problems`]) plus the structural ones a dataset generator can
+//! get wrong without the runner noticing: format and shape consistency, the
+//! row-major to column-major conversion, every path replaying to a single
+//! tensor, and the stored `log10_flops`/`log2_size` against the costs
+//! recomputed from the path. Every problem is reported, not only the first.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::{
+    file_stem, instance_paths, load_problems, parse_format_string, path, read_instance_file,
+    BenchmarkInstance, STRATEGIES,
+};
+
+pub const USAGE: &str = "\
+Usage: strided-rs-benchmark-suite validate [--data-dir <PATH>]
+
+Checks every instance file in the data directory without running anything:
+load-time checks, format/shape consistency, the column-major conversion,
+path validity, and stored vs recomputed path costs. Prints PASS or FAIL per
+instance with every problem found, and exits with status 1 if any fails.
+
+Options:
+  --data-dir <PATH>    Dataset directory (default: data/instances)
+  -h, --help           Print this help
+";
+
+/// How far a stored cost may be from the recomputed one: the dataset
+/// rounds both to four decimals.
+const COST_TOLERANCE: f64 = 1e-3;
+
+/// `log10` FLOPs of a simulated path in opt_einsum's convention, which the
+/// einsum_benchmark instances use: a step that sums a label out costs two
+/// FLOPs per multiply-add, one that doesn't costs one. [`path::cost`]
+/// counts multiply-adds, as `gen` and the binary instances do.
+fn opt_einsum_log10_flops(steps: &[path::PathStep]) -> f64 {
+    let flops: f64 = steps
+        .iter()
+        .map(|s| {
+            let sums = s
+                .left
+                .iter()
+                .chain(&s.right)
+                .any(|id| !s.output.contains(id));
+            if sums {
+                2.0 * s.flops
+            } else {
+                s.flops
+            }
+        })
+        .sum();
+    flops.max(1.0).log10()
+}
+
+/// The data directory given by `--data-dir`, or the repository's own.
+pub fn parse(args: &[String]) -> Result<PathBuf, String> {
+    let mut data_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("data/instances");
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.split_once('=') {
+            Some(("--data-dir", dir)) => data_dir = PathBuf::from(dir),
+            _ if arg == "--data-dir" => {
+                data_dir = PathBuf::from(args.next().ok_or("--data-dir requires a value")?);
+            }
+            _ if arg == "-h" || arg == "--help" => {
+                print!("{USAGE}");
+                std::process::exit(0);
+            }
+            _ => return Err(format!("validate: unknown argument {arg:?}")),
+        }
+    }
+    Ok(data_dir)
+}
+
+/// `labels` reversed, as the column-major conversion turns each operand.
+fn reversed(labels: &str) -> String {
+    labels.chars().rev().collect()
+}
+
+/// `format` with its labels renamed in order of first appearance, so two
+/// format strings writing the same contraction with other letters compare
+/// equal.
+fn canonical(format: &str) -> Vec<Option<usize>> {
+    let mut seen: Vec<char> = Vec::new();
+    format
+        .chars()
+        .map(|c| {
+            if c == ',' || c == '-' || c == '>' {
+                return None;
+            }
+            Some(seen.iter().position(|&x| x == c).unwrap_or_else(|| {
+                seen.push(c);
+                seen.len() - 1
+            }))
+        })
+        .collect()
+}
+
+/// Problems with the format string, the shapes and their column-major
+/// conversion, and with each path the instance runs.
+pub fn structural_problems(instance: &BenchmarkInstance) -> Vec<String> {
+    let mut problems = Vec::new();
+    if !instance.format_string_colmajor.contains("->") {
+        // Reported by `load_problems`; nothing below can be checked.
+        return problems;
+    }
+    let (input_indices, output_indices) = parse_format_string(&instance.format_string_colmajor);
+    if input_indices.len() != instance.num_tensors {
+        problems.push(format!(
+            "num_tensors is {} but the format string has {} operands",
+            instance.num_tensors,
+            input_indices.len()
+        ));
+    }
+    if !matches!(instance.dtype.as_str(), "float64" | "complex128") {
+        problems.push(format!("unsupported dtype {:?}", instance.dtype));
+    }
+    for (k, id) in output_indices.iter().enumerate() {
+        if output_indices[..k].contains(id) {
+            problems.push(format!("output label '{id}' is repeated"));
+        } else if !input_indices.iter().any(|ids| ids.contains(id)) {
+            problems.push(format!("output label '{id}' is in no operand"));
+        }
+    }
+
+    match (&instance.format_string, &instance.shapes) {
+        (Some(format), Some(shapes)) => {
+            let expected = match format.split_once("->") {
+                Some((row_inputs, row_output)) => Some(format!(
+                    "{}->{}",
+                    row_inputs
+                        .split(',')
+                        .map(reversed)
+                        .collect::<Vec<_>>()
+                        .join(","),
+                    reversed(row_output)
+                )),
+                None => {
+                    problems.push("format_string must contain '->'".into());
+                    None
+                }
+            };
+            if let Some(expected) =
+                expected.filter(|e| canonical(e) != canonical(&instance.format_string_colmajor))
+            {
+                problems.push(format!(
+                    "format_string_colmajor is not format_string with each operand reversed \
+                     (expected {expected})"
+                ));
+            }
+            let reversed_shapes: Vec<Vec<usize>> = shapes
+                .iter()
+                .map(|shape| shape.iter().rev().copied().collect())
+                .collect();
+            if reversed_shapes != instance.shapes_colmajor {
+                problems
+                    .push("shapes_colmajor is not shapes with each tensor's dims reversed".into());
+            }
+        }
+        (None, None) => {}
+        _ => problems.push("format_string and shapes must be given together".into()),
+    }
+
+    let dims = match path::dim_map(&input_indices, &instance.shapes_colmajor) {
+        Ok(dims) => dims,
+        Err(e) => {
+            problems.push(e);
+            return problems;
+        }
+    };
+    for (name, get_path) in STRATEGIES {
+        let path_meta = get_path(&instance.paths);
+        if instance.skips(name) || path_meta.is_missing() {
+            continue;
+        }
+        problems.extend(
+            path_problems(&input_indices, &output_indices, &dims, path_meta)
+                .into_iter()
+                .map(|e| format!("paths.{name}: {e}")),
+        );
+    }
+    problems
+}
+
+/// Whether `path_meta`'s path replays, and whether its stored costs match.
+fn path_problems(
+    input_indices: &[Vec<char>],
+    output_indices: &[char],
+    dims: &HashMap<char, usize>,
+    path_meta: &crate::PathMeta,
+) -> Vec<String> {
+    let steps = match path::simulate(input_indices, output_indices, dims, &path_meta.path) {
+        Ok(steps) => steps,
+        Err(e) => return vec![e],
+    };
+    let (multiply_adds, log2_size) = path::cost(&steps);
+    let opt_einsum = opt_einsum_log10_flops(&steps);
+    let near = |a: f64, b: f64| (a - b).abs() <= COST_TOLERANCE;
+    let mut problems = Vec::new();
+    let stored = path_meta.log10_flops;
+    if !near(stored, multiply_adds) && !near(stored, opt_einsum) {
+        problems.push(format!(
+            "stored log10_flops {stored:.4}, recomputed {multiply_adds:.4} \
+             (multiply-adds) or {opt_einsum:.4} (opt_einsum)"
+        ));
+    }
+    if !near(path_meta.log2_size, log2_size) {
+        problems.push(format!(
+            "stored log2_size {:.4}, recomputed {log2_size:.4}",
+            path_meta.log2_size
+        ));
+    }
+    problems
+}
+
+/// Check every file in `data_dir` and print the report; `false` when any
+/// instance failed.
+pub fn run(data_dir: &Path) -> bool {
+    let paths = instance_paths(data_dir);
+    println!(
+        "Validating {} instance files in {}",
+        paths.len(),
+        data_dir.display()
+    );
+    println!();
+    let mut names: HashMap<String, PathBuf> = HashMap::new();
+    let mut failed = Vec::new();
+    for file in &paths {
+        let stem = file_stem(file);
+        let (name, problems) = match read_instance_file(file)
+            .map_err(|e| format!("read failed: {e}"))
+            .and_then(|json| {
+                serde_json::from_str::<BenchmarkInstance>(&json)
+                    .map_err(|e| format!("parse failed: {e}"))
+            }) {
+            Ok(instance) => {
+                let mut problems = load_problems(&instance);
+                problems.extend(structural_problems(&instance));
+                problems.extend(instance.output_rank_mismatch());
+                if instance.name != stem {
+                    problems.push(format!(
+                        "name {:?} differs from the file name",
+                        instance.name
+                    ));
+                }
+                if let Some(other) = names.insert(instance.name.clone(), file.clone()) {
+                    problems.push(format!("name also used by {}", other.display()));
+                }
+                (instance.name, problems)
+            }
+            Err(e) => (stem.to_string(), vec![e]),
+        };
+        if problems.is_empty() {
+            println!("PASS {name}");
+        } else {
+            println!("FAIL {name}");
+            problems.iter().for_each(|p| println!("  - {p}"));
+            failed.push(name);
+        }
+    }
+    println!();
+    println!(
+        "Validation: {} passed, {} failed",
+        paths.len() - failed.len(),
+        failed.len()
+    );
+    failed.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate;
+
+    fn matmul() -> BenchmarkInstance {
+        generate::build_instance(&generate::GenOptions {
+            format: "ij,jk->ik".into(),
+            dims: [('i', 2), ('j', 3), ('k', 4)].into(),
+            dtype: "float64".into(),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn consistent_instance_has_no_problems() {
+        let mut instance = matmul();
+        assert_eq!(structural_problems(&instance), Vec::<String>::new());
+        instance.format_string = Some("ji,kj->ki".into());
+        instance.shapes = Some(vec![vec![3, 2], vec![4, 3]]);
+        assert_eq!(structural_problems(&instance), Vec::<String>::new());
+        // The same contraction in other letters.
+        instance.format_string = Some("ba,cb->ca".into());
+        assert_eq!(structural_problems(&instance), Vec::<String>::new());
+        // 24 multiply-adds summing out j: 48 FLOPs for opt_einsum.
+        instance.paths.opt_flops.log10_flops = 48f64.log10();
+        assert_eq!(structural_problems(&instance), Vec::<String>::new());
+    }
+
+    #[test]
+    fn every_structural_problem_is_reported() {
+        let mut instance = matmul();
+        instance.num_tensors = 3;
+        instance.format_string = Some("ij,jk->ik".into());
+        instance.shapes = Some(vec![vec![2, 3], vec![3, 4]]);
+        instance.paths.opt_flops.log10_flops += 1.0;
+        instance.paths.opt_size.path = vec![[0, 0]];
+        let problems = structural_problems(&instance);
+        assert_eq!(problems.len(), 5, "{problems:#?}");
+        assert!(problems[0].starts_with("num_tensors is 3"));
+        assert!(problems[1].contains("(expected ji,kj->ki)"));
+        assert!(problems[2].starts_with("shapes_colmajor"));
+        assert!(problems[3].starts_with("paths.opt_flops: stored log10_flops"));
+        assert!(problems[4].starts_with("paths.opt_size: step 0: invalid pair"));
+
+        instance.format_string_colmajor = "ij,jk->iz".into();
+        let problems = structural_problems(&instance);
+        assert!(problems.contains(&"output label 'z' is in no operand".to_string()));
+    }
+}